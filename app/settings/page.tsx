@@ -2,6 +2,7 @@
 import { Monitor, Moon, Store, Sun } from 'lucide-react';
 import { useTheme } from 'next-themes';
 
+import ResolverSettingsForm from '@/components/resolver-settings';
 import {
   Accordion,
   AccordionContent,
@@ -18,6 +19,7 @@ import {
   SelectTrigger,
   SelectValue,
 } from '@/components/ui/select';
+import VerifySettingsForm from '@/components/verify-settings';
 
 export default function SettingsPage() {
   const { theme, setTheme } = useTheme();
@@ -68,6 +70,27 @@ export default function SettingsPage() {
             </div>
           </AccordionContent>
         </AccordionItem>
+        <AccordionItem value="resolver">
+          <AccordionTrigger>Expired Links</AccordionTrigger>
+          <AccordionContent className="flex flex-col gap-4">
+            <p className="text-muted-foreground text-sm">
+              Presigned and tokenized links stop working after a while. When one is refused, Ferrix
+              can ask a resolver for a fresh link and continue where it left off, as long as the
+              new link serves the same file.
+            </p>
+            <ResolverSettingsForm />
+          </AccordionContent>
+        </AccordionItem>
+        <AccordionItem value="verification">
+          <AccordionTrigger>Verification</AccordionTrigger>
+          <AccordionContent className="flex flex-col gap-4">
+            <p className="text-muted-foreground text-sm">
+              Finished downloads are checked against the checksum given when they were added. Ferrix
+              can also look for one the server publishes beside the file.
+            </p>
+            <VerifySettingsForm />
+          </AccordionContent>
+        </AccordionItem>
         <AccordionItem value="plugins">
           <AccordionTrigger>
             <div className="flex items-center gap-2">
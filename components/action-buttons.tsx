@@ -2,14 +2,17 @@
 
 import { invoke } from '@tauri-apps/api/core';
 import { revealItemInDir } from '@tauri-apps/plugin-opener';
-import { Pause, Play, X, Folder, RotateCw } from 'lucide-react';
+import { Pause, Play, X, Folder, RotateCw, Link } from 'lucide-react';
 import React, { useCallback, useState } from 'react';
 
 import { RemoveDownloadDialog } from './confirm-modal';
 import { useDownloads } from './download-context';
+import { RefreshUrlDialog } from './refresh-url-dialog';
 import { Status } from './types';
 import { Button } from './ui/button';
 
+import type { DownloadType } from './types';
+
 const buttonClassName = 'h-9 w-9 font-medium transition-all duration-200';
 
 interface ActionButtonsProps {
@@ -18,6 +21,7 @@ interface ActionButtonsProps {
   filePath: string;
   filename: string;
   fileExist: boolean;
+  kind: DownloadType['kind'];
 }
 
 export default function ActionButtons({
@@ -26,24 +30,55 @@ export default function ActionButtons({
   filePath,
   fileExist,
   filename,
+  kind,
 }: ActionButtonsProps) {
   const [confirmOpen, setConfirmOpen] = useState(false);
+  const [refreshOpen, setRefreshOpen] = useState(false);
   const { removeDownload } = useDownloads();
 
   const isResumeDisabled = status === Status.Writing;
-  const canToggle = status !== Status.Completed && status !== Status.Failed;
-  const canRemove = status !== Status.Downloading && status !== Status.Writing;
-  const canReveal = status === Status.Completed && fileExist;
+  const isFinished =
+    status === Status.Completed ||
+    status === Status.Verifying ||
+    status === Status.Verified ||
+    status === Status.ChecksumMismatch;
+  const canToggle =
+    !isFinished &&
+    status !== Status.Failed &&
+    status !== Status.RemoteChanged &&
+    status !== Status.UrlExpired;
+  const canRemove =
+    status !== Status.Downloading && status !== Status.Writing && status !== Status.Verifying;
+  const canReveal = isFinished && status !== Status.Verifying && fileExist;
   const canRetry = status === Status.Failed;
+  // A stream's segments come from its playlist, an expired one starts over instead.
+  const canRestart =
+    status === Status.RemoteChanged ||
+    status === Status.ChecksumMismatch ||
+    (status === Status.UrlExpired && kind !== 'file');
+  const canRefreshUrl =
+    (status === Status.UrlExpired || status === Status.Paused) && kind === 'file';
 
   const handleToggleDownload = useCallback(async () => {
-    if (status === Status.Paused || status === Status.Failed) {
-      await invoke('resume_download', { id: downloadId });
-    } else {
-      await invoke('pause_download', { id: downloadId });
+    try {
+      if (status === Status.Paused || status === Status.Failed) {
+        await invoke('resume_download', { id: downloadId });
+      } else {
+        await invoke('pause_download', { id: downloadId });
+      }
+    } catch (e) {
+      console.error('Failed to toggle download:', e);
     }
   }, [status, downloadId]);
 
+  const handleRestart = useCallback(async () => {
+    try {
+      await invoke('restart_download', { id: downloadId });
+    } catch (e) {
+      console.error('Failed to restart download:', e);
+    }
+  }, [downloadId]);
+
   const handleRemoveConfirmed = useCallback(
     async (removeFile?: boolean) => {
       await removeDownload(downloadId, !!removeFile);
@@ -70,6 +105,13 @@ export default function ActionButtons({
         fileExist={fileExist}
       />
 
+      <RefreshUrlDialog
+        open={refreshOpen}
+        onOpenChange={setRefreshOpen}
+        downloadId={downloadId}
+        filename={filename}
+      />
+
       <div className="flex gap-2">
         {canToggle && (
           <Button
@@ -102,6 +144,32 @@ export default function ActionButtons({
           </Button>
         )}
 
+        {canRestart && (
+          <Button
+            onClick={handleRestart}
+            variant="outline"
+            size="sm"
+            className={buttonClassName}
+            aria-label="Restart"
+            title="Restart from the beginning"
+          >
+            <RotateCw className="h-4 w-4" />
+          </Button>
+        )}
+
+        {canRefreshUrl && (
+          <Button
+            onClick={() => setRefreshOpen(true)}
+            variant="outline"
+            size="sm"
+            className={buttonClassName}
+            aria-label="Refresh link"
+            title="Refresh link"
+          >
+            <Link className="h-4 w-4" />
+          </Button>
+        )}
+
         {canRemove && (
           <Button
             onClick={() => setConfirmOpen(true)}
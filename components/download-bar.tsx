@@ -2,7 +2,8 @@
 
 import { zodResolver } from '@hookform/resolvers/zod';
 import { invoke } from '@tauri-apps/api/core';
-import { DownloadIcon, MoreHorizontal } from 'lucide-react';
+import { open } from '@tauri-apps/plugin-dialog';
+import { DownloadIcon, FileUp, MoreHorizontal } from 'lucide-react';
 import { useState } from 'react';
 import { useForm } from 'react-hook-form';
 import { toast } from 'sonner';
@@ -15,12 +16,14 @@ interface DownloadBarProps {
 
 import { Button } from '@/components/ui/button';
 import { Input } from '@/components/ui/input';
-import { cn } from '@/lib/utils';
+import { cn, isMetalink, isStream } from '@/lib/utils';
 import { urlFormSchema } from '@/lib/validation';
 
+import { StreamVariantDialog } from './stream-variant-dialog';
 import { Form, FormControl, FormField, FormItem } from './ui/form';
 import { Loading } from './ui/loading';
 
+import type { PendingStream } from './stream-variant-dialog';
 import type React from 'react';
 import type { z } from 'zod';
 
@@ -28,6 +31,7 @@ type UrlFormData = z.infer<typeof urlFormSchema>;
 
 export default function DownloadBar({ setIsModalOpen, setUrl, url }: DownloadBarProps) {
   const [isLoading, setIsLoading] = useState(false);
+  const [stream, setStream] = useState<PendingStream | null>(null);
 
   const form = useForm<UrlFormData>({
     resolver: zodResolver(urlFormSchema),
@@ -42,12 +46,18 @@ export default function DownloadBar({ setIsModalOpen, setUrl, url }: DownloadBar
 
     setIsLoading(true);
     try {
-      await invoke('add_new_download', {
-        url: value.url,
-        options: {
-          chunk_count: 5,
-        },
-      });
+      const options = { chunk_count: 5 };
+
+      if (isStream(value.url)) {
+        setStream({ url: value.url.trim(), options });
+        return;
+      }
+
+      if (isMetalink(value.url)) {
+        await invoke('add_metalink_download', { source: value.url.trim(), options });
+      } else {
+        await invoke('add_new_download', { url: value.url, options });
+      }
       form.setValue('url', '');
       setUrl('');
     } catch (error) {
@@ -57,6 +67,30 @@ export default function DownloadBar({ setIsModalOpen, setUrl, url }: DownloadBar
     }
   };
 
+  const handleStreamAdded = () => {
+    form.setValue('url', '');
+    setUrl('');
+  };
+
+  const handleImportMetalink = async () => {
+    const selected = await open({
+      multiple: false,
+      title: 'Import Metalink',
+      filters: [{ name: 'Metalink', extensions: ['meta4', 'metalink'] }],
+    });
+
+    if (typeof selected !== 'string') return;
+
+    setIsLoading(true);
+    try {
+      await invoke('add_metalink_download', { source: selected, options: { chunk_count: 5 } });
+    } catch (error) {
+      toast.error(`${error}`);
+    } finally {
+      setIsLoading(false);
+    }
+  };
+
   const handleUrlFieldChange = (ev: React.ChangeEvent<HTMLInputElement>) => {
     if (urlError) {
       form.clearErrors('url');
@@ -112,6 +146,15 @@ export default function DownloadBar({ setIsModalOpen, setUrl, url }: DownloadBar
             ? `Showing ${filteredDownloads.length} filtered downloads`
             : ""}
         </p> */}
+      <Button
+        onClick={handleImportMetalink}
+        disabled={isLoading}
+        className="flex items-center gap-1"
+        variant="ghost"
+        title="Import Metalink"
+      >
+        <FileUp className="h-4 w-4" />
+      </Button>
       <Button
         onClick={() => setIsModalOpen(true)}
         className="flex items-center gap-1"
@@ -122,6 +165,7 @@ export default function DownloadBar({ setIsModalOpen, setUrl, url }: DownloadBar
       >
         <MoreHorizontal className="h-4 w-4" />
       </Button>
+      <StreamVariantDialog stream={stream} setStream={setStream} onAdded={handleStreamAdded} />
     </div>
   );
 }
@@ -10,7 +10,7 @@ import StatusAndSpeed from './status-and-speed';
 import { type DownloadType, Status } from './types';
 
 function DownloadItem({ download }: { download: DownloadType }) {
-  const isDone = download.status === Status.Completed;
+  const isDone = download.status === Status.Completed || download.status === Status.Verified;
 
   return (
     <Card className="group border-border/50 from-card to-card/60 border bg-gradient-to-br shadow-sm transition hover:shadow-md">
@@ -26,6 +26,12 @@ function DownloadItem({ download }: { download: DownloadType }) {
               </h3>
               <p className="text-muted-foreground text-[11px]">
                 {(download.total_bytes / (1024 * 1024)).toFixed(1)} MB
+                {download.status === Status.Verified && download.checksum && (
+                  <span className="text-emerald-500" title={download.checksum.digest}>
+                    {' '}
+                    · {download.checksum.algorithm.toUpperCase()} verified
+                  </span>
+                )}
               </p>
             </div>
           </div>
@@ -36,6 +42,7 @@ function DownloadItem({ download }: { download: DownloadType }) {
             downloadId={download.id}
             status={download.status}
             filename={download.file_name}
+            kind={download.kind}
           />
         </div>
 
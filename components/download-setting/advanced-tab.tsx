@@ -12,8 +12,10 @@ import { FormControl, FormField, FormItem, FormLabel } from '../ui/form';
 import { Input } from '../ui/input';
 
 import AuthField from './auth-field';
+import ChecksumField from './checksum-field';
 import FormMessage from './form-message';
 import KeyValuePairField from './key-value-pair-field';
+import MirrorsField from './mirrors-field';
 import PositiveNumberField from './positive-number-field';
 import ProxyField from './proxy-field';
 
@@ -106,6 +108,21 @@ export default function AdvancedTab({ handleKeyPress }: AdvancedTabProps) {
       <AuthField />
       <KeyValuePairField name="headers" label="Headers" handleKeyPress={handleKeyPress} />
       <KeyValuePairField name="cookies" label="Cookies" handleKeyPress={handleKeyPress} />
+      <FormField
+        control={form.control}
+        name="referer"
+        render={({ field }) => (
+          <FormItem className="flex-col gap-1">
+            <FormLabel htmlFor="referer">Referer</FormLabel>
+            <FormControl>
+              <Input id="referer" {...field} placeholder="Page the link was copied from" />
+            </FormControl>
+            <FormMessage />
+          </FormItem>
+        )}
+      />
+      <MirrorsField handleKeyPress={handleKeyPress} />
+      <ChecksumField />
       {/* <SpeedLimitField form={form} /> */}
       <PositiveNumberField
         max={30}
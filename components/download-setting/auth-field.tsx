@@ -38,6 +38,7 @@ export default function AuthField() {
                 <SelectItem value="ApiKeyHeader">API Key (Header)</SelectItem>
                 <SelectItem value="ApiKeyQuery">API Key (Query)</SelectItem>
                 <SelectItem value="Cookie">Cookie Auth</SelectItem>
+                <SelectItem value="SshKey">SSH Key (SFTP)</SelectItem>
               </SelectContent>
             </Select>
           </FormItem>
@@ -186,6 +187,50 @@ export default function AuthField() {
         </div>
       )}
 
+      {authType === 'SshKey' && (
+        <div className="space-y-2">
+          <FormField
+            control={control}
+            name="auth.username"
+            render={({ field }) => (
+              <FormItem>
+                <FormLabel>Username</FormLabel>
+                <FormControl>
+                  <Input placeholder="username" {...field} />
+                </FormControl>
+                <FormMessage />
+              </FormItem>
+            )}
+          />
+          <FormField
+            control={control}
+            name="auth.private_key"
+            render={({ field }) => (
+              <FormItem>
+                <FormLabel>Private Key File</FormLabel>
+                <FormControl>
+                  <Input placeholder="Empty to use the SSH agent" {...field} />
+                </FormControl>
+                <FormMessage />
+              </FormItem>
+            )}
+          />
+          <FormField
+            control={control}
+            name="auth.passphrase"
+            render={({ field }) => (
+              <FormItem>
+                <FormLabel>Passphrase</FormLabel>
+                <FormControl>
+                  <Input type="password" placeholder="Optional" {...field} />
+                </FormControl>
+                <FormMessage />
+              </FormItem>
+            )}
+          />
+        </div>
+      )}
+
       {authType === 'Cookie' && (
         <div>
           <FormField
@@ -56,7 +56,7 @@ export default function BasicTab({ handleKeyPress }: BasicTabProps) {
                 />
               </FormControl>
               <FormDescription>
-                More chunks can improve speed but use more resources (1–16).
+                More chunks can improve speed but use more resources (1–5).
               </FormDescription>
               <FormMessage />
             </FormItem>
@@ -0,0 +1,61 @@
+import { useFormContext } from 'react-hook-form';
+
+import { FormControl, FormField, FormItem, FormLabel } from '@/components/ui/form';
+import { Input } from '@/components/ui/input';
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from '@/components/ui/select';
+
+import FormMessage from './form-message';
+
+export default function ChecksumField() {
+  const { control } = useFormContext();
+
+  return (
+    <div className="space-y-4 rounded-lg border p-2">
+      <FormField
+        control={control}
+        name="checksum.algorithm"
+        render={({ field }) => (
+          <FormItem className="flex items-center justify-between">
+            <FormLabel>Checksum</FormLabel>
+            <Select onValueChange={field.onChange} value={field.value ?? 'sha256'}>
+              <FormControl>
+                <SelectTrigger className="min-w-44">
+                  <SelectValue placeholder="Select algorithm" />
+                </SelectTrigger>
+              </FormControl>
+              <SelectContent>
+                <SelectItem value="sha256">SHA-256</SelectItem>
+                <SelectItem value="sha1">SHA-1</SelectItem>
+                <SelectItem value="md5">MD5</SelectItem>
+                <SelectItem value="blake3">BLAKE3</SelectItem>
+              </SelectContent>
+            </Select>
+          </FormItem>
+        )}
+      />
+      <FormField
+        control={control}
+        name="checksum.digest"
+        render={({ field }) => (
+          <FormItem>
+            <FormControl>
+              <Input
+                placeholder="Expected digest, found next to the file if left empty"
+                className="font-mono"
+                {...field}
+                value={field.value ?? ''}
+              />
+            </FormControl>
+            <FormMessage />
+          </FormItem>
+        )}
+      />
+    </div>
+  );
+}
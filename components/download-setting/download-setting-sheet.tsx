@@ -8,8 +8,10 @@ import { toast } from 'sonner';
 
 import { Button } from '@/components/ui/button';
 import { Form } from '@/components/ui/form';
+import { isMetalink, isStream } from '@/lib/utils';
 import { downloadFormSchema } from '@/lib/validation';
 
+import { StreamVariantDialog } from '../stream-variant-dialog';
 import { Loading } from '../ui/loading';
 import { Sheet, SheetContent, SheetDescription, SheetHeader, SheetTitle } from '../ui/sheet';
 import { Tabs, TabsList, TabsTrigger } from '../ui/tabs';
@@ -17,6 +19,7 @@ import { Tabs, TabsList, TabsTrigger } from '../ui/tabs';
 import AdvancedTab from './advanced-tab';
 import BasicTab from './basic-tab';
 
+import type { PendingStream } from '../stream-variant-dialog';
 import type { z } from 'zod';
 
 export type DownloadFormData = z.infer<typeof downloadFormSchema>;
@@ -38,6 +41,9 @@ const getDefaultFormValues = (url: string): DownloadFormData => ({
   auth: { type: 'None' },
   proxy: { type: 'system' },
   filePath: '',
+  checksum: { algorithm: 'sha256', digest: '' },
+  mirrors: [],
+  referer: '',
 });
 
 export default function DownloadSettingSheet({
@@ -47,6 +53,7 @@ export default function DownloadSettingSheet({
   url,
 }: DownloadSettingSheetProps) {
   const [isLoading, setIsLoading] = useState(false);
+  const [stream, setStream] = useState<PendingStream | null>(null);
   const form = useForm<DownloadFormData>({
     resolver: zodResolver(downloadFormSchema),
     defaultValues: getDefaultFormValues(url),
@@ -59,6 +66,13 @@ export default function DownloadSettingSheet({
     }
   }, [form, open, url]);
 
+  const handleAdded = () => {
+    toast.success('Download added');
+    onOpenChange(false);
+    setUrl('');
+    form.reset(getDefaultFormValues(''));
+  };
+
   const handleSubmit = async (values: DownloadFormData) => {
     if (!values.url?.trim()) {
       form.setError('url', { type: 'manual', message: 'URL is required' });
@@ -86,34 +100,63 @@ export default function DownloadSettingSheet({
 
     setIsLoading(true);
     try {
-      await invoke('add_new_download', {
-        url: values.url.trim(),
-        options: {
-          proxy,
-          ...(values.auth &&
-            values.auth?.type !== 'None' && {
-              auth: { ...values.auth, type: values.auth.type.toLowerCase() },
-            }),
-          headers: Object.keys(headers).length ? headers : undefined,
-          cookies: Object.keys(cookies).length ? cookies : undefined,
-          chunk_count: values.chunk,
-          ...(!!values.filePath && {
-            file_path: values.filePath,
+      const source = values.url.trim();
+      const options = {
+        proxy,
+        ...(values.auth &&
+          values.auth?.type !== 'None' && {
+            auth: { ...values.auth, type: values.auth.type.toLowerCase() },
           }),
-          speed_limit: values.speedLimit || undefined,
-          max_retries: values.maxRetries || undefined,
-          backoff_factor: values.backoffFactor || undefined,
-          timeout_secs: values.timeoutSecs || undefined,
-        },
+        headers: Object.keys(headers).length ? headers : undefined,
+        cookies: Object.keys(cookies).length ? cookies : undefined,
+        chunk_count: values.chunk,
+        ...(!!values.filePath && {
+          file_path: values.filePath,
+        }),
+        speed_limit: values.speedLimit || undefined,
+        max_retries: values.maxRetries || undefined,
+        backoff_factor: values.backoffFactor || undefined,
+        timeout_secs: values.timeoutSecs || undefined,
+        ...(!!values.mirrors?.length && {
+          mirrors: values.mirrors.map(({ url }) => url.trim()),
+        }),
+        ...(!!values.referer?.trim() && { referer: values.referer.trim() }),
+        ...(!!values.checksum?.digest && {
+          checksum: { algorithm: values.checksum.algorithm, digest: values.checksum.digest },
+        }),
+      };
+
+      if (isStream(source)) {
+        setStream({ url: source, options });
+        return;
+      }
+
+      await invoke(isMetalink(source) ? 'add_metalink_download' : 'add_new_download', {
+        ...(isMetalink(source) ? { source } : { url: source }),
+        options,
       });
 
-      toast.success('Download added');
-      onOpenChange(false);
-      setUrl('');
-      form.reset(getDefaultFormValues(''));
+      handleAdded();
     } catch (err: unknown) {
       const error = err as Error;
       console.error('Failed to add download:', err);
+
+      const fingerprint = `${err}`.match(/key fingerprint is (SHA256:\S+)/)?.[1];
+      if (fingerprint) {
+        const url = values.url.trim();
+        toast.error('Unknown SSH host', {
+          description: `Fingerprint ${fingerprint}, trust it only if it is the server's.`,
+          action: {
+            label: 'Trust',
+            onClick: () =>
+              invoke('trust_host_key', { url, fingerprint })
+                .then(() => toast.success('Host trusted, add the download again'))
+                .catch((e) => toast.error(`${e}`)),
+          },
+        });
+        return;
+      }
+
       toast.error('Failed to add download', {
         description:
           typeof error?.message === 'string'
@@ -172,6 +215,7 @@ export default function DownloadSettingSheet({
           </form>
         </Form>
       </SheetContent>
+      <StreamVariantDialog stream={stream} setStream={setStream} onAdded={handleAdded} />
     </Sheet>
   );
 }
@@ -0,0 +1,75 @@
+import { Plus, Trash2 } from 'lucide-react';
+import { useFieldArray, useFormContext } from 'react-hook-form';
+
+import { Button } from '../ui/button';
+import { FormControl, FormField, FormItem } from '../ui/form';
+import { Input } from '../ui/input';
+
+import FormMessage from './form-message';
+
+interface MirrorsFieldProps {
+  handleKeyPress?: (e: React.KeyboardEvent) => void;
+}
+
+export default function MirrorsField({ handleKeyPress }: MirrorsFieldProps) {
+  const { control } = useFormContext();
+  const { fields, append, remove } = useFieldArray({
+    control,
+    name: 'mirrors',
+  });
+
+  return (
+    <div className="space-y-2">
+      <label className="text-sm font-medium">Mirrors</label>
+
+      <div className="space-y-2">
+        {fields.length === 0 && (
+          <div className="text-muted-foreground text-xs">
+            No mirrors added. Chunks are spread over every mirror serving the same file.
+          </div>
+        )}
+
+        {fields.map((f, index) => (
+          <div key={f.id} className="flex items-start gap-2">
+            <FormField
+              control={control}
+              name={`mirrors.${index}.url`}
+              render={({ field }) => (
+                <FormItem className="flex-1 gap-1">
+                  <FormControl>
+                    <Input
+                      {...field}
+                      inputMode="url"
+                      placeholder="https://mirror.example.com/file"
+                      onKeyDown={handleKeyPress}
+                    />
+                  </FormControl>
+                  <FormMessage />
+                </FormItem>
+              )}
+            />
+            <Button
+              type="button"
+              variant="outline"
+              size="icon"
+              onClick={() => remove(index)}
+              aria-label="Remove mirror"
+            >
+              <Trash2 className="h-4 w-4" />
+            </Button>
+          </div>
+        ))}
+
+        <Button
+          type="button"
+          variant="outline"
+          className="w-full"
+          onClick={() => append({ url: '' })}
+        >
+          <Plus className="mr-2 h-4 w-4" />
+          Add Mirror
+        </Button>
+      </div>
+    </div>
+  );
+}
@@ -0,0 +1,71 @@
+import { invoke } from '@tauri-apps/api/core';
+import { useState } from 'react';
+import { toast } from 'sonner';
+
+import { Button } from '@/components/ui/button';
+import {
+  Dialog,
+  DialogContent,
+  DialogDescription,
+  DialogFooter,
+  DialogHeader,
+  DialogTitle,
+} from '@/components/ui/dialog';
+import { Input } from '@/components/ui/input';
+
+export function RefreshUrlDialog({
+  open,
+  onOpenChange,
+  downloadId,
+  filename,
+}: {
+  open: boolean;
+  onOpenChange: (open: boolean) => void;
+  downloadId: number;
+  filename: string;
+}) {
+  const [url, setUrl] = useState('');
+  const [submitting, setSubmitting] = useState(false);
+
+  const handleSubmit = async () => {
+    setSubmitting(true);
+    try {
+      await invoke('refresh_download_url', { id: downloadId, url: url.trim() });
+      setUrl('');
+      onOpenChange(false);
+    } catch (error) {
+      toast.error(`${error}`);
+    } finally {
+      setSubmitting(false);
+    }
+  };
+
+  return (
+    <Dialog open={open} onOpenChange={onOpenChange}>
+      <DialogContent>
+        <DialogHeader>
+          <DialogTitle>Refresh link</DialogTitle>
+          <DialogDescription>
+            Paste a fresh link for <b>{filename}</b>. It has to serve the same file, the download
+            continues where it stopped.
+          </DialogDescription>
+        </DialogHeader>
+
+        <Input
+          placeholder="https://example.com/file.zip"
+          value={url}
+          onChange={(e) => setUrl(e.target.value)}
+        />
+
+        <DialogFooter>
+          <Button variant="outline" onClick={() => onOpenChange(false)}>
+            Cancel
+          </Button>
+          <Button onClick={handleSubmit} disabled={!url.trim() || submitting}>
+            Refresh
+          </Button>
+        </DialogFooter>
+      </DialogContent>
+    </Dialog>
+  );
+}
@@ -0,0 +1,58 @@
+'use client';
+
+import { invoke } from '@tauri-apps/api/core';
+import { useEffect, useState } from 'react';
+import { toast } from 'sonner';
+
+import { Button } from '@/components/ui/button';
+import { Input } from '@/components/ui/input';
+import { Label } from '@/components/ui/label';
+
+interface ResolverSettings {
+  url: string | null;
+}
+
+export default function ResolverSettingsForm() {
+  const [url, setUrl] = useState('');
+  const [isSaving, setIsSaving] = useState(false);
+
+  useEffect(() => {
+    invoke<ResolverSettings>('get_resolver_settings')
+      .then((settings) => setUrl(settings.url ?? ''))
+      .catch((error) => toast.error(`${error}`));
+  }, []);
+
+  const handleSave = async () => {
+    setIsSaving(true);
+    try {
+      await invoke('update_resolver_settings', { settings: { url: url.trim() || null } });
+      toast.success('Resolver saved');
+    } catch (error) {
+      toast.error(`${error}`);
+    } finally {
+      setIsSaving(false);
+    }
+  };
+
+  return (
+    <div className="space-y-2">
+      <Label htmlFor="resolver-url">Resolver URL</Label>
+      <div className="flex gap-2">
+        <Input
+          id="resolver-url"
+          inputMode="url"
+          placeholder="https://resolver.example.com/refresh"
+          value={url}
+          onChange={(ev) => setUrl(ev.target.value)}
+        />
+        <Button onClick={handleSave} disabled={isSaving}>
+          Save
+        </Button>
+      </div>
+      <span className="text-muted-foreground text-xs">
+        Called with the expired link as <code>url</code> and its source page as{' '}
+        <code>referer</code>, it answers with a fresh link as plain text.
+      </span>
+    </div>
+  );
+}
@@ -14,6 +14,14 @@ interface SpeedAndRemaining {
   speed: number;
   remaining_time: number;
   diskSpeed: number;
+  queuedBytes: number;
+  queueCapacity: number;
+}
+
+interface DiskReport {
+  speed: number;
+  queued_bytes: number;
+  queue_capacity: number;
 }
 
 const MAX_POINTS = 90;
@@ -49,7 +57,7 @@ function StatusPill({ status, errorMessage }: { status: Status; errorMessage: st
         cls: 'bg-purple-500/10 text-purple-500 border-purple-500/20',
       },
       [Status.Paused]: {
-        label: 'Paused',
+        label: errorMessage ? `Paused: ${errorMessage}` : 'Paused',
         cls: 'bg-yellow-500/10 text-yellow-600 border-yellow-500/20',
       },
       [Status.Queued]: { label: 'Queued', cls: 'bg-muted text-foreground/70 border-transparent' },
@@ -65,6 +73,30 @@ function StatusPill({ status, errorMessage }: { status: Status; errorMessage: st
         label: `Trying ${errorMessage}`,
         cls: 'bg-red-400/10 text-red-400 border-red-400/20',
       },
+      [Status.RemoteChanged]: {
+        label: 'Remote file changed',
+        cls: 'bg-red-500/10 text-red-500 border-red-500/20',
+      },
+      [Status.Verifying]: {
+        label: 'Verifying checksum',
+        cls: 'bg-purple-500/10 text-purple-500 border-purple-500/20',
+      },
+      [Status.Verified]: {
+        label: 'Verified',
+        cls: 'bg-emerald-500/10 text-emerald-500 border-emerald-500/20',
+      },
+      [Status.ChecksumMismatch]: {
+        label: `Checksum mismatch: ${errorMessage}`,
+        cls: 'bg-red-500/10 text-red-500 border-red-500/20',
+      },
+      [Status.UrlExpired]: {
+        label: errorMessage ? `Link expired: ${errorMessage}` : 'Link expired',
+        cls: 'bg-red-500/10 text-red-500 border-red-500/20',
+      },
+      [Status.Waiting]: {
+        label: `Waiting ${errorMessage}`,
+        cls: 'bg-orange-400/10 text-orange-400 border-orange-400/20',
+      },
     };
   }, [errorMessage]);
 
@@ -97,7 +129,13 @@ function StatusAndSpeed({
 }) {
   const [downloadedBytes, setDownloadedBytes] = useState(initialDownloadedBytes);
   const [wroteBytes, setWroteBytes] = useState(initialDownloadedBytes);
-  const [sr, setSr] = useState<SpeedAndRemaining>({ speed: 0, diskSpeed: 0, remaining_time: 0 });
+  const [sr, setSr] = useState<SpeedAndRemaining>({
+    speed: 0,
+    diskSpeed: 0,
+    remaining_time: 0,
+    queuedBytes: 0,
+    queueCapacity: 0,
+  });
 
   const [series, setSeries] = useState<SpeedPoint[]>([]);
   const tRef = useRef(0);
@@ -124,12 +162,17 @@ function StatusAndSpeed({
       });
     });
 
-    const un3 = listen<number>(`disk_speed_${id}`, (ev) => {
-      const disk = Math.max(0, ev.payload ?? 0);
+    const un3 = listen<DiskReport>(`disk_speed_${id}`, (ev) => {
+      const disk = Math.max(0, ev.payload.speed ?? 0);
       const net = lastNet.current;
       lastDisk.current = disk;
 
-      setSr((prev) => ({ ...prev, diskSpeed: disk }));
+      setSr((prev) => ({
+        ...prev,
+        diskSpeed: disk,
+        queuedBytes: ev.payload.queued_bytes,
+        queueCapacity: ev.payload.queue_capacity,
+      }));
 
       tRef.current += 1;
       setSeries((old) => {
@@ -157,7 +200,10 @@ function StatusAndSpeed({
   }, [initialDownloadedBytes]);
 
   const isWriting = status === Status.Writing;
-  const isDownloading = status === Status.Downloading || status === Status.Trying;
+  // Servers that don't send a length leave total_bytes at 0 until the download completes.
+  const isSizeUnknown = totalBytes === 0;
+  const isDownloading =
+    status === Status.Downloading || status === Status.Trying || status === Status.Waiting;
 
   const progress = useMemo(() => {
     const bytes = isWriting ? wroteBytes : downloadedBytes;
@@ -177,15 +223,22 @@ function StatusAndSpeed({
                 {fmtThroughputKB(sr.speed)}
               </span>
               <span className="text-muted-foreground text-xs">/</span>
-              <span className="inline-flex items-center gap-1 text-xs">
+              <span
+                className="inline-flex items-center gap-1 text-xs"
+                title={`${(sr.queuedBytes / (1024 * 1024)).toFixed(1)} of ${(sr.queueCapacity / (1024 * 1024)).toFixed(0)} MB waiting for the disk`}
+              >
                 <HardDrive className="h-3 w-3" />
                 {fmtThroughputKB(sr.diskSpeed)}
               </span>
-              <span className="text-muted-foreground text-xs">•</span>
-              <span className="inline-flex items-center gap-1 text-xs">
-                <Clock className="h-3 w-3" />
-                {fmtRemaining(sr.remaining_time)}
-              </span>
+              {!isSizeUnknown && (
+                <>
+                  <span className="text-muted-foreground text-xs">•</span>
+                  <span className="inline-flex items-center gap-1 text-xs">
+                    <Clock className="h-3 w-3" />
+                    {fmtRemaining(sr.remaining_time)}
+                  </span>
+                </>
+              )}
             </>
           )}
           {isWriting && <span className="ml-2 text-xs text-purple-500">Writing to disk…</span>}
@@ -195,9 +248,9 @@ function StatusAndSpeed({
         </span>
       </div>
 
-      <div className="relative">
+      <div className={clsx('relative', isSizeUnknown && isDownloading && 'animate-pulse')}>
         <Progress
-          value={progress}
+          value={isSizeUnknown ? (isDownloading ? 100 : 0) : progress}
           className={clsx(
             'bg-muted/30 h-2 overflow-hidden',
             '[&>div]:transition-all [&>div]:duration-300',
@@ -9,14 +9,19 @@ function getStatusColor(status: Status) {
     case Status.Downloading:
       return 'bg-blue-500';
     case Status.Completed:
+    case Status.Verified:
       return 'bg-green-500';
     case Status.Failed:
+    case Status.RemoteChanged:
+    case Status.ChecksumMismatch:
+    case Status.UrlExpired:
       return 'bg-red-500';
     case Status.Paused:
       return 'bg-yellow-500';
     case Status.Queued:
       return 'bg-gray-500';
     case Status.Writing:
+    case Status.Verifying:
       return 'bg-purple-500';
     default:
       return 'bg-gray-500';
@@ -0,0 +1,136 @@
+import { invoke } from '@tauri-apps/api/core';
+import { useEffect, useState } from 'react';
+import { toast } from 'sonner';
+
+import { Button } from '@/components/ui/button';
+import {
+  Dialog,
+  DialogContent,
+  DialogDescription,
+  DialogFooter,
+  DialogHeader,
+  DialogTitle,
+} from '@/components/ui/dialog';
+import { cn } from '@/lib/utils';
+
+import { Loading } from './ui/loading';
+
+interface StreamVariant {
+  id: string;
+  bandwidth?: number;
+  resolution?: string;
+  codecs?: string;
+  mime_type?: string;
+}
+
+interface StreamManifest {
+  kind: 'hls' | 'dash';
+  variants: StreamVariant[];
+}
+
+export interface PendingStream {
+  url: string;
+  options: Record<string, unknown>;
+}
+
+const formatBandwidth = (bandwidth?: number) =>
+  bandwidth ? `${(bandwidth / 1_000_000).toFixed(2)} Mbps` : 'Unknown bitrate';
+
+export function StreamVariantDialog({
+  stream,
+  setStream,
+  onAdded,
+}: {
+  stream: PendingStream | null;
+  setStream: (stream: PendingStream | null) => void;
+  onAdded?: () => void;
+}) {
+  const [manifest, setManifest] = useState<StreamManifest>();
+  const [selected, setSelected] = useState<string>();
+  const [submitting, setSubmitting] = useState(false);
+
+  useEffect(() => {
+    setManifest(undefined);
+    if (!stream) return;
+
+    invoke<StreamManifest>('inspect_stream', stream)
+      .then((manifest) => {
+        setManifest(manifest);
+        setSelected(manifest.variants[0]?.id);
+      })
+      .catch((error) => {
+        toast.error(`${error}`);
+        setStream(null);
+      });
+  }, [stream, setStream]);
+
+  const handleSubmit = async () => {
+    if (!stream || !manifest || !selected) return;
+
+    setSubmitting(true);
+    try {
+      await invoke('add_stream_download', {
+        ...stream,
+        kind: manifest.kind,
+        variantId: selected,
+      });
+      setStream(null);
+      onAdded?.();
+    } catch (error) {
+      toast.error(`${error}`);
+    } finally {
+      setSubmitting(false);
+    }
+  };
+
+  return (
+    <Dialog open={!!stream} onOpenChange={(v) => !v && !submitting && setStream(null)}>
+      <DialogContent>
+        <DialogHeader>
+          <DialogTitle>Pick a quality</DialogTitle>
+          <DialogDescription>
+            The stream is downloaded segment by segment and joined into one file.
+          </DialogDescription>
+        </DialogHeader>
+
+        {manifest ? (
+          <div className="flex max-h-80 flex-col gap-1 overflow-y-auto">
+            {manifest.variants.map((variant) => (
+              <button
+                key={variant.id}
+                type="button"
+                onClick={() => setSelected(variant.id)}
+                className={cn('rounded-md border px-3 py-2 text-left text-sm', {
+                  'border-blue-500 bg-blue-500/10': selected === variant.id,
+                })}
+              >
+                <div className="font-medium">
+                  {variant.resolution ?? variant.mime_type ?? manifest.kind.toUpperCase()}
+                  <span className="text-muted-foreground ml-2 font-normal">
+                    {formatBandwidth(variant.bandwidth)}
+                  </span>
+                </div>
+                {variant.codecs && (
+                  <div className="text-muted-foreground truncate text-xs">{variant.codecs}</div>
+                )}
+              </button>
+            ))}
+          </div>
+        ) : (
+          <div className="flex justify-center py-6">
+            <Loading />
+          </div>
+        )}
+
+        <DialogFooter>
+          <Button variant="outline" onClick={() => setStream(null)} disabled={submitting}>
+            Cancel
+          </Button>
+          <Button onClick={handleSubmit} disabled={!selected || submitting}>
+            Download
+          </Button>
+        </DialogFooter>
+      </DialogContent>
+    </Dialog>
+  );
+}
@@ -13,6 +13,19 @@ export interface DownloadType {
   file_exist: boolean;
   modified_at: string;
   error_message: string;
+  speed_limit: number | null;
+  etag: string | null;
+  last_modified: string | null;
+  checksum: Checksum | null;
+  referer: string | null;
+  kind: 'file' | 'hls' | 'dash';
+}
+
+export type ChecksumAlgorithm = 'sha256' | 'sha1' | 'md5' | 'blake3';
+
+export interface Checksum {
+  algorithm: ChecksumAlgorithm;
+  digest: string;
 }
 
 export enum ContentType {
@@ -35,4 +48,10 @@ export enum Status {
   Failed = 'failed',
   Writing = 'writing',
   Trying = 'trying',
+  Waiting = 'waiting',
+  RemoteChanged = 'remote_changed',
+  Verifying = 'verifying',
+  Verified = 'verified',
+  ChecksumMismatch = 'checksum_mismatch',
+  UrlExpired = 'url_expired',
 }
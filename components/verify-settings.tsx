@@ -0,0 +1,49 @@
+'use client';
+
+import { invoke } from '@tauri-apps/api/core';
+import { useEffect, useState } from 'react';
+import { toast } from 'sonner';
+
+import { Label } from '@/components/ui/label';
+import { Switch } from '@/components/ui/switch';
+
+interface VerifySettings {
+  discover_checksums: boolean;
+}
+
+export default function VerifySettingsForm() {
+  const [discoverChecksums, setDiscoverChecksums] = useState(false);
+
+  useEffect(() => {
+    invoke<VerifySettings>('get_verify_settings')
+      .then((settings) => setDiscoverChecksums(settings.discover_checksums))
+      .catch((error) => toast.error(`${error}`));
+  }, []);
+
+  const handleChange = async (checked: boolean) => {
+    setDiscoverChecksums(checked);
+    try {
+      await invoke('update_verify_settings', { settings: { discover_checksums: checked } });
+    } catch (error) {
+      setDiscoverChecksums(!checked);
+      toast.error(`${error}`);
+    }
+  };
+
+  return (
+    <div className="space-y-2">
+      <div className="flex items-center gap-2">
+        <Switch
+          id="discover-checksums"
+          checked={discoverChecksums}
+          onCheckedChange={handleChange}
+        />
+        <Label htmlFor="discover-checksums">Look for published checksums</Label>
+      </div>
+      <span className="text-muted-foreground text-xs">
+        Requests <code>.sha256</code> and <code>SHA256SUMS</code> next to a finished download that
+        has no checksum, sending the same credentials, headers and cookies as the download.
+      </span>
+    </div>
+  );
+}
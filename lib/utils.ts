@@ -4,3 +4,13 @@ import { twMerge } from 'tailwind-merge';
 export function cn(...inputs: ClassValue[]) {
   return twMerge(clsx(inputs));
 }
+
+/** Metalink descriptors are added through their own command, one download per file. */
+export function isMetalink(url: string) {
+  return /\.(meta4|metalink)$/i.test(url.trim().split(/[?#]/)[0]);
+}
+
+/** HLS playlists and DASH manifests are downloaded as a variant picked from them. */
+export function isStream(url: string) {
+  return /\.(m3u8|mpd)$/i.test(url.trim().split(/[?#]/)[0]);
+}
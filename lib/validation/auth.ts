@@ -36,6 +36,13 @@ export const authSchema = z
       cookie: z.string().min(1, 'Cookie value is required'),
     }),
 
+    z.object({
+      type: z.literal('SshKey'),
+      username: z.string().min(1, 'Username is required'),
+      private_key: z.string().optional(),
+      passphrase: z.string().optional(),
+    }),
+
     z.object({
       type: z.literal('None'),
     }),
@@ -0,0 +1,24 @@
+import { z } from 'zod';
+
+const digestLengths = {
+  sha256: 64,
+  sha1: 40,
+  md5: 32,
+  blake3: 64,
+} as const;
+
+export const checksumAlgorithms = Object.keys(digestLengths) as Array<keyof typeof digestLengths>;
+
+export const checksumSchema = z
+  .object({
+    algorithm: z.enum(['sha256', 'sha1', 'md5', 'blake3']),
+    digest: z.string().trim().optional(),
+  })
+  .optional()
+  .refine(
+    (value) =>
+      !value?.digest ||
+      (value.digest.length === digestLengths[value.algorithm] &&
+        /^[0-9a-fA-F]+$/.test(value.digest)),
+    { message: 'Checksum does not match the selected algorithm', path: ['digest'] },
+  );
@@ -1,13 +1,15 @@
 import { z } from 'zod';
 
 import { authSchema } from './auth';
+import { checksumSchema } from './checksum';
 import { chunkSchema } from './chunk';
 import { cookiesArraySchema } from './cookies';
 import { filePathSchema } from './file-path';
 import { headersArraySchema } from './headers';
+import { mirrorsArraySchema } from './mirrors';
 import { positiveNumberSchema } from './positive-number';
 import { proxySchema } from './proxy';
-import { urlSchema } from './url';
+import { refererSchema, urlSchema } from './url';
 
 export const downloadFormSchema = z
   .object({
@@ -18,6 +20,9 @@ export const downloadFormSchema = z
     filePath: filePathSchema,
     headers: headersArraySchema,
     cookies: cookiesArraySchema,
+    checksum: checksumSchema,
+    mirrors: mirrorsArraySchema,
+    referer: refererSchema,
     speedLimit: positiveNumberSchema({
       message: 'Speed limit must be a positive number',
     }),
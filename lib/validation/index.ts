@@ -7,3 +7,5 @@ export * from './key-value';
 export * from './cookies';
 export * from './headers';
 export * from './proxy';
+export * from './checksum';
+export * from './mirrors';
@@ -0,0 +1,11 @@
+import { z } from 'zod';
+
+import { urlSchema } from './url';
+
+export const mirrorsArraySchema = z
+  .array(z.object({ url: urlSchema }))
+  .optional()
+  .refine((arr = []) => {
+    const urls = arr.map((item) => item.url.trim());
+    return new Set(urls).size === urls.length;
+  }, 'Duplicate mirrors are not allowed');
@@ -1,7 +1,14 @@
 import { z } from 'zod';
 
-export const urlSchema = z.string().refine((val) => /^https?:\/\/\S+$/.test(val), {
+export const urlSchema = z.string().refine((val) => /^(https?|s?ftp|ftps|ftpes):\/\/\S+$/.test(val), {
   message: 'URL is not valid.',
 });
 
+export const refererSchema = z
+  .string()
+  .optional()
+  .refine((val) => !val?.trim() || /^https?:\/\/\S+$/.test(val.trim()), {
+    message: 'Referer must be an http(s) URL.',
+  });
+
 export const urlFormSchema = z.object({ url: urlSchema });
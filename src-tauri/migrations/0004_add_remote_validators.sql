@@ -0,0 +1,9 @@
+ALTER TABLE
+    downloads
+ADD
+    COLUMN etag TEXT;
+
+ALTER TABLE
+    downloads
+ADD
+    COLUMN last_modified TEXT;
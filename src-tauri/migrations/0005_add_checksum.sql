@@ -0,0 +1,9 @@
+ALTER TABLE
+    downloads
+ADD
+    COLUMN checksum_algorithm TEXT;
+
+ALTER TABLE
+    downloads
+ADD
+    COLUMN checksum TEXT;
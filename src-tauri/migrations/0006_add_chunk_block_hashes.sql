@@ -0,0 +1,4 @@
+ALTER TABLE
+    download_chunks
+ADD
+    COLUMN block_hashes BLOB DEFAULT NULL;
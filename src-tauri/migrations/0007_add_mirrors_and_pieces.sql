@@ -0,0 +1,17 @@
+CREATE TABLE IF NOT EXISTS download_mirrors (
+    download_id INTEGER NOT NULL REFERENCES downloads(id) ON DELETE CASCADE,
+    url TEXT NOT NULL,
+    priority INTEGER NOT NULL DEFAULT 0,
+    location TEXT,
+    PRIMARY KEY (download_id, url)
+);
+
+CREATE TABLE IF NOT EXISTS download_pieces (
+    download_id INTEGER NOT NULL REFERENCES downloads(id) ON DELETE CASCADE,
+    piece_index INTEGER NOT NULL,
+    start_byte INTEGER NOT NULL,
+    end_byte INTEGER NOT NULL,
+    algorithm TEXT NOT NULL,
+    hash TEXT NOT NULL,
+    PRIMARY KEY (download_id, piece_index)
+);
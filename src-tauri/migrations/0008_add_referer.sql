@@ -0,0 +1,4 @@
+ALTER TABLE
+    downloads
+ADD
+    COLUMN referer TEXT;
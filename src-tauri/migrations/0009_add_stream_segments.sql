@@ -0,0 +1,17 @@
+ALTER TABLE
+    downloads
+ADD
+    COLUMN kind TEXT NOT NULL DEFAULT 'file';
+
+CREATE TABLE IF NOT EXISTS download_segments (
+    download_id INTEGER NOT NULL REFERENCES downloads(id) ON DELETE CASCADE,
+    segment_index INTEGER NOT NULL,
+    url TEXT NOT NULL,
+    range_start INTEGER,
+    range_end INTEGER,
+    key_url TEXT,
+    iv TEXT,
+    downloaded_bytes INTEGER NOT NULL DEFAULT 0,
+    completed BOOLEAN NOT NULL DEFAULT 0,
+    PRIMARY KEY (download_id, segment_index)
+);
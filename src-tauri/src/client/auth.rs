@@ -8,12 +8,34 @@ use tauri_plugin_http::reqwest::RequestBuilder;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum AuthType {
-    Basic { username: String, password: String },
-    Bearer { token: String },
-    CustomToken { scheme: String, token: String },
-    ApiKeyHeader { header_name: String, key: String },
-    ApiKeyQuery { key_name: String, key: String },
-    Cookie { cookie: String },
+    Basic {
+        username: String,
+        password: String,
+    },
+    Bearer {
+        token: String,
+    },
+    CustomToken {
+        scheme: String,
+        token: String,
+    },
+    ApiKeyHeader {
+        header_name: String,
+        key: String,
+    },
+    ApiKeyQuery {
+        key_name: String,
+        key: String,
+    },
+    Cookie {
+        cookie: String,
+    },
+    /// SFTP only. Without `private_key` the SSH agent is asked for keys.
+    SshKey {
+        username: String,
+        private_key: Option<String>,
+        passphrase: Option<String>,
+    },
 }
 
 impl super::Client {
@@ -42,9 +64,9 @@ impl super::Client {
             Some(AuthType::Cookie { cookie }) => {
                 return request.header(COOKIE, cookie);
             }
-            None => {}
+            Some(AuthType::SshKey { .. }) | None => {}
         }
 
-        return request;
+        request
     }
 }
@@ -1,4 +1,5 @@
 use std::{collections::HashMap, time::Duration};
+use tauri::Url;
 use tauri_plugin_http::reqwest::Client as ReqwestClient;
 
 use crate::{client::ProxyType, emitter::Emitter};
@@ -11,21 +12,24 @@ impl super::Client {
         headers: &Option<HashMap<String, String>>,
         cookies: &Option<HashMap<String, String>>,
     ) -> Result<Self, super::ClientError> {
+        let protocol = super::Protocol::from_url(url)
+            .ok_or_else(|| super::ClientError::InvalidUrl(url.to_string()))?;
+
+        // FTP and SFTP open their own sockets, a proxy set for them would be silently skipped.
+        if protocol != super::Protocol::Http && !matches!(proxy, None | Some(ProxyType::System)) {
+            let scheme = url.split_once(':').map_or(url, |(scheme, _)| scheme);
+            return Err(super::ClientError::ProxyNotSupported(scheme.to_uppercase()));
+        }
+
         let mut builder = ReqwestClient::builder().connect_timeout(Duration::from_secs(10));
 
-        match headers {
-            Some(custom_headers) => {
-                builder = builder.default_headers(Self::get_headers(custom_headers))
-            }
-            None => {}
+        if let Some(custom_headers) = headers {
+            builder = builder.default_headers(Self::get_headers(custom_headers))
         }
 
-        match cookies {
-            Some(custom_cookies) => {
-                let jar = Self::get_cookie_jar(url, custom_cookies.to_owned());
-                builder = builder.cookie_provider(jar);
-            }
-            None => {}
+        if let Some(custom_cookies) = cookies {
+            let jar = Self::get_cookie_jar(url, custom_cookies.to_owned());
+            builder = builder.cookie_provider(jar);
         };
 
         match proxy {
@@ -43,6 +47,56 @@ impl super::Client {
             client,
             url: url.to_string(),
             auth: auth.clone(),
+            protocol,
         })
     }
+
+    /// A client for a mirror of `primary`. The credentials, headers and cookies were
+    /// given for the primary URL, so another origin only gets the proxy.
+    pub fn for_source(
+        url: &str,
+        primary: &str,
+        auth: &Option<super::AuthType>,
+        proxy: &Option<super::ProxyType>,
+        headers: &Option<HashMap<String, String>>,
+        cookies: &Option<HashMap<String, String>>,
+    ) -> Result<Self, super::ClientError> {
+        if Self::same_origin(url, primary) {
+            return Self::new(url, auth, proxy, headers, cookies);
+        }
+
+        Self::new(url, &None, proxy, &None, &None)
+    }
+
+    fn same_origin(url: &str, other: &str) -> bool {
+        match (Url::parse(url), Url::parse(other)) {
+            (Ok(url), Ok(other)) => url.origin() == other.origin(),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client::Client;
+
+    #[test]
+    fn same_origin_needs_scheme_host_and_port() {
+        let primary = "https://example.com/files/a.iso";
+
+        assert!(Client::same_origin("https://example.com/b.iso", primary));
+        assert!(Client::same_origin(
+            "https://example.com:443/b.iso",
+            primary
+        ));
+        assert!(!Client::same_origin("http://example.com/a.iso", primary));
+        assert!(!Client::same_origin(
+            "https://mirror.example.com/a.iso",
+            primary
+        ));
+        assert!(!Client::same_origin(
+            "https://example.com:8443/a.iso",
+            primary
+        ));
+    }
 }
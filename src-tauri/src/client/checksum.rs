@@ -0,0 +1,44 @@
+use tauri_plugin_http::reqwest::Url;
+
+use crate::models::{Checksum, ChecksumAlgorithm};
+
+/// Checksum files are a few lines, anything bigger is not what we are looking for.
+const MAX_SUMS_BYTES: usize = 256 * 1024;
+
+impl super::Client {
+    /// Looks for a published digest next to the download, first `<url>.sha256`
+    /// and then a `SHA256SUMS` list in the same directory. Lists name the file as the
+    /// server does, which may differ from the name we saved it under.
+    pub async fn discover_checksum(&self, file_name: &str) -> Option<Checksum> {
+        let url = Url::parse(&self.url).ok()?;
+
+        let remote_name = url
+            .path_segments()
+            .and_then(|mut segments| segments.rfind(|s| !s.is_empty()))
+            .map(|s| String::from_utf8_lossy(&Self::percent_decode(s)).into_owned());
+
+        let mut sidecar = url.clone();
+        sidecar.set_query(None);
+        sidecar.set_path(&format!("{}.sha256", url.path()));
+
+        let sums = url.join("SHA256SUMS").ok()?;
+
+        for candidate in [sidecar, sums] {
+            let Ok(content) = self.fetch_text(candidate.as_str(), MAX_SUMS_BYTES).await else {
+                continue;
+            };
+
+            let checksum = remote_name
+                .iter()
+                .map(String::as_str)
+                .chain([file_name])
+                .find_map(|name| Checksum::parse_sums(ChecksumAlgorithm::Sha256, &content, name));
+
+            if checksum.is_some() {
+                return checksum;
+            }
+        }
+
+        None
+    }
+}
@@ -0,0 +1,622 @@
+use std::cmp::Reverse;
+
+use quick_xml::{events::Event, Reader};
+use tauri_plugin_http::reqwest::Url;
+
+use super::{ClientError, MediaPlaylist, MediaSegment, StreamVariant};
+
+/// More segments than this is a broken manifest, not a long video.
+const MAX_SEGMENTS: u64 = 100_000;
+
+/// Just enough of an XML tree to walk an MPD.
+#[derive(Debug, Default)]
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Element>,
+    text: String,
+}
+
+impl Element {
+    fn attribute(&self, name: &str) -> Option<String> {
+        super::Client::attribute(&self.attributes, name)
+    }
+
+    fn parsed<T: std::str::FromStr>(&self, name: &str) -> Option<T> {
+        self.attribute(name).and_then(|v| v.parse::<T>().ok())
+    }
+
+    fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+}
+
+impl super::Client {
+    pub(super) fn is_dash(text: &str) -> bool {
+        text.contains("<MPD") || text.contains(":MPD")
+    }
+
+    /// Representations of the first period that aren't DRM protected. Audio and video
+    /// are separate representations in DASH, so each is offered on its own.
+    pub(super) fn dash_variants(xml: &str) -> Result<Vec<StreamVariant>, ClientError> {
+        let mpd = Self::parse_mpd(xml)?;
+        let period = Self::dash_period(&mpd)?;
+
+        let mut variants = Vec::new();
+        let mut protected = false;
+
+        for set in period.children("AdaptationSet") {
+            for representation in set.children("Representation") {
+                if set.child("ContentProtection").is_some()
+                    || representation.child("ContentProtection").is_some()
+                {
+                    protected = true;
+                    continue;
+                }
+
+                let Some(id) = representation.attribute("id") else {
+                    continue;
+                };
+
+                let width = representation
+                    .attribute("width")
+                    .or_else(|| set.attribute("width"));
+                let height = representation
+                    .attribute("height")
+                    .or_else(|| set.attribute("height"));
+
+                variants.push(StreamVariant {
+                    id,
+                    bandwidth: representation.parsed("bandwidth"),
+                    resolution: width.zip(height).map(|(w, h)| format!("{}x{}", w, h)),
+                    codecs: representation
+                        .attribute("codecs")
+                        .or_else(|| set.attribute("codecs")),
+                    mime_type: Self::dash_mime_type(set, representation),
+                });
+            }
+        }
+
+        if variants.is_empty() {
+            return Err(match protected {
+                true => ClientError::UnsupportedStream("DRM protected content".to_string()),
+                false => ClientError::InvalidPlaylist("no representations".to_string()),
+            });
+        }
+
+        variants.sort_by_key(|variant| Reverse(variant.bandwidth));
+
+        Ok(variants)
+    }
+
+    /// Segments of one representation, from a `SegmentTemplate` (numbered or with a
+    /// timeline), a `SegmentList`, or the `BaseURL` alone for a single file.
+    pub(super) fn dash_segments(
+        mpd_url: &Url,
+        xml: &str,
+        representation_id: &str,
+    ) -> Result<MediaPlaylist, ClientError> {
+        let invalid = |message: &str| ClientError::InvalidPlaylist(message.to_string());
+
+        let mpd = Self::parse_mpd(xml)?;
+        let period = Self::dash_period(&mpd)?;
+
+        let (set, representation) = period
+            .children("AdaptationSet")
+            .flat_map(|set| set.children("Representation").map(move |r| (set, r)))
+            .find(|(_, r)| r.attribute("id").as_deref() == Some(representation_id))
+            .ok_or_else(|| invalid("representation not found"))?;
+
+        // Every level may carry a `BaseURL` relative to the one above it.
+        let mut base = mpd_url.clone();
+
+        for element in [&mpd, period, set, representation] {
+            if let Some(base_url) = element.child("BaseURL") {
+                if !base_url.text.trim().is_empty() {
+                    base = Self::join_url(&base, &base_url.text)?;
+                }
+            }
+        }
+
+        let bandwidth = representation.attribute("bandwidth").unwrap_or_default();
+        let media_url = |url: &str, range: Option<(u64, u64)>| -> Result<_, ClientError> {
+            Ok(MediaSegment {
+                url: Self::join_url(&base, url)?.to_string(),
+                range,
+                key: None,
+            })
+        };
+
+        let template = representation
+            .child("SegmentTemplate")
+            .or_else(|| set.child("SegmentTemplate"))
+            .or_else(|| period.child("SegmentTemplate"));
+        let list = representation
+            .child("SegmentList")
+            .or_else(|| set.child("SegmentList"));
+
+        let mut segments = Vec::new();
+
+        if let Some(template) = template {
+            let fill = |template: &str, number: u64, time: u64| {
+                Self::fill_template(template, representation_id, &bandwidth, number, time)
+            };
+
+            if let Some(initialization) = template.attribute("initialization") {
+                segments.push(media_url(&fill(&initialization, 0, 0), None)?);
+            }
+
+            let media = template
+                .attribute("media")
+                .ok_or_else(|| invalid("SegmentTemplate without media"))?;
+            let start_number = template.parsed::<u64>("startNumber").unwrap_or(1);
+            let timescale = template.parsed::<u64>("timescale").unwrap_or(1).max(1);
+            let offset = template
+                .parsed::<u64>("presentationTimeOffset")
+                .unwrap_or(0);
+            let period_end = Self::dash_duration(&mpd, period)
+                .map(|secs| offset + (secs * timescale as f64).round() as u64);
+
+            let mut times = Vec::new();
+
+            if let Some(timeline) = template.child("SegmentTimeline") {
+                let entries = timeline.children("S").collect::<Vec<_>>();
+                let mut time = offset;
+
+                for (index, entry) in entries.iter().enumerate() {
+                    time = entry.parsed("t").unwrap_or(time);
+
+                    let duration = entry
+                        .parsed::<u64>("d")
+                        .filter(|d| *d > 0)
+                        .ok_or_else(|| invalid("timeline entry without duration"))?;
+
+                    // A negative repeat runs until the next entry or the end of the period.
+                    let repeat = match entry.parsed::<i64>("r").unwrap_or(0) {
+                        r if r >= 0 => r as u64,
+                        _ => entries
+                            .get(index + 1)
+                            .and_then(|next| next.parsed::<u64>("t"))
+                            .or(period_end)
+                            .ok_or_else(|| invalid("open ended timeline without a duration"))?
+                            .saturating_sub(time)
+                            .div_ceil(duration)
+                            .saturating_sub(1),
+                    };
+
+                    for _ in 0..=repeat {
+                        if times.len() as u64 >= MAX_SEGMENTS {
+                            return Err(invalid("too many segments"));
+                        }
+
+                        times.push(time);
+                        time += duration;
+                    }
+                }
+            } else {
+                let duration = template
+                    .parsed::<u64>("duration")
+                    .filter(|d| *d > 0)
+                    .ok_or_else(|| invalid("SegmentTemplate without duration or timeline"))?;
+                let period_end = period_end.ok_or_else(|| invalid("unknown duration"))?;
+                let count = (period_end - offset).div_ceil(duration);
+
+                if count > MAX_SEGMENTS {
+                    return Err(invalid("too many segments"));
+                }
+
+                times.extend((0..count).map(|index| offset + index * duration));
+            }
+
+            for (index, time) in times.into_iter().enumerate() {
+                segments.push(media_url(
+                    &fill(&media, start_number + index as u64, time),
+                    None,
+                )?);
+            }
+        } else if let Some(list) = list {
+            if let Some(initialization) = list.child("Initialization") {
+                let url = initialization.attribute("sourceURL").unwrap_or_default();
+                let range = Self::dash_range(initialization.attribute("range"))?;
+                segments.push(media_url(&url, range)?);
+            }
+
+            for segment in list.children("SegmentURL") {
+                let url = segment.attribute("media").unwrap_or_default();
+                let range = Self::dash_range(segment.attribute("mediaRange"))?;
+                segments.push(media_url(&url, range)?);
+            }
+        } else {
+            // `SegmentBase` or nothing at all, the representation is one file.
+            segments.push(media_url("", None)?);
+        }
+
+        let mime_type = Self::dash_mime_type(set, representation);
+
+        let extension = match mime_type.as_deref() {
+            Some("video/webm" | "audio/webm") => "webm",
+            Some(mime) if mime.starts_with("audio/") => "m4a",
+            _ => "mp4",
+        };
+
+        Ok(MediaPlaylist {
+            segments,
+            extension: extension.to_string(),
+            content_type: mime_type.unwrap_or_else(|| "video/mp4".to_string()),
+        })
+    }
+
+    fn parse_mpd(xml: &str) -> Result<Element, ClientError> {
+        let invalid = |e: &dyn ToString| ClientError::InvalidPlaylist(e.to_string());
+
+        let mut reader = Reader::from_str(xml);
+        reader.config_mut().trim_text(true);
+
+        let mut path = vec![Element::default()];
+
+        loop {
+            match reader.read_event().map_err(|e| invalid(&e))? {
+                Event::Start(e) => path.push(Element {
+                    name: Self::local_name(&e),
+                    attributes: Self::attributes(&e),
+                    ..Default::default()
+                }),
+                Event::Empty(e) => {
+                    if let Some(parent) = path.last_mut() {
+                        parent.children.push(Element {
+                            name: Self::local_name(&e),
+                            attributes: Self::attributes(&e),
+                            ..Default::default()
+                        });
+                    }
+                }
+                Event::Text(e) => {
+                    if let Some(element) = path.last_mut() {
+                        element
+                            .text
+                            .push_str(&e.unescape().map_err(|e| invalid(&e))?);
+                    }
+                }
+                Event::End(_) if path.len() > 1 => {
+                    let element = path.pop().unwrap_or_default();
+
+                    if let Some(parent) = path.last_mut() {
+                        parent.children.push(element);
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        path.into_iter()
+            .next()
+            .and_then(|root| root.children.into_iter().find(|e| e.name == "MPD"))
+            .ok_or_else(|| invalid(&"missing MPD element"))
+    }
+
+    /// Only the first period is downloaded, later ones are usually ads or bumpers.
+    fn dash_period(mpd: &Element) -> Result<&Element, ClientError> {
+        if mpd.attribute("type").as_deref() == Some("dynamic") {
+            return Err(ClientError::UnsupportedStream(
+                "live manifests have no end to download".to_string(),
+            ));
+        }
+
+        mpd.child("Period")
+            .ok_or_else(|| ClientError::InvalidPlaylist("no Period".to_string()))
+    }
+
+    fn dash_mime_type(set: &Element, representation: &Element) -> Option<String> {
+        representation
+            .attribute("mimeType")
+            .or_else(|| set.attribute("mimeType"))
+    }
+
+    /// Length of the period in seconds.
+    fn dash_duration(mpd: &Element, period: &Element) -> Option<f64> {
+        period
+            .attribute("duration")
+            .or_else(|| mpd.attribute("mediaPresentationDuration"))
+            .and_then(|value| Self::iso_duration(&value))
+    }
+
+    /// ISO 8601 duration such as `PT1H2M3.5S` or `P1DT2H`.
+    fn iso_duration(value: &str) -> Option<f64> {
+        let value = value.trim().strip_prefix('P')?;
+        let (date, time) = value.split_once('T').unwrap_or((value, ""));
+
+        let sum = |part: &str, units: &[(char, f64)]| -> Option<f64> {
+            let mut total = 0.0;
+            let mut number = String::new();
+
+            for c in part.chars() {
+                if c.is_ascii_digit() || c == '.' {
+                    number.push(c);
+                    continue;
+                }
+
+                let (_, scale) = units.iter().find(|(unit, _)| *unit == c)?;
+                total += number.parse::<f64>().ok()? * scale;
+                number.clear();
+            }
+
+            number.is_empty().then_some(total)
+        };
+
+        let date = sum(
+            date,
+            &[
+                ('Y', 365.0 * 86_400.0),
+                ('M', 30.0 * 86_400.0),
+                ('W', 7.0 * 86_400.0),
+                ('D', 86_400.0),
+            ],
+        )?;
+        let time = sum(time, &[('H', 3_600.0), ('M', 60.0), ('S', 1.0)])?;
+
+        Some(date + time)
+    }
+
+    /// `first-last` byte range of a `SegmentList` entry.
+    fn dash_range(value: Option<String>) -> Result<Option<(u64, u64)>, ClientError> {
+        let Some(value) = value else {
+            return Ok(None);
+        };
+
+        value
+            .split_once('-')
+            .and_then(|(start, end)| Some((start.trim().parse().ok()?, end.trim().parse().ok()?)))
+            .filter(|(start, end)| start <= end)
+            .map(Some)
+            .ok_or_else(|| ClientError::InvalidPlaylist(format!("bad range {}", value)))
+    }
+
+    /// Fills `$RepresentationID$`, `$Bandwidth$`, `$Number$` and `$Time$`, the last
+    /// two with an optional `%0<width>d` format. `$$` is a literal dollar sign.
+    fn fill_template(
+        template: &str,
+        representation_id: &str,
+        bandwidth: &str,
+        number: u64,
+        time: u64,
+    ) -> String {
+        let mut url = String::new();
+
+        for (index, part) in template.split('$').enumerate() {
+            if index % 2 == 0 {
+                url.push_str(part);
+                continue;
+            }
+
+            let (name, format) = part.split_once('%').unwrap_or((part, ""));
+
+            let value = match name {
+                "" => "$".to_string(),
+                "RepresentationID" => representation_id.to_string(),
+                "Bandwidth" => bandwidth.to_string(),
+                "Number" => number.to_string(),
+                "Time" => time.to_string(),
+                _ => format!("${}$", part),
+            };
+
+            let width = format
+                .strip_prefix('0')
+                .and_then(|f| f.strip_suffix('d'))
+                .and_then(|w| w.parse::<usize>().ok())
+                .unwrap_or(0);
+
+            url.push_str(&format!("{:0>width$}", value, width = width));
+        }
+
+        url
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::Client;
+
+    fn mpd(body: &str) -> String {
+        format!(
+            r#"<?xml version="1.0"?>
+            <MPD xmlns="urn:mpeg:dash:schema:mpd:2011" type="static" mediaPresentationDuration="PT10S">
+              {body}
+            </MPD>"#
+        )
+    }
+
+    fn urls(xml: &str, representation_id: &str) -> Vec<String> {
+        let mpd_url = Url::parse("https://cdn.example.com/v/manifest.mpd").unwrap();
+
+        Client::dash_segments(&mpd_url, xml, representation_id)
+            .unwrap()
+            .segments
+            .into_iter()
+            .map(|segment| segment.url)
+            .collect()
+    }
+
+    #[test]
+    fn offers_unprotected_representations_by_bandwidth() {
+        let xml = mpd(r#"<Period>
+              <AdaptationSet mimeType="video/mp4" width="1280" height="720" codecs="avc1">
+                <Representation id="low" bandwidth="500000" width="640" height="360"/>
+                <Representation id="high" bandwidth="3000000"/>
+              </AdaptationSet>
+              <AdaptationSet mimeType="audio/mp4">
+                <ContentProtection schemeIdUri="urn:mpeg:dash:mp4protection:2011"/>
+                <Representation id="drm" bandwidth="128000"/>
+              </AdaptationSet>
+            </Period>"#);
+
+        let variants = Client::dash_variants(&xml).unwrap();
+        let ids = variants.iter().map(|v| v.id.as_str()).collect::<Vec<_>>();
+
+        assert_eq!(ids, ["high", "low"]);
+        assert_eq!(variants[0].resolution.as_deref(), Some("1280x720"));
+        assert_eq!(variants[1].resolution.as_deref(), Some("640x360"));
+        assert_eq!(variants[1].codecs.as_deref(), Some("avc1"));
+        assert_eq!(variants[1].mime_type.as_deref(), Some("video/mp4"));
+    }
+
+    #[test]
+    fn rejects_protected_and_live_manifests() {
+        let protected = mpd(r#"<Period><AdaptationSet>
+              <Representation id="a"><ContentProtection/></Representation>
+            </AdaptationSet></Period>"#);
+        let live = r#"<MPD type="dynamic"><Period/></MPD>"#;
+
+        assert!(matches!(
+            Client::dash_variants(&protected),
+            Err(ClientError::UnsupportedStream(_))
+        ));
+        assert!(matches!(
+            Client::dash_variants(live),
+            Err(ClientError::UnsupportedStream(_))
+        ));
+        assert!(matches!(
+            Client::dash_variants("<html/>"),
+            Err(ClientError::InvalidPlaylist(_))
+        ));
+    }
+
+    #[test]
+    fn numbered_template_covers_the_period() {
+        let xml = mpd(
+            r#"<BaseURL>media/</BaseURL><Period><AdaptationSet mimeType="audio/mp4">
+              <BaseURL>audio/</BaseURL>
+              <SegmentTemplate initialization="$RepresentationID$/init.mp4"
+                media="$RepresentationID$/$Bandwidth$/seg-$Number%05d$.m4s"
+                startNumber="5" duration="4" timescale="1"/>
+              <Representation id="a1" bandwidth="128000"/>
+            </AdaptationSet></Period>"#,
+        );
+
+        let base = "https://cdn.example.com/v/media/audio/a1";
+        assert_eq!(
+            urls(&xml, "a1"),
+            [
+                format!("{base}/init.mp4"),
+                format!("{base}/128000/seg-00005.m4s"),
+                format!("{base}/128000/seg-00006.m4s"),
+                format!("{base}/128000/seg-00007.m4s"),
+            ]
+        );
+
+        let mpd_url = Url::parse("https://cdn.example.com/v/manifest.mpd").unwrap();
+        let playlist = Client::dash_segments(&mpd_url, &xml, "a1").unwrap();
+        assert_eq!(playlist.extension, "m4a");
+        assert_eq!(playlist.content_type, "audio/mp4");
+    }
+
+    #[test]
+    fn timeline_repeats_entries() {
+        let xml = mpd(r#"<Period duration="PT2S"><AdaptationSet>
+              <SegmentTemplate media="$Time$.m4s" timescale="1000" presentationTimeOffset="100">
+                <SegmentTimeline>
+                  <S t="100" d="400" r="1"/>
+                  <S d="300" r="-1"/>
+                </SegmentTimeline>
+              </SegmentTemplate>
+              <Representation id="v"/>
+            </AdaptationSet></Period>"#);
+
+        let names = urls(&xml, "v")
+            .into_iter()
+            .map(|url| url.rsplit('/').next().unwrap().to_string())
+            .collect::<Vec<_>>();
+
+        // Two 400ms entries, then 300ms ones until the period ends at 2100.
+        assert_eq!(
+            names,
+            ["100.m4s", "500.m4s", "900.m4s", "1200.m4s", "1500.m4s", "1800.m4s"]
+        );
+    }
+
+    #[test]
+    fn segment_list_keeps_byte_ranges() {
+        let xml = mpd(r#"<Period><AdaptationSet>
+              <Representation id="v" mimeType="video/webm">
+                <BaseURL>https://media.example.com/video.webm</BaseURL>
+                <SegmentList>
+                  <Initialization range="0-99"/>
+                  <SegmentURL mediaRange="100-499"/>
+                  <SegmentURL media="other.webm" mediaRange="0-9"/>
+                </SegmentList>
+              </Representation>
+            </AdaptationSet></Period>"#);
+
+        let mpd_url = Url::parse("https://cdn.example.com/v/manifest.mpd").unwrap();
+        let playlist = Client::dash_segments(&mpd_url, &xml, "v").unwrap();
+        let segments = playlist
+            .segments
+            .iter()
+            .map(|segment| (segment.url.as_str(), segment.range))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            segments,
+            [
+                ("https://media.example.com/video.webm", Some((0, 99))),
+                ("https://media.example.com/video.webm", Some((100, 499))),
+                ("https://media.example.com/other.webm", Some((0, 9))),
+            ]
+        );
+        assert_eq!(playlist.extension, "webm");
+    }
+
+    #[test]
+    fn base_url_alone_is_a_single_file() {
+        let xml = mpd(r#"<Period><AdaptationSet>
+              <Representation id="v"><BaseURL>video.mp4</BaseURL></Representation>
+            </AdaptationSet></Period>"#);
+
+        assert_eq!(urls(&xml, "v"), ["https://cdn.example.com/v/video.mp4"]);
+    }
+
+    #[test]
+    fn rejects_broken_segment_descriptions() {
+        let mpd_url = Url::parse("https://cdn.example.com/v/manifest.mpd").unwrap();
+        let segments = |body: &str| Client::dash_segments(&mpd_url, &mpd(body), "v");
+
+        assert!(segments("<Period/>").is_err());
+        assert!(segments(
+            r#"<Period><AdaptationSet><SegmentTemplate media="x"/><Representation id="v"/></AdaptationSet></Period>"#
+        )
+        .is_err());
+        assert!(segments(
+            r#"<Period><AdaptationSet><SegmentTemplate media="x" duration="1" timescale="1000000"/><Representation id="v"/></AdaptationSet></Period>"#
+        )
+        .is_err());
+        assert!(segments(
+            r#"<Period><AdaptationSet><SegmentList><SegmentURL mediaRange="9-1"/></SegmentList><Representation id="v"/></AdaptationSet></Period>"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn parses_iso_durations() {
+        assert_eq!(Client::iso_duration("PT1H2M3.5S"), Some(3723.5));
+        assert_eq!(Client::iso_duration("P1DT2H"), Some(93_600.0));
+        assert_eq!(Client::iso_duration("PT"), Some(0.0));
+        assert_eq!(Client::iso_duration("1H"), None);
+        assert_eq!(Client::iso_duration("PT5X"), None);
+        assert_eq!(Client::iso_duration("PT5"), None);
+    }
+
+    #[test]
+    fn fills_template_identifiers() {
+        let fill = |template: &str| Client::fill_template(template, "v1", "800", 7, 9000);
+
+        assert_eq!(fill("$RepresentationID$_$Number$.m4s"), "v1_7.m4s");
+        assert_eq!(fill("$Time%08d$-$Bandwidth$"), "00009000-800");
+        assert_eq!(fill("cost$$$Number$"), "cost$7");
+        assert_eq!(fill("$Unknown$"), "$Unknown$");
+    }
+}
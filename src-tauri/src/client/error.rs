@@ -1,8 +1,14 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
-use tauri::http::StatusCode;
+use chrono::{DateTime, Utc};
+use libssh2_sys::{
+    LIBSSH2_ERROR_SOCKET_DISCONNECT, LIBSSH2_ERROR_SOCKET_RECV, LIBSSH2_ERROR_SOCKET_SEND,
+    LIBSSH2_ERROR_TIMEOUT,
+};
+use tauri::http::{header::RETRY_AFTER, HeaderMap, StatusCode};
 use tauri_plugin_http::reqwest;
 use thiserror::Error;
+use tokio_native_tls::native_tls;
 
 #[derive(Debug, Error, Clone)]
 pub enum ClientError {
@@ -10,7 +16,10 @@ pub enum ClientError {
     Reqwest(#[from] Arc<reqwest::Error>),
 
     #[error("{status}")]
-    Http { status: StatusCode },
+    Http {
+        status: StatusCode,
+        retry_after: Option<Duration>,
+    },
 
     #[error("{0}")]
     Deserialize(#[from] Arc<serde_json::Error>),
@@ -21,8 +30,62 @@ pub enum ClientError {
     #[error("timeout error")]
     StreamTimeout,
 
-    #[error("unexpected chunk hash")]
-    UnexpectedChunkHash,
+    #[error("remote file changed since the download started")]
+    RemoteChanged,
+
+    #[error("server ignored the requested range")]
+    RangeNotSupported,
+
+    #[error("requested bytes {requested}, server sent {received}")]
+    RangeMismatch { requested: String, received: String },
+
+    #[error("connection closed before the range was complete")]
+    UnexpectedEof,
+
+    #[error("response is larger than {0} bytes")]
+    BodyTooLarge(usize),
+
+    #[error("invalid metalink: {0}")]
+    InvalidMetalink(String),
+
+    #[error("invalid playlist: {0}")]
+    InvalidPlaylist(String),
+
+    #[error("unsupported stream: {0}")]
+    UnsupportedStream(String),
+
+    #[error("segment could not be decrypted, the key may be wrong")]
+    DecryptFailed,
+
+    #[error("resolver returned an invalid url: {0}")]
+    InvalidResolvedUrl(String),
+
+    #[error("unsupported or invalid url: {0}")]
+    InvalidUrl(String),
+
+    #[error("{0} downloads can't go through a proxy")]
+    ProxyNotSupported(String),
+
+    #[error("{0}")]
+    Io(#[from] Arc<std::io::Error>),
+
+    #[error("{0}")]
+    Tls(#[from] Arc<native_tls::Error>),
+
+    #[error("ftp {code}: {message}")]
+    Ftp { code: u16, message: String },
+
+    #[error("{0}")]
+    Ssh(#[from] Arc<ssh2::Error>),
+
+    #[error("ssh authentication failed")]
+    SshAuthFailed,
+
+    #[error("{host} is not in known_hosts, its key fingerprint is {fingerprint}")]
+    UnknownHostKey { host: String, fingerprint: String },
+
+    #[error("host key for {0} doesn't match known_hosts")]
+    HostKeyMismatch(String),
 }
 
 impl From<reqwest::Error> for ClientError {
@@ -31,6 +94,24 @@ impl From<reqwest::Error> for ClientError {
     }
 }
 
+impl From<std::io::Error> for ClientError {
+    fn from(value: std::io::Error) -> Self {
+        ClientError::Io(Arc::new(value))
+    }
+}
+
+impl From<native_tls::Error> for ClientError {
+    fn from(value: native_tls::Error) -> Self {
+        ClientError::Tls(Arc::new(value))
+    }
+}
+
+impl From<ssh2::Error> for ClientError {
+    fn from(value: ssh2::Error) -> Self {
+        ClientError::Ssh(Arc::new(value))
+    }
+}
+
 impl From<serde_json::Error> for ClientError {
     fn from(value: serde_json::Error) -> Self {
         ClientError::Deserialize(Arc::new(value))
@@ -38,13 +119,74 @@ impl From<serde_json::Error> for ClientError {
 }
 
 impl ClientError {
+    pub fn http(status: StatusCode, headers: &HeaderMap) -> Self {
+        ClientError::Http {
+            status,
+            retry_after: Self::parse_retry_after(headers),
+        }
+    }
+
+    /// How long the server asked us to wait before trying again, if it said so.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            ClientError::Http { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// `Retry-After` is either delta-seconds or an HTTP date, `RateLimit-Reset`
+    /// is always delta-seconds.
+    fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+        let header = |name| headers.get(name).and_then(|v| v.to_str().ok());
+
+        if let Some(value) = header(RETRY_AFTER.as_str()) {
+            if let Ok(secs) = value.trim().parse::<u64>() {
+                return Some(Duration::from_secs(secs));
+            }
+
+            if let Ok(date) = DateTime::parse_from_rfc2822(value.trim()) {
+                let wait = date.with_timezone(&Utc) - Utc::now();
+                return Some(wait.to_std().unwrap_or(Duration::ZERO));
+            }
+        }
+
+        header("ratelimit-reset")
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .map(Duration::from_secs)
+    }
+
+    /// What a signed or tokenized link answers once it ran out.
+    pub fn is_url_expired(&self) -> bool {
+        matches!(
+            self,
+            ClientError::Http { status, .. } if matches!(status.as_u16(), 401 | 403 | 410)
+        )
+    }
+
     pub fn is_retryable(&self) -> bool {
         match self {
             ClientError::Reqwest(e) => e.is_timeout() || e.is_connect() || e.is_request(),
             ClientError::Http { status, .. } => {
                 matches!(status.as_u16(), 408 | 429 | 500..=504)
             }
-            ClientError::StreamTimeout | ClientError::UnexpectedChunkHash => true,
+            // 4xx replies are FTP's transient failures, such as too many connections.
+            ClientError::Ftp { code, .. } => (400..500).contains(code),
+            // Socket and timeout failures of the SSH session.
+            ClientError::Ssh(e) => match e.code() {
+                ssh2::ErrorCode::Session(code) => [
+                    LIBSSH2_ERROR_SOCKET_SEND,
+                    LIBSSH2_ERROR_TIMEOUT,
+                    LIBSSH2_ERROR_SOCKET_DISCONNECT,
+                    LIBSSH2_ERROR_SOCKET_RECV,
+                ]
+                .contains(&code),
+                _ => false,
+            },
+            // A corrupted transfer fails to decrypt, the segment is fetched again.
+            ClientError::StreamTimeout
+            | ClientError::UnexpectedEof
+            | ClientError::DecryptFailed
+            | ClientError::Io(_) => true,
             _ => false,
         }
     }
@@ -0,0 +1,345 @@
+use std::path::Path;
+
+use tauri::http::{header::CONTENT_DISPOSITION, HeaderMap};
+use tauri_plugin_http::reqwest::Url;
+
+/// Names Windows refuses for files regardless of extension.
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Longest file name we hand to the file system, in bytes.
+const MAX_FILE_NAME_BYTES: usize = 200;
+
+impl super::Client {
+    /// Picks a file name from `Content-Disposition`, then the last URL path segment,
+    /// then the domain, and makes it safe to create on disk.
+    pub(super) fn file_name(headers: &HeaderMap, final_url: &Url, extension: &str) -> String {
+        let name = headers
+            .get(CONTENT_DISPOSITION)
+            .map(|v| String::from_utf8_lossy(v.as_bytes()).into_owned())
+            .and_then(|v| Self::content_disposition_file_name(&v))
+            .and_then(|name| Self::sanitize_file_name(&name))
+            .or_else(|| {
+                final_url
+                    .path_segments()
+                    .and_then(|mut segments| segments.rfind(|s| !s.is_empty()))
+                    .map(|s| String::from_utf8_lossy(&Self::percent_decode(s)).into_owned())
+                    .and_then(|name| Self::sanitize_file_name(&name))
+            });
+
+        match name {
+            Some(name) if Path::new(&name).extension().is_some() => name,
+            Some(name) => format!("{name}.{extension}"),
+            None => match final_url.domain() {
+                Some(domain) => format!("{domain}.{extension}"),
+                None => format!("file.{extension}"),
+            },
+        }
+    }
+
+    /// RFC 6266 parameters, with `filename*` (RFC 5987) winning over `filename`.
+    fn content_disposition_file_name(value: &str) -> Option<String> {
+        let mut file_name = None;
+        let mut file_name_ext = None;
+
+        for (key, value) in Self::disposition_params(value) {
+            match key.to_ascii_lowercase().as_str() {
+                "filename*" => file_name_ext = Self::decode_ext_value(&value),
+                "filename" => file_name = Some(value),
+                _ => {}
+            }
+        }
+
+        file_name_ext.or(file_name).filter(|name| !name.is_empty())
+    }
+
+    /// Splits `attachment; key=value; key="quoted; value"` into its parameters,
+    /// honoring quotes and backslash escapes.
+    fn disposition_params(value: &str) -> Vec<(String, String)> {
+        let mut params = Vec::new();
+        let mut chars = value.chars().peekable();
+
+        // Skip the disposition type.
+        for c in chars.by_ref() {
+            if c == ';' {
+                break;
+            }
+        }
+
+        loop {
+            let key = chars
+                .by_ref()
+                .take_while(|c| *c != '=')
+                .collect::<String>()
+                .trim()
+                .to_string();
+
+            if key.is_empty() {
+                break;
+            }
+
+            while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                chars.next();
+            }
+
+            let mut value = String::new();
+
+            if chars.peek() == Some(&'"') {
+                chars.next();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => value.extend(chars.next()),
+                        '"' => break,
+                        c => value.push(c),
+                    }
+                }
+                // Drop anything up to the next parameter.
+                for c in chars.by_ref() {
+                    if c == ';' {
+                        break;
+                    }
+                }
+            } else {
+                value = chars
+                    .by_ref()
+                    .take_while(|c| *c != ';')
+                    .collect::<String>()
+                    .trim()
+                    .to_string();
+            }
+
+            params.push((key, value));
+        }
+
+        params
+    }
+
+    /// `charset'language'percent-encoded`, only UTF-8 and ISO-8859-1 are required.
+    fn decode_ext_value(value: &str) -> Option<String> {
+        let mut parts = value.splitn(3, '\'');
+        let charset = parts.next()?;
+        let _language = parts.next()?;
+        let bytes = Self::percent_decode(parts.next()?);
+
+        match charset.to_ascii_lowercase().as_str() {
+            "utf-8" => String::from_utf8(bytes).ok(),
+            "iso-8859-1" => Some(bytes.into_iter().map(char::from).collect()),
+            _ => None,
+        }
+    }
+
+    pub(super) fn percent_decode(value: &str) -> Vec<u8> {
+        let bytes = value.as_bytes();
+        let mut decoded = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+
+        while i < bytes.len() {
+            let hex = bytes
+                .get(i + 1..i + 3)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+            match (bytes[i], hex) {
+                (b'%', Some(byte)) => {
+                    decoded.push(byte);
+                    i += 3;
+                }
+                (byte, _) => {
+                    decoded.push(byte);
+                    i += 1;
+                }
+            }
+        }
+
+        decoded
+    }
+
+    /// Keeps only the final path component, replaces characters no file system
+    /// accepts and steers clear of reserved device names.
+    pub(super) fn sanitize_file_name(name: &str) -> Option<String> {
+        let name = name.rsplit(['/', '\\']).next().unwrap_or(name);
+
+        let name = name
+            .chars()
+            .map(|c| match c {
+                '<' | '>' | ':' | '"' | '|' | '?' | '*' => '_',
+                c if c.is_control() => '_',
+                c => c,
+            })
+            .collect::<String>();
+
+        let name = name.trim().trim_matches('.').trim();
+
+        if name.is_empty() {
+            return None;
+        }
+
+        let stem = name.split('.').next().unwrap_or(name);
+        let name = match RESERVED_NAMES.contains(&stem.to_ascii_uppercase().as_str()) {
+            true => format!("_{name}"),
+            false => name.to_string(),
+        };
+
+        Some(Self::truncate_file_name(name))
+    }
+
+    /// Shortens the stem so the extension survives.
+    fn truncate_file_name(name: String) -> String {
+        if name.len() <= MAX_FILE_NAME_BYTES {
+            return name;
+        }
+
+        let extension = Path::new(&name)
+            .extension()
+            .map(|ext| format!(".{}", ext.to_string_lossy()))
+            .filter(|ext| ext.len() < MAX_FILE_NAME_BYTES / 2)
+            .unwrap_or_default();
+
+        let mut stem_len = MAX_FILE_NAME_BYTES - extension.len();
+        while !name.is_char_boundary(stem_len) {
+            stem_len -= 1;
+        }
+
+        format!("{}{}", &name[..stem_len], extension)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tauri::http::HeaderValue;
+
+    use super::*;
+    use crate::client::Client;
+
+    fn file_name(disposition: Option<&[u8]>, url: &str) -> String {
+        let mut headers = HeaderMap::new();
+        if let Some(disposition) = disposition {
+            headers.insert(
+                CONTENT_DISPOSITION,
+                HeaderValue::from_bytes(disposition).unwrap(),
+            );
+        }
+
+        Client::file_name(&headers, &Url::parse(url).unwrap(), "bin")
+    }
+
+    #[test]
+    fn extended_file_name_wins_over_plain() {
+        let disposition =
+            b"attachment; filename=\"fallback.txt\"; filename*=UTF-8''na%C3%AFve%20file.txt";
+
+        assert_eq!(
+            file_name(Some(disposition), "https://example.com/x"),
+            "naïve file.txt"
+        );
+    }
+
+    #[test]
+    fn extended_file_name_decodes_latin1() {
+        let disposition = b"attachment; filename*=iso-8859-1'en'%A3%20rates.pdf";
+
+        assert_eq!(
+            file_name(Some(disposition), "https://example.com/x"),
+            "£ rates.pdf"
+        );
+    }
+
+    #[test]
+    fn falls_back_when_extended_value_is_unusable() {
+        let unknown_charset = b"attachment; filename*=koi8-r''%C1.txt; filename=plain.txt";
+        let invalid_utf8 = b"attachment; filename*=UTF-8''%FF.txt; filename=plain.txt";
+
+        assert_eq!(
+            file_name(Some(unknown_charset), "https://example.com/x"),
+            "plain.txt"
+        );
+        assert_eq!(
+            file_name(Some(invalid_utf8), "https://example.com/x"),
+            "plain.txt"
+        );
+    }
+
+    #[test]
+    fn quoted_file_name_keeps_semicolons_and_escapes() {
+        let disposition = br#"attachment; filename="a; b \"c\".zip"; size=10"#;
+
+        assert_eq!(
+            file_name(Some(disposition), "https://example.com/x"),
+            "a; b _c_.zip"
+        );
+    }
+
+    #[test]
+    fn file_name_parameter_is_case_insensitive() {
+        assert_eq!(
+            file_name(
+                Some(b"inline; FILENAME=report.csv"),
+                "https://example.com/x"
+            ),
+            "report.csv"
+        );
+    }
+
+    #[test]
+    fn falls_back_to_url_then_domain() {
+        assert_eq!(
+            file_name(None, "https://example.com/files/my%20archive.tar.gz"),
+            "my archive.tar.gz"
+        );
+        assert_eq!(
+            file_name(Some(b"attachment"), "https://example.com/dir/readme/"),
+            "readme.bin"
+        );
+        assert_eq!(file_name(None, "https://example.com/"), "example.com.bin");
+        assert_eq!(file_name(None, "http://127.0.0.1/"), "file.bin");
+    }
+
+    #[test]
+    fn sanitize_strips_paths_and_bad_characters() {
+        assert_eq!(
+            Client::sanitize_file_name("../../etc/passwd").as_deref(),
+            Some("passwd")
+        );
+        assert_eq!(
+            Client::sanitize_file_name("C:\\Users\\me\\a:b?.txt").as_deref(),
+            Some("a_b_.txt")
+        );
+        assert_eq!(
+            Client::sanitize_file_name("tab\there.txt").as_deref(),
+            Some("tab_here.txt")
+        );
+        assert_eq!(Client::sanitize_file_name(" .. ").as_deref(), None);
+        assert_eq!(Client::sanitize_file_name("dir/").as_deref(), None);
+    }
+
+    #[test]
+    fn sanitize_prefixes_reserved_device_names() {
+        assert_eq!(
+            Client::sanitize_file_name("con.txt").as_deref(),
+            Some("_con.txt")
+        );
+        assert_eq!(Client::sanitize_file_name("LPT1").as_deref(), Some("_LPT1"));
+        assert_eq!(
+            Client::sanitize_file_name("console.txt").as_deref(),
+            Some("console.txt")
+        );
+    }
+
+    #[test]
+    fn truncation_keeps_extension_and_char_boundaries() {
+        let name = format!("{}.mkv", "é".repeat(150));
+        let truncated = Client::sanitize_file_name(&name).unwrap();
+
+        assert!(truncated.len() <= MAX_FILE_NAME_BYTES);
+        assert!(truncated.ends_with(".mkv"));
+        assert!(truncated.starts_with("éé"));
+    }
+
+    #[test]
+    fn percent_decode_leaves_broken_escapes() {
+        assert_eq!(Client::percent_decode("a%20b%2"), b"a b%2");
+        assert_eq!(Client::percent_decode("%zz%41"), b"%zzA");
+    }
+}
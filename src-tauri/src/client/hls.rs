@@ -0,0 +1,434 @@
+use std::cmp::Reverse;
+
+use tauri_plugin_http::reqwest::Url;
+
+use super::{ClientError, MediaPlaylist, MediaSegment, SegmentKey, StreamVariant};
+
+impl super::Client {
+    pub(super) fn is_hls(text: &str) -> bool {
+        text.trim_start_matches('\u{feff}')
+            .trim_start()
+            .starts_with("#EXTM3U")
+    }
+
+    /// Variants of a master playlist (RFC 8216), or the playlist itself when it
+    /// already lists segments. Alternative renditions (`EXT-X-MEDIA`) are not offered,
+    /// so a variant with separate audio downloads as video only.
+    pub(super) fn hls_variants(base: &Url, text: &str) -> Result<Vec<StreamVariant>, ClientError> {
+        let mut variants = Vec::new();
+        let mut stream_info = None;
+
+        for line in Self::playlist_lines(text) {
+            if let Some(list) = line.strip_prefix("#EXT-X-STREAM-INF:") {
+                stream_info = Some(Self::hls_attributes(list));
+            } else if !line.starts_with('#') {
+                if let Some(attributes) = stream_info.take() {
+                    variants.push(StreamVariant {
+                        id: Self::join_url(base, line)?.to_string(),
+                        bandwidth: Self::attribute(&attributes, "BANDWIDTH")
+                            .and_then(|v| v.parse::<u64>().ok()),
+                        resolution: Self::attribute(&attributes, "RESOLUTION"),
+                        codecs: Self::attribute(&attributes, "CODECS"),
+                        mime_type: None,
+                    });
+                }
+            }
+        }
+
+        if variants.is_empty() {
+            if !text.contains("#EXTINF") {
+                return Err(ClientError::InvalidPlaylist(
+                    "no variants or segments".to_string(),
+                ));
+            }
+
+            variants.push(StreamVariant {
+                id: base.to_string(),
+                bandwidth: None,
+                resolution: None,
+                codecs: None,
+                mime_type: None,
+            });
+        }
+
+        variants.sort_by_key(|variant| Reverse(variant.bandwidth));
+
+        Ok(variants)
+    }
+
+    /// Segments of a media playlist, with the `EXT-X-MAP` initialization section
+    /// in front of the segments it applies to.
+    pub(super) fn hls_segments(base: &Url, text: &str) -> Result<MediaPlaylist, ClientError> {
+        let invalid = |message: &str| ClientError::InvalidPlaylist(message.to_string());
+
+        if !Self::is_hls(text) {
+            return Err(invalid("missing #EXTM3U header"));
+        }
+
+        let mut segments = Vec::new();
+        let mut sequence: u64 = 0;
+        let mut key: Option<(Url, Option<[u8; 16]>)> = None;
+        let mut byte_range: Option<(u64, Option<u64>)> = None;
+        let mut next_offset: u64 = 0;
+        let mut map: Option<MediaSegment> = None;
+        let mut ended = false;
+
+        // Without an explicit IV, AES-128 uses the media sequence number of the segment.
+        let segment_key = |key: &Option<(Url, Option<[u8; 16]>)>, sequence: u64| {
+            key.as_ref().map(|(url, iv)| SegmentKey {
+                url: url.to_string(),
+                iv: iv.unwrap_or_else(|| {
+                    let mut iv = [0; 16];
+                    iv[8..].copy_from_slice(&sequence.to_be_bytes());
+                    iv
+                }),
+            })
+        };
+
+        for line in Self::playlist_lines(text) {
+            if line.starts_with("#EXT-X-STREAM-INF:") {
+                return Err(invalid("expected a media playlist, got a master playlist"));
+            } else if let Some(value) = line.strip_prefix("#EXT-X-MEDIA-SEQUENCE:") {
+                sequence = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| invalid("bad media sequence"))?;
+            } else if let Some(list) = line.strip_prefix("#EXT-X-KEY:") {
+                let attributes = Self::hls_attributes(list);
+
+                key = match Self::attribute(&attributes, "METHOD").as_deref() {
+                    Some("NONE") => None,
+                    Some("AES-128") => {
+                        let uri = Self::attribute(&attributes, "URI")
+                            .ok_or_else(|| invalid("AES-128 key without URI"))?;
+                        let iv = match Self::attribute(&attributes, "IV") {
+                            Some(iv) => Some(Self::parse_iv(&iv)?),
+                            None => None,
+                        };
+
+                        Some((Self::join_url(base, &uri)?, iv))
+                    }
+                    Some(method) => {
+                        return Err(ClientError::UnsupportedStream(format!(
+                            "{} encryption",
+                            method
+                        )))
+                    }
+                    None => return Err(invalid("key without METHOD")),
+                };
+            } else if let Some(list) = line.strip_prefix("#EXT-X-MAP:") {
+                let attributes = Self::hls_attributes(list);
+                let uri = Self::attribute(&attributes, "URI")
+                    .ok_or_else(|| invalid("map without URI"))?;
+
+                let range = match Self::attribute(&attributes, "BYTERANGE") {
+                    Some(value) => {
+                        let (length, offset) = Self::parse_byte_range(&value)?;
+                        let start = offset.unwrap_or(0);
+                        Some((start, start + length - 1))
+                    }
+                    None => None,
+                };
+
+                let init = MediaSegment {
+                    url: Self::join_url(base, &uri)?.to_string(),
+                    range,
+                    key: segment_key(&key, sequence),
+                };
+
+                // Repeated after every discontinuity, only a new one goes into the file.
+                if map
+                    .as_ref()
+                    .is_none_or(|map| map.url != init.url || map.range != range)
+                {
+                    segments.push(init.clone());
+                    map = Some(init);
+                }
+            } else if let Some(value) = line.strip_prefix("#EXT-X-BYTERANGE:") {
+                byte_range = Some(Self::parse_byte_range(value)?);
+            } else if line == "#EXT-X-ENDLIST" {
+                ended = true;
+            } else if !line.starts_with('#') {
+                // A sub-range without an offset continues where the previous one ended.
+                let range = byte_range.take().map(|(length, offset)| {
+                    let start = offset.unwrap_or(next_offset);
+                    (start, start + length - 1)
+                });
+
+                if let Some((_, end)) = range {
+                    next_offset = end + 1;
+                }
+
+                segments.push(MediaSegment {
+                    url: Self::join_url(base, line)?.to_string(),
+                    range,
+                    key: segment_key(&key, sequence),
+                });
+
+                sequence += 1;
+            }
+        }
+
+        if !ended {
+            return Err(ClientError::UnsupportedStream(
+                "live playlists have no end to download".to_string(),
+            ));
+        }
+
+        if segments.is_empty() {
+            return Err(invalid("no segments"));
+        }
+
+        // An initialization section means fragmented MP4 instead of MPEG-TS.
+        let (extension, content_type) = match map {
+            Some(_) => ("mp4", "video/mp4"),
+            None => ("ts", "video/mp2t"),
+        };
+
+        Ok(MediaPlaylist {
+            segments,
+            extension: extension.to_string(),
+            content_type: content_type.to_string(),
+        })
+    }
+
+    fn playlist_lines(text: &str) -> impl Iterator<Item = &str> {
+        text.lines().map(str::trim).filter(|line| !line.is_empty())
+    }
+
+    /// Splits `KEY=VALUE,KEY="quoted, value"` into its attributes.
+    fn hls_attributes(list: &str) -> Vec<(String, String)> {
+        let mut attributes = Vec::new();
+        let mut rest = list.trim();
+
+        while let Some((key, after)) = rest.split_once('=') {
+            let (value, after) = match after.strip_prefix('"') {
+                Some(quoted) => quoted.split_once('"').unwrap_or((quoted, "")),
+                None => after.split_once(',').unwrap_or((after, "")),
+            };
+
+            attributes.push((key.trim().to_string(), value.to_string()));
+            rest = after.trim_start_matches(',').trim_start();
+        }
+
+        attributes
+    }
+
+    /// `<length>[@<offset>]`, as used by `EXT-X-BYTERANGE` and a map's `BYTERANGE`.
+    fn parse_byte_range(value: &str) -> Result<(u64, Option<u64>), ClientError> {
+        let invalid = || ClientError::InvalidPlaylist(format!("bad byte range {}", value));
+
+        let (length, offset) = match value.trim().split_once('@') {
+            Some((length, offset)) => (length, Some(offset.parse().map_err(|_| invalid())?)),
+            None => (value.trim(), None),
+        };
+
+        match length.parse::<u64>() {
+            Ok(length) if length > 0 => Ok((length, offset)),
+            _ => Err(invalid()),
+        }
+    }
+
+    fn parse_iv(value: &str) -> Result<[u8; 16], ClientError> {
+        let hex_value = value
+            .strip_prefix("0x")
+            .or_else(|| value.strip_prefix("0X"))
+            .unwrap_or(value);
+
+        hex::decode(hex_value)
+            .ok()
+            .and_then(|iv| iv.try_into().ok())
+            .ok_or_else(|| ClientError::InvalidPlaylist(format!("bad IV {}", value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::Client;
+
+    fn base() -> Url {
+        Url::parse("https://cdn.example.com/video/index.m3u8").unwrap()
+    }
+
+    fn segments(text: &str) -> Result<MediaPlaylist, ClientError> {
+        Client::hls_segments(&base(), text)
+    }
+
+    #[test]
+    fn master_playlist_lists_variants_by_bandwidth() {
+        let text = "\u{feff}#EXTM3U
+#EXT-X-STREAM-INF:BANDWIDTH=800000,RESOLUTION=640x360,CODECS=\"avc1.4d401e,mp4a.40.2\"
+low/index.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=2400000,RESOLUTION=1280x720
+https://other.example.com/high.m3u8
+";
+        assert!(Client::is_hls(text));
+
+        let variants = Client::hls_variants(&base(), text).unwrap();
+
+        assert_eq!(variants.len(), 2);
+        assert_eq!(variants[0].id, "https://other.example.com/high.m3u8");
+        assert_eq!(
+            variants[1].id,
+            "https://cdn.example.com/video/low/index.m3u8"
+        );
+        assert_eq!(variants[1].bandwidth, Some(800000));
+        assert_eq!(variants[1].codecs.as_deref(), Some("avc1.4d401e,mp4a.40.2"));
+    }
+
+    #[test]
+    fn media_playlist_is_its_own_variant() {
+        let text = "#EXTM3U\n#EXTINF:4,\na.ts\n#EXT-X-ENDLIST\n";
+        let variants = Client::hls_variants(&base(), text).unwrap();
+
+        assert_eq!(variants.len(), 1);
+        assert_eq!(variants[0].id, base().to_string());
+        assert!(Client::hls_variants(&base(), "#EXTM3U\n").is_err());
+    }
+
+    #[test]
+    fn iv_defaults_to_the_media_sequence_number() {
+        let text = "#EXTM3U
+#EXT-X-MEDIA-SEQUENCE:258
+#EXT-X-KEY:METHOD=AES-128,URI=\"key.bin\"
+#EXTINF:4,
+a.ts
+#EXTINF:4,
+b.ts
+#EXT-X-KEY:METHOD=AES-128,URI=\"https://keys.example.com/k2\",IV=0X000102030405060708090A0B0C0D0E0F
+#EXTINF:4,
+c.ts
+#EXT-X-KEY:METHOD=NONE
+#EXTINF:4,
+d.ts
+#EXT-X-ENDLIST
+";
+        let playlist = segments(text).unwrap();
+        let keys = playlist
+            .segments
+            .iter()
+            .map(|segment| segment.key.clone())
+            .collect::<Vec<_>>();
+
+        let mut first_iv = [0; 16];
+        first_iv[14] = 1;
+        first_iv[15] = 2;
+        let mut second_iv = first_iv;
+        second_iv[15] = 3;
+
+        assert_eq!(
+            keys[0],
+            Some(SegmentKey {
+                url: "https://cdn.example.com/video/key.bin".to_string(),
+                iv: first_iv,
+            })
+        );
+        assert_eq!(keys[1].as_ref().unwrap().iv, second_iv);
+        assert_eq!(
+            keys[2],
+            Some(SegmentKey {
+                url: "https://keys.example.com/k2".to_string(),
+                iv: std::array::from_fn(|i| i as u8),
+            })
+        );
+        assert_eq!(keys[3], None);
+        assert_eq!(playlist.extension, "ts");
+    }
+
+    #[test]
+    fn byte_ranges_continue_from_the_previous_one() {
+        let text = "#EXTM3U
+#EXTINF:4,
+#EXT-X-BYTERANGE:100@50
+all.ts
+#EXTINF:4,
+#EXT-X-BYTERANGE:200
+all.ts
+#EXTINF:4,
+whole.ts
+#EXT-X-ENDLIST
+";
+        let ranges = segments(text)
+            .unwrap()
+            .segments
+            .iter()
+            .map(|segment| segment.range)
+            .collect::<Vec<_>>();
+
+        assert_eq!(ranges, [Some((50, 149)), Some((150, 349)), None]);
+    }
+
+    #[test]
+    fn repeated_map_goes_in_only_once() {
+        let text = "#EXTM3U
+#EXT-X-MAP:URI=\"init.mp4\",BYTERANGE=\"720@0\"
+#EXTINF:4,
+a.m4s
+#EXT-X-DISCONTINUITY
+#EXT-X-MAP:URI=\"init.mp4\",BYTERANGE=\"720@0\"
+#EXTINF:4,
+b.m4s
+#EXT-X-MAP:URI=\"init2.mp4\"
+#EXTINF:4,
+c.m4s
+#EXT-X-ENDLIST
+";
+        let playlist = segments(text).unwrap();
+        let urls = playlist
+            .segments
+            .iter()
+            .map(|segment| segment.url.rsplit('/').next().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(urls, ["init.mp4", "a.m4s", "b.m4s", "init2.mp4", "c.m4s"]);
+        assert_eq!(playlist.segments[0].range, Some((0, 719)));
+        assert_eq!(playlist.extension, "mp4");
+    }
+
+    #[test]
+    fn rejects_playlists_we_cannot_download() {
+        let live = "#EXTM3U\n#EXTINF:4,\na.ts\n";
+        let master = "#EXTM3U\n#EXT-X-STREAM-INF:BANDWIDTH=1\nlow.m3u8\n";
+        let sample_aes =
+            "#EXTM3U\n#EXT-X-KEY:METHOD=SAMPLE-AES,URI=\"k\"\n#EXTINF:4,\na.ts\n#EXT-X-ENDLIST\n";
+        let bad_iv =
+            "#EXTM3U\n#EXT-X-KEY:METHOD=AES-128,URI=\"k\",IV=0x01\n#EXTINF:4,\na.ts\n#EXT-X-ENDLIST\n";
+
+        assert!(matches!(
+            segments(live),
+            Err(ClientError::UnsupportedStream(_))
+        ));
+        assert!(matches!(
+            segments(master),
+            Err(ClientError::InvalidPlaylist(_))
+        ));
+        assert!(matches!(
+            segments(sample_aes),
+            Err(ClientError::UnsupportedStream(_))
+        ));
+        assert!(matches!(
+            segments(bad_iv),
+            Err(ClientError::InvalidPlaylist(_))
+        ));
+        assert!(matches!(
+            segments("#EXTM3U\n#EXT-X-ENDLIST\n"),
+            Err(ClientError::InvalidPlaylist(_))
+        ));
+        assert!(matches!(
+            segments("<html></html>"),
+            Err(ClientError::InvalidPlaylist(_))
+        ));
+    }
+
+    #[test]
+    fn parses_byte_ranges() {
+        assert_eq!(Client::parse_byte_range("100").unwrap(), (100, None));
+        assert_eq!(
+            Client::parse_byte_range(" 100@20 ").unwrap(),
+            (100, Some(20))
+        );
+        assert!(Client::parse_byte_range("0@20").is_err());
+        assert!(Client::parse_byte_range("100@x").is_err());
+    }
+}
@@ -1,33 +1,64 @@
+use std::path::Path;
+
 use mime2ext::mime2ext;
 use tauri::http::{
-    header::{ACCEPT_RANGES, CONTENT_DISPOSITION, CONTENT_LENGTH, CONTENT_TYPE},
-    Method,
+    header::{
+        ACCEPT_RANGES, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, ETAG, LAST_MODIFIED, RANGE,
+    },
+    HeaderMap, Method, StatusCode,
 };
+use tauri_plugin_http::reqwest::Url;
+
+/// `Last-Modified` layout, used for the modification times of FTP and SFTP files too.
+pub(super) const HTTP_DATE: &str = "%a, %d %b %Y %H:%M:%S GMT";
 
 #[derive(Clone, Debug)]
 pub struct InspectResponse {
     pub supports_range: bool,
+    /// Zero when the server doesn't say, the download then runs as a single stream.
     pub content_length: u64,
     pub content_type: String,
     pub file_name: String,
     pub extension: String,
     pub url: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
 }
 
 impl super::Client {
     pub async fn inspect(&self) -> Result<InspectResponse, super::ClientError> {
+        if let super::Protocol::Ftp(security) = self.protocol {
+            return self.ftp_inspect(security).await;
+        }
+
+        if self.protocol == super::Protocol::Sftp {
+            return self.sftp_inspect().await;
+        }
+
+        match self.inspect_head().await {
+            // Plenty of CDNs and signed URLs refuse HEAD or answer it without a length,
+            // a one byte GET tells us the same.
+            Err(super::ClientError::Http { .. } | super::ClientError::MissingContentLength) => {
+                self.inspect_get().await
+            }
+            Ok(response) if response.content_length == 0 => self.inspect_get().await,
+            result => result,
+        }
+    }
+
+    async fn inspect_head(&self) -> Result<InspectResponse, super::ClientError> {
         let request = self.client.request(Method::HEAD, &self.url);
         let request = Self::auth_handler(request, &self.auth);
 
         let response = request.send().await?;
         let status = response.status();
-        let final_url = response.url().clone();
-        let headers = response.headers().clone();
 
         if !status.is_success() {
-            return Err(super::ClientError::Http { status });
+            return Err(super::ClientError::http(status, response.headers()));
         }
 
+        let headers = response.headers();
+
         let supports_range = headers
             .get(ACCEPT_RANGES)
             .map(|v| v == "bytes")
@@ -39,41 +70,123 @@ impl super::Client {
             .and_then(|f| f.parse::<u64>().ok())
             .ok_or(super::ClientError::MissingContentLength)?;
 
+        Ok(Self::inspect_response(
+            response.url(),
+            headers,
+            content_length,
+            supports_range,
+        ))
+    }
+
+    /// Asks for the first byte only. A `206` carries the total size in `Content-Range`,
+    /// a `200` means the server ignores ranges and sends the whole body, which we drop.
+    /// Either may leave the size unknown (`bytes 0-0/*`, chunked transfer).
+    async fn inspect_get(&self) -> Result<InspectResponse, super::ClientError> {
+        let request = self.client.request(Method::GET, &self.url);
+        let request = Self::auth_handler(request, &self.auth).header(RANGE, "bytes=0-0");
+
+        let response = request.send().await?;
+        let status = response.status();
+
+        if !status.is_success() {
+            return Err(super::ClientError::http(status, response.headers()));
+        }
+
+        let headers = response.headers();
+
+        let (content_length, supports_range) = match status {
+            StatusCode::PARTIAL_CONTENT => {
+                let total = headers
+                    .get(CONTENT_RANGE)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.rsplit_once('/'))
+                    .and_then(|(_, total)| total.trim().parse::<u64>().ok())
+                    .unwrap_or(0);
+
+                (total, total > 0)
+            }
+            _ => {
+                let length = headers
+                    .get(CONTENT_LENGTH)
+                    .and_then(|f| f.to_str().ok())
+                    .and_then(|f| f.parse::<u64>().ok())
+                    .unwrap_or(0);
+
+                (length, false)
+            }
+        };
+
+        Ok(Self::inspect_response(
+            response.url(),
+            headers,
+            content_length,
+            supports_range,
+        ))
+    }
+
+    fn inspect_response(
+        final_url: &Url,
+        headers: &HeaderMap,
+        content_length: u64,
+        supports_range: bool,
+    ) -> InspectResponse {
         let content_type = headers
             .get(CONTENT_TYPE)
             .and_then(|v| v.to_str().ok())
             .unwrap_or("application/octet-stream");
 
-        let extension = mime2ext(content_type).unwrap_or("bin");
+        let etag = headers
+            .get(ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string());
 
-        let file_name = headers
-            .get(CONTENT_DISPOSITION)
+        let last_modified = headers
+            .get(LAST_MODIFIED)
             .and_then(|v| v.to_str().ok())
-            .and_then(|v| {
-                v.split(';')
-                    .find_map(|part| part.trim().strip_prefix("filename="))
-                    .map(|name| name.trim_matches('"').to_string())
-            })
-            .or_else(|| {
-                final_url
-                    .path_segments()
-                    .and_then(|segments| segments.filter(|s| !s.is_empty()).last())
-                    .map(|s| s.to_string())
-            })
-            .or_else(|| {
-                final_url
-                    .domain()
-                    .map(|domain| format!("{domain}.{extension}"))
-            })
-            .unwrap_or_else(|| format!("file.{extension}"));
-
-        Ok(InspectResponse {
+            .map(|v| v.to_string());
+
+        let extension = mime2ext(content_type).unwrap_or("bin");
+
+        let file_name = Self::file_name(headers, final_url, extension);
+
+        InspectResponse {
             url: final_url.to_string(),
             file_name: file_name.to_string(),
             content_type: content_type.to_string(),
             content_length,
             extension: extension.to_string(),
             supports_range,
-        })
+            etag,
+            last_modified,
+        }
+    }
+
+    /// Response for a file behind a plain path, where the name and extension come
+    /// from the URL alone and no ETag exists.
+    pub(super) fn path_response(
+        url: &Url,
+        content_length: u64,
+        supports_range: bool,
+        last_modified: Option<String>,
+    ) -> InspectResponse {
+        let extension = Path::new(url.path())
+            .extension()
+            .map(|e| e.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "bin".to_string());
+
+        InspectResponse {
+            file_name: Self::file_name(&HeaderMap::new(), url, &extension),
+            url: url.to_string(),
+            content_type: "application/octet-stream".to_string(),
+            content_length,
+            extension,
+            supports_range,
+            etag: None,
+            last_modified,
+        }
+    }
+
+    pub(super) fn parse_url(url: &str) -> Result<Url, super::ClientError> {
+        Url::parse(url).map_err(|_| super::ClientError::InvalidUrl(url.to_string()))
     }
 }
@@ -0,0 +1,319 @@
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader,
+};
+
+use crate::models::{Checksum, ChecksumAlgorithm};
+
+/// Descriptors with piece hashes for large files run to a few megabytes.
+const MAX_METALINK_BYTES: usize = 16 * 1024 * 1024;
+
+/// One `<file>` of a metalink.
+#[derive(Debug, Clone, Default)]
+pub struct MetalinkFile {
+    /// Safe to save under, empty when the metalink gave none we can use.
+    pub name: String,
+    pub size: Option<u64>,
+    /// Mirrors, most preferred first.
+    pub urls: Vec<MetalinkUrl>,
+    /// Whole file digests we know how to check, strongest first.
+    pub hashes: Vec<Checksum>,
+    pub pieces: Option<MetalinkPieces>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MetalinkUrl {
+    pub url: String,
+    /// Lower is preferred, as in RFC 5854. Mirrors without one go last.
+    pub priority: i64,
+    pub location: Option<String>,
+}
+
+/// Digests of consecutive `length` sized pieces of the file.
+#[derive(Debug, Clone)]
+pub struct MetalinkPieces {
+    pub length: u64,
+    pub algorithm: ChecksumAlgorithm,
+    pub hashes: Vec<String>,
+}
+
+impl MetalinkFile {
+    /// Strongest digest the metalink gives for the whole file.
+    pub fn checksum(&self) -> Option<Checksum> {
+        self.hashes.first().cloned()
+    }
+}
+
+impl super::Client {
+    /// Downloads and parses the metalink this client points at.
+    pub async fn metalink(&self) -> Result<Vec<MetalinkFile>, super::ClientError> {
+        let xml = self.fetch_text(&self.url, MAX_METALINK_BYTES).await?;
+        Self::parse_metalink(&xml)
+    }
+
+    /// Parses an RFC 5854 (`.meta4`) document. Elements are matched by local name,
+    /// so prefixed namespaces work, and anything we don't use is skipped.
+    pub fn parse_metalink(xml: &str) -> Result<Vec<MetalinkFile>, super::ClientError> {
+        let invalid = |e: &dyn ToString| super::ClientError::InvalidMetalink(e.to_string());
+
+        let mut reader = Reader::from_str(xml);
+        reader.config_mut().trim_text(true);
+
+        let mut files = Vec::new();
+        let mut file: Option<MetalinkFile> = None;
+        let mut pieces: Option<(Option<u64>, Option<ChecksumAlgorithm>, Vec<String>)> = None;
+        let mut path: Vec<String> = Vec::new();
+        let mut attributes: Vec<(String, String)> = Vec::new();
+        let mut text = String::new();
+        let mut is_metalink = false;
+
+        loop {
+            match reader.read_event().map_err(|e| invalid(&e))? {
+                Event::Start(e) => {
+                    let name = Self::local_name(&e);
+                    attributes = Self::attributes(&e);
+                    text.clear();
+
+                    match name.as_str() {
+                        "metalink" => is_metalink = true,
+                        "file" => {
+                            file = Some(MetalinkFile {
+                                name: Self::attribute(&attributes, "name").unwrap_or_default(),
+                                ..Default::default()
+                            })
+                        }
+                        "pieces" if file.is_some() => {
+                            pieces = Some((
+                                Self::attribute(&attributes, "length")
+                                    .and_then(|v| v.parse::<u64>().ok()),
+                                Self::attribute(&attributes, "type")
+                                    .and_then(|v| v.parse::<ChecksumAlgorithm>().ok()),
+                                Vec::new(),
+                            ))
+                        }
+                        _ => {}
+                    }
+
+                    path.push(name);
+                }
+                Event::Text(e) => text.push_str(&e.unescape().map_err(|e| invalid(&e))?),
+                Event::CData(e) => text.push_str(&String::from_utf8_lossy(&e)),
+                Event::End(_) => {
+                    let name = path.pop().unwrap_or_default();
+                    let parent = path.last().map(String::as_str);
+                    let value = text.trim().to_string();
+                    text.clear();
+
+                    match (name.as_str(), parent, file.as_mut()) {
+                        ("file", _, Some(_)) => files.extend(file.take()),
+                        ("size", Some("file"), Some(file)) => {
+                            file.size = value.parse::<u64>().ok();
+                        }
+                        ("url", Some("file"), Some(file)) if !value.is_empty() => {
+                            file.urls.push(MetalinkUrl {
+                                url: value,
+                                priority: Self::attribute(&attributes, "priority")
+                                    .and_then(|v| v.parse::<i64>().ok())
+                                    .unwrap_or(i64::MAX),
+                                location: Self::attribute(&attributes, "location"),
+                            });
+                        }
+                        ("hash", Some("file"), Some(file)) => {
+                            let checksum = Self::attribute(&attributes, "type")
+                                .and_then(|v| v.parse::<ChecksumAlgorithm>().ok())
+                                .and_then(|algorithm| Checksum::new(algorithm, &value).ok());
+                            file.hashes.extend(checksum);
+                        }
+                        ("hash", Some("pieces"), Some(_)) => {
+                            if let Some((_, _, hashes)) = pieces.as_mut() {
+                                hashes.push(value.to_ascii_lowercase());
+                            }
+                        }
+                        ("pieces", _, Some(file)) => {
+                            // Pieces in an algorithm we can't compute are no use to us.
+                            if let Some((Some(length), Some(algorithm), hashes)) = pieces.take() {
+                                if length > 0 && !hashes.is_empty() {
+                                    file.pieces = Some(MetalinkPieces {
+                                        length,
+                                        algorithm,
+                                        hashes,
+                                    });
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                Event::Empty(e) if Self::local_name(&e) == "metalink" => is_metalink = true,
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        if !is_metalink {
+            return Err(invalid(&"missing metalink element"));
+        }
+
+        files.retain(|file| !file.urls.is_empty());
+
+        if files.is_empty() {
+            return Err(invalid(&"no file with a download url"));
+        }
+
+        for file in files.iter_mut() {
+            // Names may carry a directory, we only ever save into the chosen folder.
+            file.name = file
+                .name
+                .rsplit(['/', '\\'])
+                .next()
+                .and_then(Self::sanitize_file_name)
+                .unwrap_or_default();
+            file.urls.sort_by_key(|url| url.priority);
+            file.hashes
+                .sort_by_key(|checksum| match checksum.algorithm {
+                    ChecksumAlgorithm::Sha256 | ChecksumAlgorithm::Blake3 => 0,
+                    ChecksumAlgorithm::Sha1 => 1,
+                    ChecksumAlgorithm::Md5 => 2,
+                });
+        }
+
+        Ok(files)
+    }
+
+    pub(super) fn local_name(e: &BytesStart) -> String {
+        String::from_utf8_lossy(e.local_name().as_ref()).into_owned()
+    }
+
+    pub(super) fn attributes(e: &BytesStart) -> Vec<(String, String)> {
+        e.attributes()
+            .flatten()
+            .filter_map(|attr| {
+                let key = String::from_utf8_lossy(attr.key.local_name().as_ref()).into_owned();
+                let value = attr.unescape_value().ok()?.into_owned();
+                Some((key, value))
+            })
+            .collect()
+    }
+
+    pub(super) fn attribute(attributes: &[(String, String)], name: &str) -> Option<String> {
+        attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.trim().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{Client, ClientError};
+
+    const SHA1: &str = "a9993e364706816aba3e25717850c26c9cd0d89d";
+    const SHA256: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    #[test]
+    fn parses_mirrors_hashes_and_pieces() {
+        let xml = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+            <metalink xmlns="urn:ietf:params:xml:ns:metalink">
+              <file name="dir/example.iso">
+                <size>2048</size>
+                <hash type="sha-1">{SHA1}</hash>
+                <hash type="sha-256">{SHA256}</hash>
+                <hash type="sha-512">ignored</hash>
+                <pieces length="1024" type="sha-1">
+                  <hash>AA</hash>
+                  <hash>bb</hash>
+                </pieces>
+                <url>https://c.example.com/example.iso</url>
+                <url priority="2" location="de">https://b.example.com/example.iso</url>
+                <url priority="1">https://a.example.com/a&amp;b.iso</url>
+                <url priority="3"> </url>
+              </file>
+            </metalink>"#
+        );
+
+        let files = Client::parse_metalink(&xml).unwrap();
+        assert_eq!(files.len(), 1);
+
+        let file = &files[0];
+        assert_eq!(file.name, "example.iso");
+        assert_eq!(file.size, Some(2048));
+
+        let urls = file.urls.iter().map(|u| u.url.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            urls,
+            [
+                "https://a.example.com/a&b.iso",
+                "https://b.example.com/example.iso",
+                "https://c.example.com/example.iso",
+            ]
+        );
+        assert_eq!(file.urls[1].location.as_deref(), Some("de"));
+        assert_eq!(file.urls[2].priority, i64::MAX);
+
+        assert_eq!(
+            file.checksum().unwrap().algorithm,
+            ChecksumAlgorithm::Sha256
+        );
+        assert_eq!(file.hashes.len(), 2);
+
+        let pieces = file.pieces.as_ref().unwrap();
+        assert_eq!(pieces.length, 1024);
+        assert_eq!(pieces.algorithm, ChecksumAlgorithm::Sha1);
+        assert_eq!(pieces.hashes, ["aa", "bb"]);
+    }
+
+    #[test]
+    fn matches_prefixed_namespaces_and_cdata() {
+        let xml = r#"<m:metalink xmlns:m="urn:ietf:params:xml:ns:metalink">
+              <m:file name="a.bin"><m:url><![CDATA[https://example.com/a.bin?x=1&y=2]]></m:url></m:file>
+            </m:metalink>"#;
+
+        let files = Client::parse_metalink(xml).unwrap();
+
+        assert_eq!(files[0].urls[0].url, "https://example.com/a.bin?x=1&y=2");
+    }
+
+    #[test]
+    fn drops_pieces_we_cannot_check() {
+        let xml = r#"<metalink><file name="a.bin">
+              <pieces length="1024" type="sha-512"><hash>aa</hash></pieces>
+              <url>https://example.com/a.bin</url>
+            </file></metalink>"#;
+
+        let files = Client::parse_metalink(xml).unwrap();
+
+        assert!(files[0].pieces.is_none());
+        assert!(files[0].checksum().is_none());
+    }
+
+    #[test]
+    fn skips_files_without_urls_and_unsafe_names() {
+        let xml = r#"<metalink>
+              <file name="empty.bin"></file>
+              <file name="../.."><url>https://example.com/x</url></file>
+            </metalink>"#;
+
+        let files = Client::parse_metalink(xml).unwrap();
+
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].name, "");
+    }
+
+    #[test]
+    fn rejects_documents_that_are_not_usable_metalinks() {
+        let invalid = |xml: &str| {
+            matches!(
+                Client::parse_metalink(xml),
+                Err(ClientError::InvalidMetalink(_))
+            )
+        };
+
+        assert!(invalid("<html><body>not found</body></html>"));
+        assert!(invalid("<metalink/>"));
+        assert!(invalid(
+            "<metalink><file name=\"a\"><url>x</url></metalink>"
+        ));
+    }
+}
@@ -2,19 +2,36 @@ use tauri_plugin_http::reqwest::Client as ReqwestClient;
 
 mod auth;
 mod builder;
+mod checksum;
 mod cookies;
+mod dash;
 mod error;
+mod filename;
+mod ftp;
 mod headers;
+mod hls;
 mod inspect;
+mod metalink;
+mod playlist;
+mod protocol;
 mod proxy;
+mod resolve;
+mod sftp;
 mod stream;
+mod text;
 
 pub use auth::AuthType;
 pub use error::*;
+pub use inspect::InspectResponse;
+pub use metalink::*;
+pub use playlist::*;
+pub use protocol::*;
 pub use proxy::*;
 
+#[derive(Debug)]
 pub struct Client {
     url: String,
     client: ReqwestClient,
     auth: Option<AuthType>,
+    protocol: Protocol,
 }
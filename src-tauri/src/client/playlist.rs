@@ -0,0 +1,162 @@
+use std::fmt;
+
+use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, KeyIvInit};
+use serde::{Deserialize, Serialize};
+use tauri_plugin_http::reqwest::Url;
+
+/// Playlists and manifests of long videos with short segments run to a few megabytes.
+const MAX_PLAYLIST_BYTES: usize = 16 * 1024 * 1024;
+/// An AES-128 key is 16 bytes, anything much larger is not a key.
+const MAX_KEY_BYTES: usize = 1024;
+
+type Aes128CbcDec = cbc::Decryptor<aes::Aes128>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamKind {
+    Hls,
+    Dash,
+}
+
+impl fmt::Display for StreamKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            StreamKind::Hls => "hls",
+            StreamKind::Dash => "dash",
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// A rendition the user can pick from a master playlist or an MPD.
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamVariant {
+    /// Media playlist URL for HLS, representation id for DASH.
+    pub id: String,
+    pub bandwidth: Option<u64>,
+    pub resolution: Option<String>,
+    pub codecs: Option<String>,
+    pub mime_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamManifest {
+    pub kind: StreamKind,
+    /// Highest bandwidth first.
+    pub variants: Vec<StreamVariant>,
+}
+
+/// Segments of one variant, in the order they are joined.
+#[derive(Debug, Clone)]
+pub struct MediaPlaylist {
+    pub segments: Vec<MediaSegment>,
+    pub extension: String,
+    pub content_type: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaSegment {
+    pub url: String,
+    /// Inclusive byte range within `url`.
+    pub range: Option<(u64, u64)>,
+    pub key: Option<SegmentKey>,
+}
+
+/// AES-128-CBC key location and the IV the segment was encrypted with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SegmentKey {
+    pub url: String,
+    pub iv: [u8; 16],
+}
+
+impl super::Client {
+    /// Variants of the HLS playlist or DASH manifest this client points at.
+    pub async fn stream_manifest(&self) -> Result<StreamManifest, super::ClientError> {
+        let (url, text) = self.fetch_playlist(&self.url).await?;
+
+        if Self::is_hls(&text) {
+            return Ok(StreamManifest {
+                kind: StreamKind::Hls,
+                variants: Self::hls_variants(&url, &text)?,
+            });
+        }
+
+        if Self::is_dash(&text) {
+            return Ok(StreamManifest {
+                kind: StreamKind::Dash,
+                variants: Self::dash_variants(&text)?,
+            });
+        }
+
+        Err(super::ClientError::InvalidPlaylist(
+            "not an HLS playlist or DASH manifest".to_string(),
+        ))
+    }
+
+    /// Segments of the variant picked from `stream_manifest`.
+    pub async fn media_playlist(
+        &self,
+        kind: StreamKind,
+        variant_id: &str,
+    ) -> Result<MediaPlaylist, super::ClientError> {
+        match kind {
+            StreamKind::Hls => {
+                let (url, text) = self.fetch_playlist(variant_id).await?;
+                Self::hls_segments(&url, &text)
+            }
+            StreamKind::Dash => {
+                let (url, text) = self.fetch_playlist(&self.url).await?;
+                Self::dash_segments(&url, &text, variant_id)
+            }
+        }
+    }
+
+    /// Output file name for a stream, the playlist name with the media extension.
+    pub fn stream_file_name(&self, extension: &str) -> String {
+        let stem = Self::parse_url(&self.url).ok().and_then(|url| {
+            let name = url.path_segments()?.rfind(|s| !s.is_empty())?;
+            let name = String::from_utf8_lossy(&Self::percent_decode(name)).into_owned();
+            let stem = name
+                .rsplit_once('.')
+                .map_or(name.as_str(), |(stem, _)| stem);
+            Self::sanitize_file_name(stem)
+        });
+
+        format!("{}.{}", stem.as_deref().unwrap_or("stream"), extension)
+    }
+
+    pub async fn segment_key(&self, url: &str) -> Result<[u8; 16], super::ClientError> {
+        let (_, key) = self.fetch_bytes(url, MAX_KEY_BYTES).await?;
+
+        key.try_into().map_err(|key: Vec<u8>| {
+            super::ClientError::InvalidPlaylist(format!("key is {} bytes, not 16", key.len()))
+        })
+    }
+
+    /// Decrypts a whole AES-128 segment in place, dropping the PKCS7 padding.
+    pub fn decrypt_segment(
+        key: &[u8; 16],
+        iv: &[u8; 16],
+        data: &mut Vec<u8>,
+    ) -> Result<(), super::ClientError> {
+        let len = Aes128CbcDec::new(key.into(), iv.into())
+            .decrypt_padded_mut::<Pkcs7>(data)
+            .map_err(|_| super::ClientError::DecryptFailed)?
+            .len();
+
+        data.truncate(len);
+
+        Ok(())
+    }
+
+    async fn fetch_playlist(&self, url: &str) -> Result<(Url, String), super::ClientError> {
+        let (url, body) = self.fetch_bytes(url, MAX_PLAYLIST_BYTES).await?;
+        Ok((url, String::from_utf8_lossy(&body).into_owned()))
+    }
+
+    pub(super) fn join_url(base: &Url, reference: &str) -> Result<Url, super::ClientError> {
+        base.join(reference.trim())
+            .map_err(|_| super::ClientError::InvalidPlaylist(format!("bad url {}", reference)))
+    }
+}
@@ -0,0 +1,33 @@
+/// How an FTP session protects its control and data connections.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FtpSecurity {
+    /// `ftp://`, everything in the clear.
+    None,
+    /// `ftpes://`, a plain connection upgraded with `AUTH TLS`.
+    Explicit,
+    /// `ftps://`, TLS from the first byte on port 990.
+    Implicit,
+}
+
+/// Transport a `Client` speaks, picked from the URL scheme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    Http,
+    Ftp(FtpSecurity),
+    Sftp,
+}
+
+impl Protocol {
+    pub fn from_url(url: &str) -> Option<Self> {
+        let (scheme, _) = url.split_once("://")?;
+
+        match scheme.to_ascii_lowercase().as_str() {
+            "http" | "https" => Some(Protocol::Http),
+            "ftp" => Some(Protocol::Ftp(FtpSecurity::None)),
+            "ftpes" => Some(Protocol::Ftp(FtpSecurity::Explicit)),
+            "ftps" => Some(Protocol::Ftp(FtpSecurity::Implicit)),
+            "sftp" => Some(Protocol::Sftp),
+            _ => None,
+        }
+    }
+}
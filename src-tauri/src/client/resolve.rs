@@ -0,0 +1,36 @@
+use tauri_plugin_http::reqwest::Url;
+
+/// A resolver answers with one link, anything longer is not an answer.
+const MAX_RESOLVED_BYTES: usize = 64 * 1024;
+
+impl super::Client {
+    /// Asks the resolver this client points at for a fresh link to `url`.
+    pub async fn resolve_url(
+        &self,
+        url: &str,
+        referer: Option<&str>,
+    ) -> Result<String, super::ClientError> {
+        let mut endpoint = Url::parse(&self.url)
+            .map_err(|e| super::ClientError::InvalidResolvedUrl(e.to_string()))?;
+
+        endpoint.query_pairs_mut().append_pair("url", url);
+        if let Some(referer) = referer {
+            endpoint.query_pairs_mut().append_pair("referer", referer);
+        }
+
+        let body = self
+            .fetch_text(endpoint.as_str(), MAX_RESOLVED_BYTES)
+            .await?;
+
+        let resolved = body.lines().map(str::trim).find(|line| !line.is_empty());
+
+        match resolved.map(Url::parse) {
+            Some(Ok(resolved)) if matches!(resolved.scheme(), "http" | "https") => {
+                Ok(resolved.to_string())
+            }
+            _ => Err(super::ClientError::InvalidResolvedUrl(
+                body.chars().take(200).collect(),
+            )),
+        }
+    }
+}
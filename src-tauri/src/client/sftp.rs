@@ -0,0 +1,411 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    net::{TcpStream, ToSocketAddrs},
+    path::{Path, PathBuf},
+    pin::Pin,
+    time::Duration,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::DateTime;
+use futures_util::{stream, Stream};
+use ssh2::{CheckResult, HashType, HostKeyType, KnownHostFileKind, Session, Sftp};
+use tauri_plugin_http::reqwest::Url;
+use tokio::{sync::mpsc, task::spawn_blocking};
+use tokio_util::bytes::Bytes;
+
+use super::{inspect::HTTP_DATE, AuthType, ClientError, InspectResponse};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Blocking calls on a session give up after this long.
+const SESSION_TIMEOUT_MS: u32 = 30_000;
+
+const READ_BUFFER_BYTES: usize = 64 * 1024;
+
+/// An authenticated session on a host whose key is in `known_hosts`.
+/// libssh2 blocks, so everything here runs on the blocking pool.
+struct SftpSession {
+    // Keeps the connection open for as long as `sftp` is used.
+    _session: Session,
+    sftp: Sftp,
+}
+
+impl SftpSession {
+    fn connect(url: &Url, auth: &Option<AuthType>) -> Result<Self, ClientError> {
+        let (host, port) = Self::address(url)?;
+        let session = Self::handshake(&host, port)?;
+
+        Self::verify_host_key(&session, &host, port)?;
+        Self::authenticate(&session, url, auth)?;
+
+        let sftp = session.sftp()?;
+
+        Ok(Self {
+            _session: session,
+            sftp,
+        })
+    }
+
+    fn address(url: &Url) -> Result<(String, u16), ClientError> {
+        let host = url
+            .host_str()
+            .map(|host| host.trim_start_matches('[').trim_end_matches(']'))
+            .ok_or_else(|| ClientError::InvalidUrl(url.to_string()))?;
+
+        Ok((host.to_string(), url.port().unwrap_or(22)))
+    }
+
+    fn handshake(host: &str, port: u16) -> Result<Session, ClientError> {
+        let mut last_error = io::Error::new(io::ErrorKind::NotFound, "host has no address");
+        let mut tcp = None;
+
+        for address in (host, port).to_socket_addrs()? {
+            match TcpStream::connect_timeout(&address, CONNECT_TIMEOUT) {
+                Ok(stream) => {
+                    tcp = Some(stream);
+                    break;
+                }
+                Err(err) => last_error = err,
+            }
+        }
+
+        let mut session = Session::new()?;
+        session.set_timeout(SESSION_TIMEOUT_MS);
+        session.set_tcp_stream(tcp.ok_or(last_error)?);
+        session.handshake()?;
+
+        Ok(session)
+    }
+
+    fn verify_host_key(session: &Session, host: &str, port: u16) -> Result<(), ClientError> {
+        match Self::check_host_key(session, host, port)? {
+            CheckResult::Match => Ok(()),
+            CheckResult::Mismatch => Err(ClientError::HostKeyMismatch(host.to_string())),
+            CheckResult::NotFound | CheckResult::Failure => Err(ClientError::UnknownHostKey {
+                host: Self::host_entry(host, port),
+                fingerprint: Self::fingerprint(session),
+            }),
+        }
+    }
+
+    fn check_host_key(
+        session: &Session,
+        host: &str,
+        port: u16,
+    ) -> Result<CheckResult, ClientError> {
+        let (key, _) = session
+            .host_key()
+            .ok_or_else(|| ClientError::HostKeyMismatch(host.to_string()))?;
+
+        let mut known_hosts = session.known_hosts()?;
+
+        if let Some(path) = Self::known_hosts_path().filter(|path| path.exists()) {
+            known_hosts.read_file(&path, KnownHostFileKind::OpenSSH)?;
+        }
+
+        Ok(known_hosts.check_port(host, port, key))
+    }
+
+    /// `AuthType::Basic` logs in with a password, `AuthType::SshKey` with a key file
+    /// or, without one, whatever the SSH agent offers. The URL user fills in a
+    /// missing username.
+    fn authenticate(
+        session: &Session,
+        url: &Url,
+        auth: &Option<AuthType>,
+    ) -> Result<(), ClientError> {
+        let url_username = || {
+            let username = super::Client::percent_decode(url.username());
+            String::from_utf8_lossy(&username).into_owned()
+        };
+        let username = |username: &str| match username.is_empty() {
+            true => url_username(),
+            false => username.to_string(),
+        };
+
+        match auth {
+            Some(AuthType::Basic {
+                username: user,
+                password,
+            }) => {
+                session.userauth_password(&username(user), password)?;
+            }
+            Some(AuthType::SshKey {
+                username: user,
+                private_key,
+                passphrase,
+            }) => {
+                let user = username(user);
+
+                match private_key.as_deref().filter(|path| !path.is_empty()) {
+                    Some(path) => session.userauth_pubkey_file(
+                        &user,
+                        None,
+                        Path::new(path),
+                        passphrase.as_deref().filter(|p| !p.is_empty()),
+                    )?,
+                    None => session.userauth_agent(&user)?,
+                }
+            }
+            _ => match url.password() {
+                Some(password) => {
+                    let password = super::Client::percent_decode(password);
+                    session
+                        .userauth_password(&url_username(), &String::from_utf8_lossy(&password))?
+                }
+                None => session.userauth_agent(&url_username())?,
+            },
+        }
+
+        if !session.authenticated() {
+            return Err(ClientError::SshAuthFailed);
+        }
+
+        Ok(())
+    }
+
+    fn modified(&self, path: &str) -> Result<Option<String>, ClientError> {
+        Ok(self
+            .sftp
+            .stat(Path::new(path))?
+            .mtime
+            .and_then(|mtime| DateTime::from_timestamp(mtime as i64, 0))
+            .map(|time| time.format(HTTP_DATE).to_string()))
+    }
+
+    fn known_hosts_path() -> Option<PathBuf> {
+        std::env::var_os("HOME")
+            .or_else(|| std::env::var_os("USERPROFILE"))
+            .map(|home| PathBuf::from(home).join(".ssh").join("known_hosts"))
+    }
+
+    /// How OpenSSH names a host in `known_hosts`, with the port only when it isn't 22.
+    fn host_entry(host: &str, port: u16) -> String {
+        match port {
+            22 => host.to_string(),
+            port => format!("[{}]:{}", host, port),
+        }
+    }
+
+    /// The `SHA256:...` form `ssh` prints when it meets an unknown host.
+    fn fingerprint(session: &Session) -> String {
+        let hash = session.host_key_hash(HashType::Sha256).unwrap_or_default();
+        format!("SHA256:{}", STANDARD.encode(hash).trim_end_matches('='))
+    }
+
+    fn key_type_name(key_type: HostKeyType) -> Option<&'static str> {
+        match key_type {
+            HostKeyType::Rsa => Some("ssh-rsa"),
+            HostKeyType::Dss => Some("ssh-dss"),
+            HostKeyType::Ecdsa256 => Some("ecdsa-sha2-nistp256"),
+            HostKeyType::Ecdsa384 => Some("ecdsa-sha2-nistp384"),
+            HostKeyType::Ecdsa521 => Some("ecdsa-sha2-nistp521"),
+            HostKeyType::Ed25519 => Some("ssh-ed25519"),
+            HostKeyType::Unknown => None,
+        }
+    }
+
+    /// Appends the host's current key to `known_hosts` when it still has the
+    /// fingerprint the user confirmed and the host has no key there yet. A changed
+    /// key stays a mismatch, it has to be removed from the file by hand. The file
+    /// is only appended to, so lines libssh2 can't parse survive.
+    fn trust(url: &Url, fingerprint: &str) -> Result<(), ClientError> {
+        let (host, port) = Self::address(url)?;
+        let session = Self::handshake(&host, port)?;
+
+        let actual = Self::fingerprint(&session);
+        if actual != fingerprint {
+            return Err(ClientError::HostKeyMismatch(host));
+        }
+
+        match Self::check_host_key(&session, &host, port)? {
+            CheckResult::NotFound => {}
+            CheckResult::Match => return Ok(()),
+            CheckResult::Mismatch | CheckResult::Failure => {
+                return Err(ClientError::HostKeyMismatch(host))
+            }
+        }
+
+        let (key, key_type) = session
+            .host_key()
+            .ok_or_else(|| ClientError::HostKeyMismatch(host.clone()))?;
+
+        let key_type = Self::key_type_name(key_type)
+            .ok_or_else(|| ClientError::HostKeyMismatch(host.clone()))?;
+
+        let path = Self::known_hosts_path().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "no home directory for known_hosts")
+        })?;
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        writeln!(
+            file,
+            "{} {} {}",
+            Self::host_entry(&host, port),
+            key_type,
+            STANDARD.encode(key)
+        )?;
+
+        Ok(())
+    }
+}
+
+/// SFTP through `stat` for inspection and read offsets for ranges. Each stream runs
+/// its own session, custom headers don't apply and `Client::new` refuses a proxy.
+impl super::Client {
+    pub(super) async fn sftp_inspect(&self) -> Result<InspectResponse, ClientError> {
+        let url = Self::parse_url(&self.url)?;
+        let auth = self.auth.clone();
+
+        let (url, content_length, last_modified) = Self::blocking(move || {
+            let session = SftpSession::connect(&url, &auth)?;
+            let path = Self::sftp_path(&url);
+            let stat = session.sftp.stat(Path::new(&path))?;
+
+            if stat.is_dir() {
+                return Err(ClientError::InvalidUrl(format!("{} is a directory", url)));
+            }
+
+            let last_modified = session.modified(&path)?;
+            Ok((url, stat.size.unwrap_or(0), last_modified))
+        })
+        .await?;
+
+        Ok(Self::path_response(
+            &url,
+            content_length,
+            content_length > 0,
+            last_modified,
+        ))
+    }
+
+    /// SFTP has no `If-Range`, the modification time taken at inspection plays its part.
+    pub(super) async fn sftp_stream(
+        &self,
+        range: Option<(i64, i64)>,
+        if_range: Option<&str>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<Bytes, ClientError>> + Send>>, ClientError> {
+        let url = Self::parse_url(&self.url)?;
+        let auth = self.auth.clone();
+        let if_range = if_range.map(str::to_string);
+
+        let start = range.map(|(start, _)| start as u64).unwrap_or(0);
+        let mut remaining = range
+            .map(|(start, end)| (end - start + 1) as u64)
+            .unwrap_or(u64::MAX);
+
+        // Opened before returning so a missing file or a changed one fails the request,
+        // as a bad status would over HTTP.
+        let (session, mut file) = Self::blocking(move || {
+            let session = SftpSession::connect(&url, &auth)?;
+            let path = Self::sftp_path(&url);
+
+            if let Some(validator) = if_range {
+                if session
+                    .modified(&path)?
+                    .is_some_and(|modified| modified != validator)
+                {
+                    return Err(ClientError::RemoteChanged);
+                }
+            }
+
+            let mut file = session.sftp.open(Path::new(&path))?;
+            file.seek(SeekFrom::Start(start))?;
+
+            Ok((session, file))
+        })
+        .await?;
+
+        let (sender, receiver) = mpsc::channel(4);
+
+        spawn_blocking(move || {
+            let _session = session;
+            let mut buffer = vec![0; READ_BUFFER_BYTES];
+
+            while remaining > 0 {
+                let size = remaining.min(READ_BUFFER_BYTES as u64) as usize;
+
+                let bytes = match file.read(&mut buffer[..size]) {
+                    Ok(0) => break,
+                    Ok(read) => {
+                        remaining -= read as u64;
+                        Ok(Bytes::copy_from_slice(&buffer[..read]))
+                    }
+                    Err(err) => Err(ClientError::from(err)),
+                };
+
+                let failed = bytes.is_err();
+
+                // The receiver is gone once the chunk finished, paused or timed out.
+                if sender.blocking_send(bytes).is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        let stream = stream::unfold(receiver, |mut receiver| async move {
+            receiver.recv().await.map(|bytes| (bytes, receiver))
+        });
+
+        Ok(Box::pin(stream))
+    }
+
+    pub(super) async fn sftp_fetch_text(
+        &self,
+        url: &str,
+        max_bytes: usize,
+    ) -> Result<String, ClientError> {
+        let url = Self::parse_url(url)?;
+        let auth = self.auth.clone();
+
+        let body = Self::blocking(move || {
+            let session = SftpSession::connect(&url, &auth)?;
+            let file = session.sftp.open(Path::new(&Self::sftp_path(&url)))?;
+
+            let mut body = Vec::new();
+            file.take(max_bytes as u64 + 1).read_to_end(&mut body)?;
+
+            Ok(body)
+        })
+        .await?;
+
+        if body.len() > max_bytes {
+            return Err(ClientError::BodyTooLarge(max_bytes));
+        }
+
+        Ok(String::from_utf8_lossy(&body).into_owned())
+    }
+
+    /// Adds the key an SSH host presents to `known_hosts`, for when the user checked
+    /// the fingerprint an `UnknownHostKey` error reported.
+    pub async fn trust_host_key(url: &str, fingerprint: &str) -> Result<(), ClientError> {
+        let url = Self::parse_url(url)?;
+        let fingerprint = fingerprint.trim().to_string();
+
+        Self::blocking(move || SftpSession::trust(&url, &fingerprint)).await
+    }
+
+    /// Paths are absolute, `/~/` makes them relative to the login directory.
+    fn sftp_path(url: &Url) -> String {
+        let path = String::from_utf8_lossy(&Self::percent_decode(url.path())).into_owned();
+
+        match path.strip_prefix("/~/") {
+            Some(relative) => relative.to_string(),
+            None => path,
+        }
+    }
+
+    async fn blocking<T: Send + 'static>(
+        f: impl FnOnce() -> Result<T, ClientError> + Send + 'static,
+    ) -> Result<T, ClientError> {
+        spawn_blocking(f)
+            .await
+            .map_err(|err| ClientError::from(io::Error::other(err)))?
+    }
+}
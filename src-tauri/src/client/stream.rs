@@ -1,34 +1,225 @@
 use std::pin::Pin;
 
 use futures_util::{Stream, StreamExt};
-use tauri::http::{header::RANGE, Method};
+use tauri::http::{
+    header::{CONTENT_RANGE, ETAG, IF_RANGE, LAST_MODIFIED, RANGE},
+    HeaderMap, Method, StatusCode,
+};
 use tokio_util::bytes::Bytes;
 
 impl super::Client {
     pub async fn stream(
         &self,
         range: Option<(i64, i64)>,
+        if_range: Option<&str>,
     ) -> Result<
         Pin<Box<dyn Stream<Item = Result<Bytes, super::ClientError>> + Send>>,
         super::ClientError,
     > {
-        let request = self.client.request(Method::GET, &self.url);
+        if let super::Protocol::Ftp(security) = self.protocol {
+            return self.ftp_stream(security, range, if_range).await;
+        }
+
+        if self.protocol == super::Protocol::Sftp {
+            return self.sftp_stream(range, if_range).await;
+        }
+
+        self.http_stream(&self.url, range, if_range).await
+    }
+
+    /// One segment of an HLS or DASH stream. Segments share the client of their
+    /// playlist, so they reuse its connections, auth and headers.
+    pub async fn stream_segment(
+        &self,
+        url: &str,
+        range: Option<(i64, i64)>,
+    ) -> Result<
+        Pin<Box<dyn Stream<Item = Result<Bytes, super::ClientError>> + Send>>,
+        super::ClientError,
+    > {
+        self.http_stream(url, range, None).await
+    }
+
+    async fn http_stream(
+        &self,
+        url: &str,
+        range: Option<(i64, i64)>,
+        if_range: Option<&str>,
+    ) -> Result<
+        Pin<Box<dyn Stream<Item = Result<Bytes, super::ClientError>> + Send>>,
+        super::ClientError,
+    > {
+        let request = self.client.request(Method::GET, url);
         let request = Self::auth_handler(request, &self.auth);
 
         let request = if let Some(range) = range {
             let (start, end) = range;
             let range_header = format!("bytes={}-{}", start, end);
-            request.header(RANGE, range_header)
+            let request = request.header(RANGE, range_header);
+
+            match if_range {
+                Some(validator) => request.header(IF_RANGE, validator),
+                None => request,
+            }
         } else {
             request
         };
 
         let response = request.send().await?;
+        let status = response.status();
+
+        if !status.is_success() {
+            return Err(super::ClientError::http(status, response.headers()));
+        }
+
+        if let Some((start, end)) = range {
+            match status {
+                // With `If-Range` a full response may mean the validator no longer matches,
+                // so the bytes on disk belong to a different file. A server that simply
+                // ignores ranges answers the same way, but still with our validator.
+                StatusCode::OK
+                    if if_range.is_some_and(|v| Self::validator_changed(v, response.headers())) =>
+                {
+                    return Err(super::ClientError::RemoteChanged);
+                }
+                StatusCode::PARTIAL_CONTENT => {
+                    let content_range = response
+                        .headers()
+                        .get(CONTENT_RANGE)
+                        .and_then(|v| v.to_str().ok());
+
+                    Self::check_content_range(content_range, start, end)?;
+                }
+                _ => return Err(super::ClientError::RangeNotSupported),
+            }
+        }
 
         let stream = response
             .bytes_stream()
             .map(|res| res.map_err(super::ClientError::from));
 
-        return Ok(Box::pin(stream));
+        Ok(Box::pin(stream))
+    }
+
+    /// Whether a full response carries another validator than the one sent in `If-Range`.
+    /// A strong ETag is compared with the ETag, anything else is a `Last-Modified` date.
+    /// A response without the header can't tell us, so it isn't taken as a change.
+    fn validator_changed(validator: &str, headers: &HeaderMap) -> bool {
+        let header = if validator.starts_with('"') {
+            ETAG
+        } else {
+            LAST_MODIFIED
+        };
+
+        headers
+            .get(header)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.trim() != validator.trim())
+    }
+
+    /// The server may send less than asked for, never a different start or more.
+    fn check_content_range(
+        content_range: Option<&str>,
+        start: i64,
+        end: i64,
+    ) -> Result<(), super::ClientError> {
+        let received = content_range
+            .and_then(|v| v.trim().strip_prefix("bytes "))
+            .and_then(|v| v.split_once('/'))
+            .and_then(|(range, _)| range.split_once('-'))
+            .and_then(|(s, e)| {
+                Some((s.trim().parse::<i64>().ok()?, e.trim().parse::<i64>().ok()?))
+            });
+
+        match received {
+            Some((s, e)) if s == start && e <= end => Ok(()),
+            _ => Err(super::ClientError::RangeMismatch {
+                requested: format!("{}-{}", start, end),
+                received: content_range.unwrap_or("none").to_string(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tauri::http::{
+        header::{ETAG, LAST_MODIFIED},
+        HeaderMap, HeaderName, HeaderValue,
+    };
+
+    use crate::client::{Client, ClientError};
+
+    fn check(content_range: Option<&str>) -> Result<(), ClientError> {
+        Client::check_content_range(content_range, 100, 199)
+    }
+
+    #[test]
+    fn accepts_the_requested_range() {
+        assert!(check(Some("bytes 100-199/1000")).is_ok());
+        assert!(check(Some(" bytes 100 - 199/*")).is_ok());
+    }
+
+    #[test]
+    fn accepts_a_shorter_range() {
+        assert!(check(Some("bytes 100-149/150")).is_ok());
+    }
+
+    #[test]
+    fn rejects_another_start_or_a_longer_range() {
+        assert!(matches!(
+            check(Some("bytes 0-199/1000")),
+            Err(ClientError::RangeMismatch { .. })
+        ));
+        assert!(matches!(
+            check(Some("bytes 100-299/1000")),
+            Err(ClientError::RangeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_missing_or_malformed_ranges() {
+        assert!(matches!(
+            check(None),
+            Err(ClientError::RangeMismatch { received, .. }) if received == "none"
+        ));
+        assert!(check(Some("bytes */1000")).is_err());
+        assert!(check(Some("items 100-199/1000")).is_err());
+        assert!(check(Some("bytes 100-199")).is_err());
+    }
+
+    fn headers(name: HeaderName, value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn full_response_with_the_same_validator_is_not_a_change() {
+        let etag = headers(ETAG, "\"abc\"");
+        assert!(!Client::validator_changed("\"abc\"", &etag));
+
+        let date = "Wed, 21 Oct 2015 07:28:00 GMT";
+        let modified = headers(LAST_MODIFIED, date);
+        assert!(!Client::validator_changed(date, &modified));
+    }
+
+    #[test]
+    fn full_response_with_another_validator_is_a_change() {
+        let etag = headers(ETAG, "\"def\"");
+        assert!(Client::validator_changed("\"abc\"", &etag));
+
+        let modified = headers(LAST_MODIFIED, "Thu, 22 Oct 2015 07:28:00 GMT");
+        assert!(Client::validator_changed(
+            "Wed, 21 Oct 2015 07:28:00 GMT",
+            &modified
+        ));
+    }
+
+    #[test]
+    fn full_response_without_a_validator_is_not_a_change() {
+        assert!(!Client::validator_changed("\"abc\"", &HeaderMap::new()));
+        let modified = headers(LAST_MODIFIED, "Wed, 21 Oct 2015 07:28:00 GMT");
+        assert!(!Client::validator_changed("\"abc\"", &modified));
     }
 }
@@ -0,0 +1,57 @@
+use futures_util::StreamExt;
+use tauri::http::Method;
+use tauri_plugin_http::reqwest::Url;
+
+impl super::Client {
+    /// Body of a small text file such as a checksum list or a metalink,
+    /// refusing anything larger than `max_bytes`.
+    pub(super) async fn fetch_text(
+        &self,
+        url: &str,
+        max_bytes: usize,
+    ) -> Result<String, super::ClientError> {
+        match super::Protocol::from_url(url) {
+            Some(super::Protocol::Ftp(security)) => {
+                return self.ftp_fetch_text(url, security, max_bytes).await;
+            }
+            Some(super::Protocol::Sftp) => return self.sftp_fetch_text(url, max_bytes).await,
+            _ => {}
+        }
+
+        let (_, body) = self.fetch_bytes(url, max_bytes).await?;
+
+        Ok(String::from_utf8_lossy(&body).into_owned())
+    }
+
+    /// Body of a small HTTP resource along with the URL it was served from,
+    /// which is what relative links inside it resolve against.
+    pub(super) async fn fetch_bytes(
+        &self,
+        url: &str,
+        max_bytes: usize,
+    ) -> Result<(Url, Vec<u8>), super::ClientError> {
+        let request = self.client.request(Method::GET, url);
+        let request = Self::auth_handler(request, &self.auth);
+
+        let response = request.send().await?;
+        let status = response.status();
+
+        if !status.is_success() {
+            return Err(super::ClientError::http(status, response.headers()));
+        }
+
+        let final_url = response.url().clone();
+        let mut body = Vec::new();
+        let mut stream = response.bytes_stream();
+
+        while let Some(bytes) = stream.next().await {
+            body.extend_from_slice(&bytes?);
+
+            if body.len() > max_bytes {
+                return Err(super::ClientError::BodyTooLarge(max_bytes));
+            }
+        }
+
+        Ok((final_url, body))
+    }
+}
@@ -1,7 +1,12 @@
 use crate::{
+    client::{Client, StreamKind, StreamManifest},
     dispatch,
     manager::{DownloadOptions, DownloadsManager},
-    models::Download,
+    models::{
+        BandwidthSettings, Checksum, DiskSettings, Download, DownloadMirror, ResolverSettings,
+        RetrySettings, VerifySettings,
+    },
+    registry::Registry,
     repository::download::DownloadRepository,
 };
 
@@ -10,6 +15,29 @@ pub async fn add_new_download(url: String, options: DownloadOptions) -> Result<(
     DownloadsManager::add_new_download(url, options).await
 }
 
+#[tauri::command]
+pub async fn add_metalink_download(source: String, options: DownloadOptions) -> Result<(), String> {
+    DownloadsManager::add_metalink_download(source, options).await
+}
+
+#[tauri::command]
+pub async fn inspect_stream(
+    url: String,
+    options: DownloadOptions,
+) -> Result<StreamManifest, String> {
+    DownloadsManager::inspect_stream(url, options).await
+}
+
+#[tauri::command]
+pub async fn add_stream_download(
+    url: String,
+    kind: StreamKind,
+    variant_id: String,
+    options: DownloadOptions,
+) -> Result<(), String> {
+    DownloadsManager::add_stream_download(url, kind, variant_id, options).await
+}
+
 #[tauri::command]
 pub async fn get_download_list() -> Result<Vec<Download>, String> {
     DownloadRepository::find_all(None)
@@ -18,16 +46,113 @@ pub async fn get_download_list() -> Result<Vec<Download>, String> {
 }
 
 #[tauri::command]
-pub fn pause_download(id: i64) {
-    dispatch!(registry, PauseDownload, (id));
+pub fn pause_download(id: i64) -> Result<(), String> {
+    dispatch!(registry, PauseDownload, (id)).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn resume_download(id: i64) -> Result<(), String> {
+    dispatch!(registry, ResumeDownload, (id)).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn restart_download(id: i64) -> Result<(), String> {
+    DownloadsManager::restart_download(id).await
+}
+
+#[tauri::command]
+pub async fn verify_download(id: i64, checksum: Option<Checksum>) -> Result<(), String> {
+    DownloadsManager::verify_download(id, checksum).await
+}
+
+#[tauri::command]
+pub async fn refresh_download_url(id: i64, url: String) -> Result<(), String> {
+    DownloadsManager::refresh_download_url(id, url).await
+}
+
+#[tauri::command]
+pub async fn trust_host_key(url: String, fingerprint: String) -> Result<(), String> {
+    Client::trust_host_key(&url, &fingerprint)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_download_mirrors(id: i64) -> Result<Vec<DownloadMirror>, String> {
+    DownloadsManager::get_download_mirrors(id).await
+}
+
+#[tauri::command]
+pub async fn add_download_mirror(id: i64, url: String) -> Result<(), String> {
+    DownloadsManager::add_download_mirror(id, url).await
+}
+
+#[tauri::command]
+pub async fn remove_download_mirror(id: i64, url: String) -> Result<(), String> {
+    DownloadsManager::remove_download_mirror(id, url).await
+}
+
+#[tauri::command]
+pub fn update_speed_limit(id: i64, speed_limit: Option<i64>) -> Result<(), String> {
+    dispatch!(registry, UpdateSpeedLimit, (id, speed_limit)).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn remove_download(id: i64, remove_file: bool) -> Result<(), String> {
+    dispatch!(registry, RemoveDownload, (id, remove_file)).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_bandwidth_settings() -> Result<BandwidthSettings, String> {
+    let settings = Registry::get_state().bandwidth_settings.read().await;
+    Ok(settings.clone())
+}
+
+#[tauri::command]
+pub async fn update_bandwidth_settings(settings: BandwidthSettings) -> Result<(), String> {
+    DownloadsManager::update_bandwidth_settings(settings).await
+}
+
+#[tauri::command]
+pub async fn get_disk_settings() -> Result<DiskSettings, String> {
+    let settings = Registry::get_state().disk_settings.read().await;
+    Ok(settings.clone())
+}
+
+#[tauri::command]
+pub async fn update_disk_settings(settings: DiskSettings) -> Result<(), String> {
+    DownloadsManager::update_disk_settings(settings).await
+}
+
+#[tauri::command]
+pub async fn get_resolver_settings() -> Result<ResolverSettings, String> {
+    let settings = Registry::get_state().resolver_settings.read().await;
+    Ok(settings.clone())
+}
+
+#[tauri::command]
+pub async fn update_resolver_settings(settings: ResolverSettings) -> Result<(), String> {
+    DownloadsManager::update_resolver_settings(settings).await
+}
+
+#[tauri::command]
+pub async fn get_verify_settings() -> Result<VerifySettings, String> {
+    let settings = Registry::get_state().verify_settings.read().await;
+    Ok(settings.clone())
+}
+
+#[tauri::command]
+pub async fn update_verify_settings(settings: VerifySettings) -> Result<(), String> {
+    DownloadsManager::update_verify_settings(settings).await
 }
 
 #[tauri::command]
-pub fn resume_download(id: i64) {
-    dispatch!(registry, ResumeDownload, (id));
+pub async fn get_retry_settings() -> Result<RetrySettings, String> {
+    let settings = Registry::get_state().retry_settings.read().await;
+    Ok(settings.clone())
 }
 
 #[tauri::command]
-pub fn remove_download(id: i64, remove_file: bool) {
-    dispatch!(registry, RemoveDownload, (id, remove_file));
+pub async fn update_retry_settings(settings: RetrySettings) -> Result<(), String> {
+    DownloadsManager::update_retry_settings(settings).await
 }
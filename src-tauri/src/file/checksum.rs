@@ -0,0 +1,103 @@
+use std::{
+    fs,
+    io::{self, Read},
+};
+
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use tokio::task;
+
+use super::integrity::read_exact_at;
+use crate::models::{ChecksumAlgorithm, DownloadPiece};
+
+/// Read size while hashing, large enough to keep the disk streaming.
+const READ_BUFFER_SIZE: usize = 1024 * 1024;
+
+impl super::File {
+    /// Hex digest of the whole file, read on the blocking pool.
+    pub async fn checksum(file_path: &str, algorithm: ChecksumAlgorithm) -> io::Result<String> {
+        let file_path = file_path.to_string();
+
+        task::spawn_blocking(move || {
+            let file = fs::File::open(&file_path)?;
+
+            match algorithm {
+                ChecksumAlgorithm::Sha256 => digest_file::<Sha256>(file),
+                ChecksumAlgorithm::Sha1 => digest_file::<Sha1>(file),
+                ChecksumAlgorithm::Md5 => digest_file::<Md5>(file),
+                ChecksumAlgorithm::Blake3 => {
+                    let mut hasher = blake3::Hasher::new();
+                    read_blocks(file, |block| {
+                        hasher.update(block);
+                    })?;
+                    Ok(hasher.finalize().to_hex().to_string())
+                }
+            }
+        })
+        .await
+        .map_err(io::Error::other)?
+    }
+
+    /// Pieces whose bytes in the file don't hash to the published digest.
+    /// A file too short to hold a piece counts it as damaged.
+    pub async fn damaged_pieces(
+        file_path: &str,
+        pieces: Vec<DownloadPiece>,
+    ) -> io::Result<Vec<DownloadPiece>> {
+        let file_path = file_path.to_string();
+
+        task::spawn_blocking(move || {
+            let file = fs::File::open(&file_path)?;
+            let mut buffer = Vec::new();
+            let mut damaged = Vec::new();
+
+            for piece in pieces {
+                let Ok(algorithm) = piece.algorithm.parse::<ChecksumAlgorithm>() else {
+                    continue;
+                };
+
+                buffer.resize((piece.end_byte - piece.start_byte + 1) as usize, 0);
+
+                match read_exact_at(&file, &mut buffer, piece.start_byte as u64) {
+                    Ok(()) if digest_bytes(algorithm, &buffer) == piece.hash => {}
+                    Ok(()) => damaged.push(piece),
+                    Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => damaged.push(piece),
+                    Err(e) => return Err(e),
+                }
+            }
+
+            Ok(damaged)
+        })
+        .await
+        .map_err(io::Error::other)?
+    }
+}
+
+fn digest_bytes(algorithm: ChecksumAlgorithm, bytes: &[u8]) -> String {
+    match algorithm {
+        ChecksumAlgorithm::Sha256 => hex::encode(Sha256::digest(bytes)),
+        ChecksumAlgorithm::Sha1 => hex::encode(Sha1::digest(bytes)),
+        ChecksumAlgorithm::Md5 => hex::encode(Md5::digest(bytes)),
+        ChecksumAlgorithm::Blake3 => blake3::hash(bytes).to_hex().to_string(),
+    }
+}
+
+fn digest_file<D: Digest>(file: fs::File) -> io::Result<String> {
+    let mut hasher = D::new();
+    read_blocks(file, |block| hasher.update(block))?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn read_blocks(mut file: fs::File, mut f: impl FnMut(&[u8])) -> io::Result<()> {
+    let mut buffer = vec![0; READ_BUFFER_SIZE];
+
+    loop {
+        match file.read(&mut buffer) {
+            Ok(0) => return Ok(()),
+            Ok(n) => f(&buffer[..n]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
@@ -9,6 +9,6 @@ impl super::File {
             .ok_or("failed to get parent of the file path".to_string())?;
         let space = available_space(path).map_err(|e| e.to_string())?;
 
-        return Ok(space > total_bytes);
+        Ok(space > total_bytes)
     }
 }
@@ -0,0 +1,66 @@
+use std::{io, sync::Arc};
+
+use thiserror::Error;
+
+#[derive(Debug, Error, Clone)]
+pub enum DiskError {
+    #[error("disk is full")]
+    Full,
+
+    #[error("permission denied writing the file")]
+    PermissionDenied,
+
+    #[error("storage device was removed or is not ready")]
+    DeviceRemoved,
+
+    #[error("file writer is closed")]
+    WriterClosed,
+
+    #[error("{0}")]
+    Io(Arc<io::Error>),
+}
+
+impl From<io::Error> for DiskError {
+    fn from(value: io::Error) -> Self {
+        match value.kind() {
+            io::ErrorKind::StorageFull | io::ErrorKind::QuotaExceeded => return DiskError::Full,
+            io::ErrorKind::PermissionDenied | io::ErrorKind::ReadOnlyFilesystem => {
+                return DiskError::PermissionDenied
+            }
+            io::ErrorKind::BrokenPipe => return DiskError::WriterClosed,
+            _ => {}
+        }
+
+        match value.raw_os_error() {
+            Some(code) if Self::is_device_removed(code) => DiskError::DeviceRemoved,
+            _ => DiskError::Io(Arc::new(value)),
+        }
+    }
+}
+
+impl DiskError {
+    #[cfg(unix)]
+    fn is_device_removed(code: i32) -> bool {
+        [libc::ENXIO, libc::EIO, libc::ENODEV, libc::ESTALE].contains(&code)
+    }
+
+    #[cfg(windows)]
+    fn is_device_removed(code: i32) -> bool {
+        use windows_sys::Win32::Foundation::{
+            ERROR_DEVICE_NOT_CONNECTED, ERROR_DEV_NOT_EXIST, ERROR_NOT_READY,
+        };
+
+        [
+            ERROR_NOT_READY,
+            ERROR_DEV_NOT_EXIST,
+            ERROR_DEVICE_NOT_CONNECTED,
+        ]
+        .map(|error| error as i32)
+        .contains(&code)
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn is_device_removed(_code: i32) -> bool {
+        false
+    }
+}
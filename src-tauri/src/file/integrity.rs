@@ -0,0 +1,372 @@
+use std::{fs, io};
+
+use blake3::Hasher;
+
+use crate::models::ResumeVerification;
+
+/// Bytes covered by each stored hash.
+const INTEGRITY_BLOCK_SIZE: u64 = 1024 * 1024;
+
+const HASH_LEN: usize = blake3::OUT_LEN;
+
+/// Running blake3 hashes of everything a chunk wrote, one per block plus the
+/// unfinished last block, so a resume can tell exactly which bytes are still good.
+#[derive(Debug, Clone)]
+pub struct ChunkIntegrity {
+    start: u64,
+    len: u64,
+    /// Hashes of the complete blocks, `HASH_LEN` bytes each.
+    blocks: Vec<u8>,
+    current: Hasher,
+    /// Stored hashes of a resumed chunk, not yet checked against the file.
+    /// Empty when the previous run stored none.
+    unverified: Option<Vec<u8>>,
+    /// A write landed past the hashed region, nothing can be vouched for anymore.
+    broken: bool,
+}
+
+impl ChunkIntegrity {
+    pub fn new(start: u64) -> Self {
+        Self {
+            start,
+            len: 0,
+            blocks: Vec::new(),
+            current: Hasher::new(),
+            unverified: None,
+            broken: false,
+        }
+    }
+
+    /// A chunk that already wrote `downloaded_bytes`. Its hashes are only trusted
+    /// once `verify` has read the file again.
+    pub fn resume(start: u64, downloaded_bytes: u64, stored: Option<Vec<u8>>) -> Self {
+        Self {
+            len: downloaded_bytes,
+            unverified: (downloaded_bytes > 0).then(|| stored.unwrap_or_default()),
+            ..Self::new(start)
+        }
+    }
+
+    pub fn hashed_bytes(&self) -> u64 {
+        self.len
+    }
+
+    pub fn needs_check(&self) -> bool {
+        self.unverified.is_some()
+    }
+
+    /// Feeds bytes just written at `offset`. A retried stream may write bytes that
+    /// are already hashed again, only the part past the hashed region counts.
+    pub fn update(&mut self, offset: u64, bytes: &[u8]) {
+        let end = self.start + self.len;
+
+        if self.broken || self.needs_check() || offset + bytes.len() as u64 <= end {
+            return;
+        }
+
+        if offset > end {
+            self.broken = true;
+            return;
+        }
+
+        let mut data = &bytes[(end - offset) as usize..];
+
+        while !data.is_empty() {
+            let room = (INTEGRITY_BLOCK_SIZE - self.len % INTEGRITY_BLOCK_SIZE) as usize;
+            let take = room.min(data.len());
+
+            self.current.update(&data[..take]);
+            self.len += take as u64;
+            data = &data[take..];
+
+            if self.len.is_multiple_of(INTEGRITY_BLOCK_SIZE) {
+                self.blocks
+                    .extend_from_slice(self.current.finalize().as_bytes());
+                self.current.reset();
+            }
+        }
+    }
+
+    /// Hashes to persist with the chunk, the unfinished block last.
+    /// `None` when the hashes can't describe the written bytes.
+    pub fn snapshot(&self) -> Option<Vec<u8>> {
+        if self.broken {
+            return None;
+        }
+
+        if let Some(stored) = &self.unverified {
+            return (!stored.is_empty()).then(|| stored.clone());
+        }
+
+        let mut hashes = self.blocks.clone();
+        if !self.len.is_multiple_of(INTEGRITY_BLOCK_SIZE) {
+            hashes.extend_from_slice(self.current.finalize().as_bytes());
+        }
+
+        Some(hashes)
+    }
+
+    /// Reads the written region back and compares it to the stored hashes, returning
+    /// a state that covers only the bytes that still match. Blocks without a stored
+    /// hash are read in full to rebuild them, sampling only skips blocks we can check.
+    pub fn verify(
+        &self,
+        file: &fs::File,
+        mode: ResumeVerification,
+        sample_blocks: u64,
+    ) -> io::Result<Self> {
+        let Some(stored) = &self.unverified else {
+            return Ok(self.clone());
+        };
+
+        let count = self.len.div_ceil(INTEGRITY_BLOCK_SIZE);
+        let stored = (stored.len() as u64 == count * HASH_LEN as u64).then_some(stored);
+
+        // Evenly spread samples from a random offset, so repeated resumes cover different blocks.
+        let stride = match mode {
+            ResumeVerification::Full => 1,
+            ResumeVerification::Sampled => count.div_ceil(sample_blocks.max(1)).max(1),
+        };
+        let sample_offset = fastrand::u64(..stride);
+
+        let mut checked = Self::new(self.start);
+        let mut buffer = vec![0; INTEGRITY_BLOCK_SIZE as usize];
+
+        for index in 0..count {
+            let block_start = index * INTEGRITY_BLOCK_SIZE;
+            let block_len = (self.len - block_start).min(INTEGRITY_BLOCK_SIZE);
+            let expected =
+                stored.map(|s| &s[index as usize * HASH_LEN..(index as usize + 1) * HASH_LEN]);
+
+            // The last block is always read, it holds the bytes written most recently
+            // and reseeds the running hash.
+            let is_last = index + 1 == count;
+            let sampled = index % stride == sample_offset;
+
+            if let Some(expected) = expected.filter(|_| !is_last && !sampled) {
+                checked.blocks.extend_from_slice(expected);
+                checked.len += block_len;
+                continue;
+            }
+
+            let data = &mut buffer[..block_len as usize];
+
+            match read_exact_at(file, data, self.start + block_start) {
+                Ok(()) => {}
+                // The file is shorter than the chunk claims.
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+
+            if expected.is_some_and(|expected| blake3::hash(data).as_bytes() != expected) {
+                break;
+            }
+
+            checked.update(self.start + block_start, data);
+        }
+
+        Ok(checked)
+    }
+}
+
+pub(super) fn read_exact_at(file: &fs::File, buf: &mut [u8], offset: u64) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileExt;
+        file.read_exact_at(buf, offset)
+    }
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::FileExt;
+
+        let mut read = 0;
+        while read < buf.len() {
+            match file.seek_read(&mut buf[read..], offset + read as u64) {
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(n) => read += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::OpenOptions, io::Write, path::PathBuf};
+
+    use super::*;
+
+    const START: u64 = 10;
+    const BLOCK: usize = INTEGRITY_BLOCK_SIZE as usize;
+
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        /// `data` lands at `START`, like a chunk that doesn't begin the file.
+        fn new(name: &str, data: &[u8]) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "ferrix-integrity-{}-{}",
+                std::process::id(),
+                name
+            ));
+            let mut file = fs::File::create(&path).unwrap();
+            file.write_all(&[0; START as usize]).unwrap();
+            file.write_all(data).unwrap();
+            Self(path)
+        }
+
+        fn open(&self) -> fs::File {
+            OpenOptions::new().read(true).open(&self.0).unwrap()
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    fn data(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn hashed(data: &[u8]) -> ChunkIntegrity {
+        let mut integrity = ChunkIntegrity::new(START);
+        integrity.update(START, data);
+        integrity
+    }
+
+    #[test]
+    fn hashes_complete_blocks_and_the_partial_tail() {
+        let data = data(BLOCK + BLOCK / 2);
+        let snapshot = hashed(&data).snapshot().unwrap();
+
+        assert_eq!(snapshot.len(), 2 * HASH_LEN);
+        assert_eq!(
+            &snapshot[..HASH_LEN],
+            blake3::hash(&data[..BLOCK]).as_bytes()
+        );
+        assert_eq!(
+            &snapshot[HASH_LEN..],
+            blake3::hash(&data[BLOCK..]).as_bytes()
+        );
+    }
+
+    #[test]
+    fn split_writes_hash_like_one_write() {
+        let data = data(2 * BLOCK + 7);
+        let mut integrity = ChunkIntegrity::new(START);
+
+        for (i, piece) in data.chunks(BLOCK / 3 + 1).enumerate() {
+            integrity.update(START + (i * (BLOCK / 3 + 1)) as u64, piece);
+        }
+
+        assert_eq!(integrity.hashed_bytes(), data.len() as u64);
+        assert_eq!(integrity.snapshot(), hashed(&data).snapshot());
+    }
+
+    #[test]
+    fn rewritten_bytes_only_count_past_the_hashed_region() {
+        let data = data(BLOCK + 100);
+        let mut integrity = hashed(&data[..BLOCK - 50]);
+
+        // A retry resends bytes we already have, then some new ones.
+        integrity.update(START, &data[..10]);
+        integrity.update(START + BLOCK as u64 - 100, &data[BLOCK - 100..]);
+
+        assert_eq!(integrity.hashed_bytes(), data.len() as u64);
+        assert_eq!(integrity.snapshot(), hashed(&data).snapshot());
+    }
+
+    #[test]
+    fn a_gap_breaks_the_hashes() {
+        let mut integrity = hashed(&data(100));
+        integrity.update(START + 200, &data(10));
+
+        assert_eq!(integrity.snapshot(), None);
+    }
+
+    #[test]
+    fn resumed_chunk_ignores_writes_until_verified() {
+        let stored = hashed(&data(100)).snapshot();
+        let mut integrity = ChunkIntegrity::resume(START, 100, stored.clone());
+        integrity.update(START + 100, &data(10));
+
+        assert!(integrity.needs_check());
+        assert_eq!(integrity.hashed_bytes(), 100);
+        assert_eq!(integrity.snapshot(), stored);
+        assert!(!ChunkIntegrity::resume(START, 0, None).needs_check());
+    }
+
+    #[test]
+    fn verify_keeps_everything_that_matches() {
+        let data = data(2 * BLOCK + BLOCK / 2);
+        let file = TempFile::new("match", &data);
+        let stored = hashed(&data).snapshot();
+
+        let checked = ChunkIntegrity::resume(START, data.len() as u64, stored.clone())
+            .verify(&file.open(), ResumeVerification::Full, 0)
+            .unwrap();
+
+        assert!(!checked.needs_check());
+        assert_eq!(checked.hashed_bytes(), data.len() as u64);
+        assert_eq!(checked.snapshot(), stored);
+    }
+
+    #[test]
+    fn verify_stops_at_the_first_damaged_block() {
+        let mut data = data(3 * BLOCK);
+        let stored = hashed(&data).snapshot();
+        data[BLOCK + 5] ^= 0xff;
+        let file = TempFile::new("damaged", &data);
+
+        let checked = ChunkIntegrity::resume(START, data.len() as u64, stored)
+            .verify(&file.open(), ResumeVerification::Full, 0)
+            .unwrap();
+
+        assert_eq!(checked.hashed_bytes(), BLOCK as u64);
+    }
+
+    #[test]
+    fn sampled_verify_always_reads_the_last_block() {
+        let mut data = data(4 * BLOCK + 1);
+        let stored = hashed(&data).snapshot();
+        *data.last_mut().unwrap() ^= 0xff;
+        let file = TempFile::new("sampled", &data);
+
+        let checked = ChunkIntegrity::resume(START, data.len() as u64, stored)
+            .verify(&file.open(), ResumeVerification::Sampled, 1)
+            .unwrap();
+
+        assert_eq!(checked.hashed_bytes(), 4 * BLOCK as u64);
+    }
+
+    #[test]
+    fn verify_rebuilds_missing_hashes_from_the_file() {
+        let data = data(BLOCK + 3);
+        let file = TempFile::new("rebuild", &data);
+
+        let checked = ChunkIntegrity::resume(START, data.len() as u64, None)
+            .verify(&file.open(), ResumeVerification::Sampled, 1)
+            .unwrap();
+
+        assert_eq!(checked.hashed_bytes(), data.len() as u64);
+        assert_eq!(checked.snapshot(), hashed(&data).snapshot());
+    }
+
+    #[test]
+    fn verify_stops_where_the_file_ends() {
+        let data = data(2 * BLOCK);
+        let stored = hashed(&data).snapshot();
+        let file = TempFile::new("short", &data[..BLOCK + 10]);
+
+        let checked = ChunkIntegrity::resume(START, data.len() as u64, stored)
+            .verify(&file.open(), ResumeVerification::Full, 0)
+            .unwrap();
+
+        assert_eq!(checked.hashed_bytes(), BLOCK as u64);
+    }
+}
@@ -1,21 +1,28 @@
-use tokio::sync::mpsc;
+mod checksum;
 mod chunk;
 mod disk;
+mod error;
+mod integrity;
+mod part;
 mod path;
 mod remove;
+mod segments;
 mod writer;
 
-pub use writer::WriteMessage;
+pub use error::DiskError;
+pub use integrity::ChunkIntegrity;
+pub use writer::FileSender;
 
 #[derive(Clone, Debug)]
 pub struct File;
 
 impl File {
+    #[allow(clippy::new_ret_no_self)]
     pub async fn new(
         download_id: i64,
         file_path: &str,
         total_bytes: u64,
-    ) -> anyhow::Result<mpsc::UnboundedSender<WriteMessage>> {
+    ) -> anyhow::Result<FileSender> {
         Self::setup_file_writer(download_id, file_path, total_bytes).await
     }
 }
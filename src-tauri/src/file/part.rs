@@ -0,0 +1,46 @@
+use std::path::Path;
+
+use tokio::fs;
+
+/// Suffix of the file a download is written into until it completes.
+const PART_EXTENSION: &str = "ferrix-part";
+
+impl super::File {
+    pub fn part_path(file_path: &str) -> String {
+        format!("{file_path}.{PART_EXTENSION}")
+    }
+
+    /// Downloads started before part files existed were written to the final path,
+    /// move those aside so resuming picks them up.
+    pub async fn adopt_legacy_partial(file_path: &str) -> std::io::Result<()> {
+        let part_path = Self::part_path(file_path);
+
+        if Path::new(&part_path).exists() || !Path::new(file_path).exists() {
+            return Ok(());
+        }
+
+        fs::rename(file_path, part_path).await
+    }
+
+    pub fn remove_part_file(file_path: &str) -> std::io::Result<()> {
+        let part_path = Self::part_path(file_path);
+
+        if Path::new(&part_path).exists() {
+            return std::fs::remove_file(part_path);
+        }
+
+        Ok(())
+    }
+
+    /// Gives the finished file its real name. The writer may still hold the part file
+    /// open, which is fine since the handle follows the rename.
+    pub async fn finalize(file_path: &str) -> std::io::Result<()> {
+        let part_path = Self::part_path(file_path);
+
+        if !Path::new(&part_path).exists() {
+            return Ok(());
+        }
+
+        fs::rename(part_path, file_path).await
+    }
+}
@@ -12,9 +12,7 @@ impl super::File {
             .home_dir()
             .map_err(|e| e.to_string())?;
 
-        let mut download_dir = home_dir
-            .join("Downloads".to_string())
-            .join("ferrix".to_string());
+        let mut download_dir = home_dir.join("Downloads").join("ferrix");
 
         if fs::metadata(&download_dir).await.is_err() {
             fs::create_dir_all(&download_dir)
@@ -41,7 +39,7 @@ impl super::File {
     pub async fn get_available_filename(full_path: &str) -> Result<String, String> {
         let path = Path::new(full_path);
 
-        if fs::metadata(path).await.is_err() {
+        if !Self::is_path_taken(full_path).await {
             return Ok(full_path.to_string());
         }
 
@@ -57,11 +55,19 @@ impl super::File {
 
             let candidate = dir.join(file_name);
 
-            if fs::metadata(&candidate).await.is_err() {
-                return Ok(candidate.to_string_lossy().into_owned());
+            let candidate = candidate.to_string_lossy().into_owned();
+
+            if !Self::is_path_taken(&candidate).await {
+                return Ok(candidate);
             }
         }
 
         Err("cannot available filename".to_string())
     }
+
+    /// A path is taken by a finished file as well as by a download still writing to it.
+    async fn is_path_taken(full_path: &str) -> bool {
+        fs::metadata(full_path).await.is_ok()
+            || fs::metadata(Self::part_path(full_path)).await.is_ok()
+    }
 }
@@ -1,9 +1,12 @@
 use std::fs;
 
 impl super::File {
+    /// Removes the file along with its part file, whichever of them exists.
     pub fn remove_file(file_path: &str) -> Result<(), std::io::Error> {
-        if std::path::Path::new(file_path).exists() {
-            return fs::remove_file(file_path);
+        for path in [file_path.to_string(), Self::part_path(file_path)] {
+            if std::path::Path::new(&path).exists() {
+                fs::remove_file(path)?;
+            }
         }
 
         Ok(())
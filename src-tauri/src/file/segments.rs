@@ -0,0 +1,48 @@
+use std::path::PathBuf;
+
+use tokio::{fs, io};
+
+/// Suffix of the directory a stream's segments are downloaded into.
+const SEGMENTS_EXTENSION: &str = "ferrix-segments";
+
+impl super::File {
+    pub fn segments_dir(file_path: &str) -> PathBuf {
+        PathBuf::from(format!("{file_path}.{SEGMENTS_EXTENSION}"))
+    }
+
+    pub fn segment_path(file_path: &str, segment_index: i64) -> PathBuf {
+        Self::segments_dir(file_path).join(segment_index.to_string())
+    }
+
+    pub fn remove_segments(file_path: &str) -> std::io::Result<()> {
+        let dir = Self::segments_dir(file_path);
+
+        if dir.exists() {
+            return std::fs::remove_dir_all(dir);
+        }
+
+        Ok(())
+    }
+
+    /// Writes the segments one after another into the part file, replacing whatever
+    /// it held, and returns the resulting size.
+    pub async fn join_segments(file_path: &str, segment_count: i64) -> std::io::Result<u64> {
+        let mut part = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(Self::part_path(file_path))
+            .await?;
+
+        let mut total_bytes = 0;
+
+        for segment_index in 0..segment_count {
+            let mut segment = fs::File::open(Self::segment_path(file_path, segment_index)).await?;
+            total_bytes += io::copy(&mut segment, &mut part).await?;
+        }
+
+        part.sync_data().await?;
+
+        Ok(total_bytes)
+    }
+}
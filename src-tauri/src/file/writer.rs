@@ -1,54 +1,355 @@
-use std::io::SeekFrom;
+use std::{
+    collections::HashMap,
+    fs, io,
+    sync::{Arc, Mutex as StdMutex},
+    time::{Duration, Instant},
+};
 
-use tokio::fs;
 use tokio::fs::OpenOptions;
-use tokio::io::{AsyncSeekExt, AsyncWriteExt};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot, OwnedSemaphorePermit, Semaphore};
+use tokio::{select, task, time::interval};
+use tokio_util::bytes::{Bytes, BytesMut};
 
-use crate::{dispatch, spawn};
+use super::{ChunkIntegrity, DiskError};
+use crate::{dispatch, emitter::Emitter, models::DiskSettings, registry::Registry, spawn};
 
-pub type WriteMessage = (i64, u64, u64, Vec<u8>);
+#[derive(Debug)]
+pub enum WriteMessage {
+    /// Bytes a chunk received, to be written at `offset`. The permit holds their share
+    /// of the write queue until they reach the file.
+    Write {
+        chunk_index: i64,
+        offset: u64,
+        bytes: Bytes,
+        permit: OwnedSemaphorePermit,
+    },
+    /// Writes out every buffer and fsyncs, answering once the data is on disk.
+    Flush(oneshot::Sender<Result<(), DiskError>>),
+}
+
+/// Sending half of a download's writer. Writes wait for room in a byte budget,
+/// so a disk slower than the network slows the streams instead of growing memory.
+#[derive(Debug, Clone)]
+pub struct FileSender {
+    tx: mpsc::UnboundedSender<WriteMessage>,
+    budget: Arc<Semaphore>,
+    capacity: u64,
+}
+
+impl FileSender {
+    /// Fails once the writer has stopped, the budget is closed then so nobody waits forever.
+    pub async fn write(
+        &self,
+        chunk_index: i64,
+        offset: u64,
+        bytes: Bytes,
+    ) -> Result<(), DiskError> {
+        let needed = (bytes.len() as u64).min(self.capacity) as u32;
+
+        let permit = Arc::clone(&self.budget)
+            .acquire_many_owned(needed)
+            .await
+            .map_err(|_| DiskError::WriterClosed)?;
+
+        self.tx
+            .send(WriteMessage::Write {
+                chunk_index,
+                offset,
+                bytes,
+                permit,
+            })
+            .map_err(|_| DiskError::WriterClosed)
+    }
+
+    /// Resolves once everything sent so far is written and synced.
+    pub async fn flush(&self) -> Result<(), DiskError> {
+        let (tx, rx) = oneshot::channel();
+
+        self.tx
+            .send(WriteMessage::Flush(tx))
+            .map_err(|_| DiskError::WriterClosed)?;
+
+        rx.await.map_err(|_| DiskError::WriterClosed)?
+    }
+
+    /// Bytes received but not yet written to the file.
+    pub fn queued_bytes(&self) -> u64 {
+        self.capacity
+            .saturating_sub(self.budget.available_permits() as u64)
+    }
+
+    pub fn capacity(&self) -> u64 {
+        self.capacity
+    }
+}
+
+/// Bytes of one chunk waiting to be written, contiguous from `offset`.
+struct PendingWrite {
+    offset: u64,
+    bytes: BytesMut,
+    permit: Option<OwnedSemaphorePermit>,
+    since: Instant,
+}
+
+struct BufferedWriter {
+    download_id: i64,
+    file: Arc<fs::File>,
+    settings: DiskSettings,
+    pending: HashMap<i64, PendingWrite>,
+    unsynced_bytes: u64,
+    last_sync: Instant,
+}
+
+impl BufferedWriter {
+    async fn push(
+        &mut self,
+        chunk_index: i64,
+        offset: u64,
+        bytes: Bytes,
+        permit: OwnedSemaphorePermit,
+    ) -> Result<(), DiskError> {
+        let contiguous = self
+            .pending
+            .get(&chunk_index)
+            .is_some_and(|p| p.offset + p.bytes.len() as u64 == offset);
+
+        if !contiguous {
+            self.write_out(chunk_index).await?;
+        }
+
+        let pending = self
+            .pending
+            .entry(chunk_index)
+            .or_insert_with(|| PendingWrite {
+                offset,
+                bytes: BytesMut::new(),
+                permit: None,
+                since: Instant::now(),
+            });
+        pending.bytes.extend_from_slice(&bytes);
+
+        match &mut pending.permit {
+            Some(held) => held.merge(permit),
+            None => pending.permit = Some(permit),
+        }
+
+        if pending.bytes.len() as u64 >= self.settings.write_buffer_size {
+            self.write_out(chunk_index).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn write_out(&mut self, chunk_index: i64) -> Result<(), DiskError> {
+        let Some(pending) = self.pending.remove(&chunk_index) else {
+            return Ok(());
+        };
+
+        let bytes = pending.bytes.freeze();
+        let bytes_len = bytes.len() as u64;
+
+        let file = Arc::clone(&self.file);
+        let integrity = self.integrity(chunk_index);
+        let offset = pending.offset;
+
+        // Hashing runs with the write on the blocking pool so it never stalls this task.
+        task::spawn_blocking(move || {
+            write_all_at(&file, &bytes, offset)?;
+
+            if let Some(integrity) = integrity {
+                integrity
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .update(offset, &bytes);
+            }
+
+            Ok::<_, io::Error>(())
+        })
+        .await
+        .map_err(|e| DiskError::Io(Arc::new(io::Error::other(e))))??;
+
+        // The bytes are in the file, give their room back to the streams.
+        drop(pending.permit);
+
+        self.unsynced_bytes += bytes_len;
+
+        if let Err(err) = dispatch!(
+            registry,
+            UpdateDiskReport,
+            (self.download_id, chunk_index, bytes_len)
+        ) {
+            Emitter::emit_error(err.to_string());
+        }
+
+        if self.unsynced_bytes >= self.settings.sync_bytes {
+            self.sync().await?;
+        }
+
+        Ok(())
+    }
+
+    fn integrity(&self, chunk_index: i64) -> Option<Arc<StdMutex<ChunkIntegrity>>> {
+        let reports = &Registry::get_state().reports;
+        let report = reports.get(&self.download_id)?;
+        let integrity = report.integrity.get(&chunk_index)?;
+        Some(Arc::clone(&integrity))
+    }
+
+    async fn write_out_all(&mut self) -> Result<(), DiskError> {
+        let chunks = self.pending.keys().copied().collect::<Vec<_>>();
+
+        for chunk_index in chunks {
+            self.write_out(chunk_index).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn sync(&mut self) -> Result<(), DiskError> {
+        if self.unsynced_bytes == 0 {
+            return Ok(());
+        }
+
+        let file = Arc::clone(&self.file);
+        task::spawn_blocking(move || file.sync_data())
+            .await
+            .map_err(|e| DiskError::Io(Arc::new(io::Error::other(e))))??;
+
+        self.unsynced_bytes = 0;
+        self.last_sync = Instant::now();
+
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<(), DiskError> {
+        self.write_out_all().await?;
+        self.sync().await
+    }
+
+    /// Writes out buffers that waited too long and syncs on the time policy.
+    async fn tick(&mut self) -> Result<(), DiskError> {
+        let max_age = Duration::from_millis(self.settings.flush_interval_ms);
+        let stale = self
+            .pending
+            .iter()
+            .filter(|(_, p)| p.since.elapsed() >= max_age)
+            .map(|(chunk_index, _)| *chunk_index)
+            .collect::<Vec<_>>();
+
+        for chunk_index in stale {
+            self.write_out(chunk_index).await?;
+        }
+
+        if self.last_sync.elapsed() >= Duration::from_secs(self.settings.sync_interval_secs) {
+            self.sync().await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Positional write, so chunks never share a file cursor.
+fn write_all_at(file: &fs::File, buf: &[u8], offset: u64) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileExt;
+        file.write_all_at(buf, offset)
+    }
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::FileExt;
+
+        let mut written = 0;
+        while written < buf.len() {
+            match file.seek_write(&buf[written..], offset + written as u64) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(n) => written += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
 
 impl super::File {
     pub(super) async fn setup_file_writer(
         download_id: i64,
         file_path: &str,
         total_bytes: u64,
-    ) -> anyhow::Result<mpsc::UnboundedSender<WriteMessage>> {
-        let file_exists = fs::metadata(file_path).await.is_ok();
+    ) -> anyhow::Result<FileSender> {
+        let part_path = Self::part_path(file_path);
+        let file_exists = tokio::fs::metadata(&part_path).await.is_ok();
 
-        let mut file = OpenOptions::new()
+        let file = OpenOptions::new()
             .write(true)
             .create(true)
-            .open(file_path)
+            .truncate(false)
+            .open(&part_path)
             .await?;
 
-        if !file_exists {
-            file.set_len(total_bytes as u64).await?;
+        // A download of unknown size grows as it is written.
+        if !file_exists && total_bytes > 0 {
+            file.set_len(total_bytes).await?;
         }
 
+        let settings = Registry::get_state().disk_settings.read().await.clone();
+        let flush_interval = Duration::from_millis(settings.flush_interval_ms);
+        let capacity = settings.write_queue_bytes;
+
+        let mut writer = BufferedWriter {
+            download_id,
+            file: Arc::new(file.into_std().await),
+            settings,
+            pending: HashMap::new(),
+            unsynced_bytes: 0,
+            last_sync: Instant::now(),
+        };
+
         let (tx, mut rx) = mpsc::unbounded_channel::<WriteMessage>();
+        let budget = Arc::new(Semaphore::new(capacity as usize));
+        let writer_budget = Arc::clone(&budget);
 
         spawn!("file_writer", {
-            while let Some((chunk_index, start_byte, downloaded_bytes, bytes)) = rx.recv().await {
-                file.seek(SeekFrom::Start(start_byte)).await.unwrap();
-                file.write_all(&bytes).await.unwrap();
-                file.flush().await.unwrap();
-
-                dispatch!(
-                    registry,
-                    UpdateDiskReport,
-                    (download_id, chunk_index, downloaded_bytes)
-                );
-
-                dispatch!(
-                    registry,
-                    UpdateChunkBufferReport,
-                    (download_id, chunk_index, bytes)
-                );
+            let mut ticker = interval(flush_interval);
+
+            let result = loop {
+                let result = select! {
+                    message = rx.recv() => match message {
+                        Some(WriteMessage::Write { chunk_index, offset, bytes, permit }) => {
+                            writer.push(chunk_index, offset, bytes, permit).await
+                        }
+                        Some(WriteMessage::Flush(reply)) => {
+                            let result = writer.flush().await;
+                            let _ = reply.send(result.clone());
+                            result
+                        }
+                        None => break writer.flush().await,
+                    },
+                    _ = ticker.tick() => writer.tick().await,
+                };
+
+                if result.is_err() {
+                    break result;
+                }
+            };
+
+            // Streams waiting for room fail instead of waiting on a writer that is gone.
+            writer_budget.close();
+            drop(rx);
+
+            if let Err(err) = result {
+                if let Err(err) = dispatch!(manager, WriteFailed, (download_id, err)) {
+                    Emitter::emit_error(err.to_string());
+                }
             }
         });
 
-        Ok(tx)
+        Ok(FileSender {
+            tx,
+            budget,
+            capacity,
+        })
     }
 }
@@ -14,7 +14,7 @@ mod worker;
 #[macro_use]
 mod macros;
 
-use crate::registry::Registry;
+use crate::{emitter::Emitter, registry::Registry};
 
 #[tokio::main]
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -34,10 +34,31 @@ pub async fn run() {
         .plugin(tauri_plugin_opener::init())
         .invoke_handler(tauri::generate_handler![
             command::add_new_download,
+            command::add_metalink_download,
+            command::inspect_stream,
+            command::add_stream_download,
             command::get_download_list,
             command::resume_download,
             command::pause_download,
-            command::remove_download
+            command::restart_download,
+            command::verify_download,
+            command::refresh_download_url,
+            command::trust_host_key,
+            command::get_download_mirrors,
+            command::add_download_mirror,
+            command::remove_download_mirror,
+            command::remove_download,
+            command::update_speed_limit,
+            command::get_bandwidth_settings,
+            command::update_bandwidth_settings,
+            command::get_disk_settings,
+            command::update_disk_settings,
+            command::get_resolver_settings,
+            command::update_resolver_settings,
+            command::get_verify_settings,
+            command::update_verify_settings,
+            command::get_retry_settings,
+            command::update_retry_settings
         ])
         .setup(move |app| {
             let app_handle = app.app_handle().clone();
@@ -51,7 +72,9 @@ pub async fn run() {
         .on_window_event(|_, event| {
             if let tauri::WindowEvent::CloseRequested { api, .. } = event {
                 api.prevent_close();
-                dispatch!(registry, CloseRequested);
+                if let Err(err) = dispatch!(registry, CloseRequested) {
+                    Emitter::emit_error(err.to_string());
+                }
             }
         })
         .run(tauri::generate_context!())
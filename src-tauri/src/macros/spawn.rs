@@ -13,7 +13,9 @@ macro_rules! spawn {
 
             let task_id = task_id.fetch_add(1, ::std::sync::atomic::Ordering::SeqCst);
 
-            $crate::dispatch!(registry, AddTask, (task_id, $name.into()));
+            if let Err(err) = $crate::dispatch!(registry, AddTask, (task_id, $name.into())) {
+                $crate::emitter::Emitter::emit_error(err.to_string());
+            }
 
             available_permits.swap(
                 permit.available_permits(),
@@ -31,11 +33,13 @@ macro_rules! spawn {
                 permit.available_permits(),
                 ::std::sync::atomic::Ordering::SeqCst,
             );
-            $crate::dispatch!(
+            if let Err(err) = $crate::dispatch!(
                 registry,
                 ChangeTaskStatus,
                 (task_id, $crate::registry::TaskStatus::Completed)
-            );
+            ) {
+                $crate::emitter::Emitter::emit_error(err.to_string());
+            }
         });
     }};
 }
@@ -1,31 +1,33 @@
 use std::{
     collections::HashMap,
     path::PathBuf,
-    sync::{
-        atomic::{AtomicU64, Ordering},
-        Arc,
-    },
+    sync::{atomic::Ordering, Arc},
 };
 
 use anyhow::{anyhow, Context};
-use futures_util::future::join_all;
 use serde::Deserialize;
 
 use crate::{
-    client::{AuthType, Client, ProxyType},
+    client::{AuthType, Client, InspectResponse, ProxyType},
     dispatch,
     emitter::Emitter,
-    file::File,
-    models::{NewDownload, UpdateChunk, UpdateDownload},
+    file::{DiskError, File},
+    models::{Checksum, Download, NewDownload, UpdateChunk, UpdateDownload},
     registry::Registry,
-    repository::{chunk::ChunkRepository, download::DownloadRepository},
+    repository::{
+        chunk::ChunkRepository, download::DownloadRepository, segment::SegmentRepository,
+    },
     worker::{DownloadStatus, DownloadWorker},
 };
 
-#[derive(Debug, Deserialize)]
+/// Chunks a download starts with. The connection tuner adds streams from there while
+/// they still pay off, starting wider mostly gets hosts to throttle or refuse us.
+pub(super) const MAX_CHUNK_COUNT: i64 = 5;
+
+#[derive(Debug, Clone, Deserialize)]
 pub struct DownloadOptions {
     file_path: Option<String>,
-    chunk_count: i64,
+    pub(super) chunk_count: i64,
     proxy: Option<ProxyType>,
     auth: Option<AuthType>,
     headers: Option<HashMap<String, String>>,
@@ -35,22 +37,105 @@ pub struct DownloadOptions {
     delay_secs: Option<f64>,
     backoff_factor: Option<f64>,
     timeout_secs: Option<f64>,
+    pub(super) checksum: Option<Checksum>,
+    /// Other URLs serving the same file.
+    pub(super) mirrors: Option<Vec<String>>,
+    referer: Option<String>,
 }
 
 impl super::DownloadsManager {
     pub async fn add_new_download(url: String, options: DownloadOptions) -> Result<(), String> {
-        let client = Client::new(
-            &url,
+        let response = Self::options_client(&url, &options)?
+            .inspect()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Self::check_mirrors(&url, &response, &options).await?;
+
+        let download_id = Self::insert_download(response, &options).await?;
+        Self::store_mirrors(download_id, &options).await?;
+
+        dispatch!(registry, NewDownload, (download_id)).map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    pub(super) fn options_client(url: &str, options: &DownloadOptions) -> Result<Client, String> {
+        Client::new(
+            url,
             &options.auth,
             &options.proxy,
             &options.headers,
             &options.cookies,
         )
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| e.to_string())
+    }
 
-        let response = client.inspect().await.map_err(|e| e.to_string())?;
+    pub(super) fn mirror_client(
+        url: &str,
+        primary: &str,
+        options: &DownloadOptions,
+    ) -> Result<Client, String> {
+        Client::for_source(
+            url,
+            primary,
+            &options.auth,
+            &options.proxy,
+            &options.headers,
+            &options.cookies,
+        )
+        .map_err(|e| e.to_string())
+    }
+
+    /// Stores an inspected download with its chunks, without queueing it.
+    pub(super) async fn insert_download(
+        response: InspectResponse,
+        options: &DownloadOptions,
+    ) -> Result<i64, String> {
+        // An unknown size can only be streamed front to back.
+        let supports_range = response.supports_range && response.content_length > 0;
+
+        let chunk_count = if supports_range {
+            options.chunk_count.clamp(1, MAX_CHUNK_COUNT)
+        } else {
+            1
+        };
+
+        let content_length = response.content_length;
+        let download_id =
+            Self::insert_download_row(response, options, "file", supports_range, chunk_count)
+                .await?;
+
+        if let Err(err) = Self::create_chunks(download_id, content_length, chunk_count).await {
+            Self::discard_download(download_id).await;
+            return Err(err);
+        }
+
+        Ok(download_id)
+    }
+
+    /// Drops a row whose chunks or sources failed to insert, it would never be queued.
+    pub(super) async fn discard_download(download_id: i64) {
+        if let Err(err) = DownloadRepository::delete(download_id).await {
+            Emitter::emit_error(err.to_string());
+        }
+    }
+
+    /// The `downloads` row alone. For streams `chunk_count` is how many segments
+    /// download at once.
+    pub(super) async fn insert_download_row(
+        response: InspectResponse,
+        options: &DownloadOptions,
+        kind: &str,
+        supports_range: bool,
+        chunk_count: i64,
+    ) -> Result<i64, String> {
+        let checksum = match options.checksum.clone() {
+            Some(checksum) => Some(checksum.validate().map_err(|e| e.to_string())?),
+            None => None,
+        };
 
-        let file_path = match options.file_path {
+        let file_path = match &options.file_path {
             Some(path) => {
                 let mut path_buf = PathBuf::from(path);
                 path_buf.push(&response.file_name);
@@ -64,14 +149,6 @@ impl super::DownloadsManager {
 
         let file_name = File::get_file_name(&file_path)?;
 
-        let chunk_count = if response.supports_range {
-            options.chunk_count.clamp(1, 5) as i64
-        } else {
-            1
-        };
-
-        let supports_range = if response.supports_range { 1 } else { 0 };
-
         let new_download = NewDownload {
             auth: match &options.auth {
                 Some(val) => serde_json::to_string(&val).ok(),
@@ -102,14 +179,129 @@ impl super::DownloadsManager {
             timeout_secs: options.timeout_secs,
             total_bytes: response.content_length as i64,
             url: response.url,
-            supports_range,
+            supports_range: supports_range as i64,
+            etag: response.etag,
+            last_modified: response.last_modified,
+            checksum_algorithm: checksum.as_ref().map(|c| c.algorithm.to_string()),
+            checksum: checksum.map(|c| c.digest),
+            referer: options.referer.clone(),
+            kind: kind.to_string(),
+        };
+
+        DownloadRepository::add(new_download)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    /// Starts a download over from the first byte against whatever the server serves now.
+    pub async fn restart_download(download_id: i64) -> Result<(), String> {
+        if Registry::get_state().workers.contains_key(&download_id) {
+            return Err("pause the download before restarting it".to_string());
+        }
+
+        let download = DownloadRepository::find(download_id)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if download.is_stream() {
+            SegmentRepository::reset_all(download_id)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            File::remove_segments(&download.file_path).map_err(|e| e.to_string())?;
+            File::remove_file(&download.file_path).map_err(|e| e.to_string())?;
+
+            dispatch!(registry, NewDownload, (download_id)).map_err(|e| e.to_string())?;
+
+            return Ok(());
+        }
+
+        let client = Client::new(
+            &download.url,
+            &download.auth,
+            &download.proxy,
+            &download.headers,
+            &download.cookies,
+        )
+        .map_err(|e| e.to_string())?;
+
+        let response = client.inspect().await.map_err(|e| e.to_string())?;
+
+        let supports_range = response.supports_range && response.content_length > 0;
+
+        let chunk_count = if supports_range {
+            download.chunk_count.clamp(1, MAX_CHUNK_COUNT)
+        } else {
+            1
         };
 
-        let download_id = DownloadRepository::add(new_download)
+        DownloadRepository::update_remote(
+            download_id,
+            response.content_length as i64,
+            supports_range,
+            response.etag,
+            response.last_modified,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+
+        ChunkRepository::delete_all(download_id)
             .await
             .map_err(|e| e.to_string())?;
 
-        let range = Self::get_chunk_ranges(response.content_length, chunk_count as u64);
+        Self::create_chunks(download_id, response.content_length, chunk_count).await?;
+
+        // The old bytes belong to the previous file and may be longer than the new one.
+        File::remove_file(&download.file_path).map_err(|e| e.to_string())?;
+
+        dispatch!(registry, NewDownload, (download_id)).map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    /// Size of the remote file if it can now be resumed by range and is still the one
+    /// we started, judged by the stored validators.
+    pub async fn probe_resumable(download: &Download) -> Option<i64> {
+        let client = Client::new(
+            &download.url,
+            &download.auth,
+            &download.proxy,
+            &download.headers,
+            &download.cookies,
+        )
+        .ok()?;
+
+        let response = client.inspect().await.ok()?;
+
+        (Self::same_validator(download, &response)
+            && response.supports_range
+            && response.content_length > 0)
+            .then_some(response.content_length as i64)
+    }
+
+    /// Whether the response carries the validator stored with the download.
+    /// Without one stored there is no telling, so the answer is no.
+    pub(super) fn same_validator(download: &Download, response: &InspectResponse) -> bool {
+        match (&download.etag, &download.last_modified) {
+            (Some(etag), _) => response.etag.as_ref() == Some(etag),
+            (None, Some(last_modified)) => response.last_modified.as_ref() == Some(last_modified),
+            (None, None) => false,
+        }
+    }
+
+    /// A download of unknown size gets one open ended chunk, `end_byte` of -1.
+    async fn create_chunks(
+        download_id: i64,
+        content_length: u64,
+        chunk_count: i64,
+    ) -> Result<(), String> {
+        if content_length == 0 {
+            return ChunkRepository::create(download_id, 0, (0, -1))
+                .await
+                .map_err(|e| e.to_string());
+        }
+
+        let range = Self::get_chunk_ranges(content_length, chunk_count as u64);
 
         ChunkRepository::create_all(download_id, range)
             .await
@@ -118,11 +310,7 @@ impl super::DownloadsManager {
                     .map(|f| f.to_string())
                     .collect::<Vec<String>>()
                     .join(", ")
-            })?;
-
-        dispatch!(registry, NewDownload, (download_id));
-
-        Ok(())
+            })
     }
 
     pub(super) async fn start_download_action(
@@ -144,12 +332,29 @@ impl super::DownloadsManager {
         download_id: i64,
     ) -> anyhow::Result<()> {
         match status {
-            DownloadStatus::Failed | DownloadStatus::Completed | DownloadStatus::Paused => {
-                dispatch!(manager, UpdateChunks, (download_id, true));
+            DownloadStatus::Failed
+            | DownloadStatus::RemoteChanged
+            | DownloadStatus::UrlExpired
+            | DownloadStatus::Completed
+            | DownloadStatus::Paused => {
+                dispatch!(manager, UpdateChunks, (download_id, true))?;
             }
             _ => {}
         }
 
+        if matches!(status, DownloadStatus::UrlExpired) {
+            Self::queue_url_resolve(download_id).await?;
+        }
+
+        if matches!(status, DownloadStatus::Completed) {
+            Registry::get_state().url_resolves.remove(&download_id);
+            Self::fill_unknown_size(download_id).await?;
+
+            let file_path = DownloadRepository::find(download_id).await?.file_path;
+            File::finalize(&file_path).await?;
+            File::remove_segments(&file_path)?;
+        }
+
         DownloadRepository::update(
             download_id,
             UpdateDownload {
@@ -174,11 +379,62 @@ impl super::DownloadsManager {
 
         if matches!(status, DownloadStatus::Completed) {
             Emitter::emit_notification("Download Completed", download.file_name);
+
+            // Queued behind `UpdateChunks`, so the writer has flushed by the time we hash.
+            dispatch!(manager, VerifyDownload, (download_id))?;
         }
 
         Ok(())
     }
 
+    /// Once a download of unknown size completes, what we received is its size.
+    async fn fill_unknown_size(download_id: i64) -> anyhow::Result<()> {
+        let Some(report) = Registry::get_state()
+            .reports
+            .get(&download_id)
+            .map(|r| Arc::clone(&r))
+        else {
+            return Ok(());
+        };
+
+        // Streams set their size themselves once the segments are joined.
+        if report.total_bytes > 0 || DownloadRepository::find(download_id).await?.is_stream() {
+            return Ok(());
+        }
+
+        let total_bytes = report.total_downloaded_bytes.load(Ordering::Relaxed) as i64;
+
+        for chunk in ChunkRepository::find_all(download_id).await? {
+            if chunk.end_byte < 0 {
+                ChunkRepository::update_end_byte(
+                    download_id,
+                    chunk.chunk_index,
+                    chunk.start_byte + total_bytes - 1,
+                )
+                .await?;
+            }
+        }
+
+        DownloadRepository::update(
+            download_id,
+            UpdateDownload {
+                total_bytes: Some(total_bytes),
+                status: None,
+                error_message: None,
+                auth: None,
+                backoff_factor: None,
+                cookies: None,
+                delay_secs: None,
+                headers: None,
+                max_retries: None,
+                proxy: None,
+                speed_limit: None,
+                timeout_secs: None,
+            },
+        )
+        .await
+    }
+
     pub(super) async fn pause_download_action(
         self: &Arc<Self>,
         download_id: i64,
@@ -206,35 +462,35 @@ impl super::DownloadsManager {
             download_id
         ))?;
 
-        let reports = Arc::clone(&Registry::get_state().reports);
+        let report = Registry::get_state()
+            .reports
+            .get(&download_id)
+            .map(|r| Arc::clone(&r));
 
         let worker = worker.write().await;
-        let update_chunks_futures = worker.chunks.iter().map(|chunk| {
-            let chunk_index = chunk.chunk_index;
-            let wrote_bytes = reports
-                .get(&download_id)
-                .and_then(|r| {
-                    r.chunks_wrote_bytes
-                        .get(&chunk_index)
-                        .map(|v| v.load(Ordering::Relaxed))
-                })
-                .unwrap_or(0);
-
-            async move {
-                let expected_hash = Self::hash_chunk(download_id, chunk_index)
-                    .await
-                    .ok()
-                    .map(|h| h.to_string());
-
-                UpdateChunk {
-                    downloaded_bytes: Some(wrote_bytes as i64),
-                    expected_hash,
-                    chunk_index,
-                }
-            }
-        });
 
-        let update_chunks = join_all(update_chunks_futures).await;
+        // Taken before the flush, so every byte the hashes cover is synced below.
+        let update_chunks = worker
+            .chunks
+            .iter()
+            .filter_map(|chunk| {
+                let integrity = report.as_ref()?.integrity.get(&chunk.chunk_index)?;
+                let integrity = integrity.lock().unwrap_or_else(|e| e.into_inner());
+
+                Some(UpdateChunk {
+                    chunk_index: chunk.chunk_index,
+                    downloaded_bytes: integrity.hashed_bytes() as i64,
+                    block_hashes: integrity.snapshot(),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        // Only bytes that reached the disk may be recorded as downloaded.
+        // A writer that already failed has reported it through `WriteFailed`.
+        match worker.file.flush().await {
+            Ok(()) | Err(DiskError::WriterClosed) => {}
+            Err(err) => Emitter::emit_error(err.to_string()),
+        }
 
         if let Err(errors) = ChunkRepository::update_all(download_id, update_chunks).await {
             errors.iter().for_each(|err| {
@@ -243,52 +499,77 @@ impl super::DownloadsManager {
         }
 
         if clean_after_update {
-            dispatch!(registry, CleanDownloadedItemData, (download_id));
+            dispatch!(registry, CleanDownloadedItemData, (download_id))?;
         }
 
         Ok(())
     }
 
-    pub(super) async fn reset_chunk_action(
+    /// The server answered a range request with the whole body, so `Accept-Ranges` was
+    /// wrong. Stops the worker and queues it again, `prepare_download_data` then starts
+    /// it over as one stream.
+    pub(super) async fn disable_range_action(
         self: &Arc<Self>,
         download_id: i64,
-        chunk_index: i64,
     ) -> anyhow::Result<()> {
-        ChunkRepository::update(
-            download_id,
-            UpdateChunk {
-                chunk_index,
-                downloaded_bytes: Some(0),
-                expected_hash: None,
-            },
-        )
-        .await?;
+        let state = Registry::get_state();
 
-        let reports = Arc::clone(&Registry::get_state().reports);
+        if !state.pending_restarts.insert(download_id) {
+            return Ok(());
+        }
 
-        let report = reports.get(&download_id).context(anyhow!(
-            "reset chunk error: cannot find report with download id {}",
-            download_id
-        ))?;
+        DownloadRepository::update_supports_range(download_id, false).await?;
+
+        let worker = state.workers.get(&download_id).map(|w| Arc::clone(&w));
 
-        let chunk_counter = report.chunks_wrote_bytes.get(&chunk_index)
-        .context(
-            anyhow!(
-                "reset chunk error: cannot find chunk wrote bytes in report with download id {} and chunk index {}",
-                download_id, chunk_index
-            )
-        )?;
+        if let Some(worker) = worker {
+            let mut worker = worker.write().await;
+            worker.download.supports_range = false;
+            worker.cancel_token.cancel();
+        }
 
-        let prev_chunk_bytes = chunk_counter.swap(0, Ordering::AcqRel);
+        Ok(())
+    }
 
-        let saturating_fetch_sub = |atom: &AtomicU64, amount: u64| {
-            let _ = atom.fetch_update(Ordering::AcqRel, Ordering::Acquire, |cur| {
-                Some(cur.saturating_sub(amount))
-            });
-        };
+    /// The disk refused a write. Pauses the download with the reason so it can be
+    /// resumed once there is space again or the drive is back.
+    pub(super) async fn write_failed_action(
+        self: &Arc<Self>,
+        download_id: i64,
+        err: DiskError,
+    ) -> anyhow::Result<()> {
+        // Cancel first, the chunks wait on it and the update below can fail on the same full disk.
+        let worker = Registry::get_state()
+            .workers
+            .get(&download_id)
+            .map(|w| Arc::clone(&w));
+
+        if let Some(worker) = worker {
+            worker.read().await.cancel_token.cancel();
+        }
+
+        let updated = DownloadRepository::update(
+            download_id,
+            UpdateDownload {
+                error_message: Some(err.to_string()),
+                status: None,
+                total_bytes: None,
+                auth: None,
+                backoff_factor: None,
+                cookies: None,
+                delay_secs: None,
+                headers: None,
+                max_retries: None,
+                proxy: None,
+                speed_limit: None,
+                timeout_secs: None,
+            },
+        )
+        .await;
 
-        saturating_fetch_sub(&report.total_downloaded_bytes, prev_chunk_bytes);
-        saturating_fetch_sub(&report.total_wrote_bytes, prev_chunk_bytes);
+        if let Err(err) = updated {
+            Emitter::emit_error(err.to_string());
+        }
 
         Ok(())
     }
@@ -1,13 +1,85 @@
 use std::sync::{atomic::Ordering, Arc};
 
-use crate::registry::Registry;
+use chrono::Local;
+
+use crate::{
+    models::{BandwidthSettings, Settings},
+    registry::Registry,
+    repository::settings::SettingsRepository,
+};
 
 impl super::DownloadsManager {
+    pub async fn update_bandwidth_settings(settings: BandwidthSettings) -> Result<(), String> {
+        settings.validate()?;
+
+        SettingsRepository::save(BandwidthSettings::KEY, &settings)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let max_streams_per_host = settings.max_streams_per_host;
+
+        *Registry::get_state().bandwidth_settings.write().await = settings;
+        Self::apply_bandwidth_schedule().await;
+
+        for slots in Registry::get_state().host_slots.iter() {
+            slots.resize(max_streams_per_host);
+        }
+
+        Ok(())
+    }
+
+    /// Applies the user's global cap, or the schedule active right now, to the global bucket.
+    pub async fn apply_bandwidth_schedule() {
+        let settings = Arc::clone(&Registry::get_state().bandwidth_settings);
+        let limit = settings
+            .read()
+            .await
+            .active_limit(Local::now().naive_local());
+
+        Registry::get_state().bandwidth_bucket.set_rate(limit);
+    }
+
     pub(super) async fn monitor_download_speed() {
+        Self::apply_bandwidth_schedule().await;
+        Self::update_bandwidth_share();
+
+        let reports = Arc::clone(&Registry::get_state().reports);
+        let bandwidth_limit = Registry::get_state()
+            .bandwidth_limit
+            .load(Ordering::Relaxed);
+
+        for report in reports.iter() {
+            let speed_limit = report.speed_limit.load(Ordering::Relaxed);
+            report
+                .bandwidth_bucket
+                .set_rate(Self::effective_limit(bandwidth_limit, speed_limit));
+        }
+    }
+
+    /// The download's own `speed_limit` wins over the shared bandwidth share when it is stricter.
+    pub fn effective_limit(bandwidth_limit: f64, speed_limit: u64) -> u64 {
+        let bandwidth_limit = bandwidth_limit as u64;
+
+        match (bandwidth_limit > 0, speed_limit > 0) {
+            (true, true) => bandwidth_limit.min(speed_limit),
+            (true, false) => bandwidth_limit,
+            (false, true) => speed_limit,
+            (false, false) => 0,
+        }
+    }
+
+    fn update_bandwidth_share() {
         let reports = Arc::clone(&Registry::get_state().reports);
         let bandwidth_limit = Arc::clone(&Registry::get_state().bandwidth_limit);
 
-        let download_len = reports.len().max(1) as u64;
+        // Downloads with their own speed limit are left out of the equal share,
+        // otherwise a throttled download would drag the others down to its speed.
+        let unlimited_reports = reports
+            .iter()
+            .filter(|r| r.speed_limit.load(Ordering::Relaxed) == 0)
+            .collect::<Vec<_>>();
+
+        let download_len = unlimited_reports.len().max(1) as u64;
 
         if download_len == 1 {
             if bandwidth_limit.load(Ordering::Relaxed) > 0.0 {
@@ -16,7 +88,7 @@ impl super::DownloadsManager {
             return;
         }
 
-        let all_stable_speed = reports
+        let all_stable_speed = unlimited_reports
             .iter()
             .all(|r| r.stable_speed.load(Ordering::Relaxed));
 
@@ -29,7 +101,7 @@ impl super::DownloadsManager {
 
         let download_speed = Arc::clone(&Registry::get_state().download_speed);
 
-        let current_speed = reports
+        let current_speed = unlimited_reports
             .iter()
             .map(|r| r.speed_bps.load(Ordering::Relaxed))
             .sum::<u64>() as f64;
@@ -37,7 +109,7 @@ impl super::DownloadsManager {
         let mut last_speed = download_speed.load(Ordering::Relaxed);
 
         if current_speed > last_speed {
-            last_speed = current_speed as f64;
+            last_speed = current_speed;
         } else if current_speed < last_speed {
             last_speed = current_speed * 1.1;
         }
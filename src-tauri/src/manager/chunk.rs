@@ -3,10 +3,7 @@ use std::{
     time::Instant,
 };
 
-use anyhow::{Context, Result};
-use blake3::{Hash, Hasher};
-
-use crate::{dispatch, registry::Registry};
+use crate::{dispatch, emitter::Emitter, registry::Registry};
 
 const PROGRESS_UPDATE_THRESHOLD: u8 = 5;
 const TIME_UPDATE_THRESHOLD: u64 = 10;
@@ -44,17 +41,21 @@ impl super::DownloadsManager {
             let last_update_time = Arc::clone(&r.last_update_time);
             let mut last_update_time = last_update_time.lock().await;
 
-            let percent = (100 * total_downloaded_bytes / r.total_bytes) as u8;
-            let last_percent = (100 * last_update_downloaded_bytes / r.total_bytes) as u8;
-            let is_five_percent =
-                percent as i8 - last_percent as i8 >= PROGRESS_UPDATE_THRESHOLD as i8;
+            // Without a known size only the time thresholds apply.
+            let is_five_percent = r.total_bytes > 0 && {
+                let percent = (100 * total_downloaded_bytes / r.total_bytes) as u8;
+                let last_percent = (100 * last_update_downloaded_bytes / r.total_bytes) as u8;
+                percent as i8 - last_percent as i8 >= PROGRESS_UPDATE_THRESHOLD as i8
+            };
 
             let elapsed = last_update_time.elapsed().as_secs();
             let more_than_ten_seconds = elapsed >= TIME_UPDATE_THRESHOLD;
             let more_than_five_minutes = elapsed >= TIME_MAX_STALE;
 
             if more_than_five_minutes || (is_five_percent && more_than_ten_seconds) {
-                dispatch!(manager, UpdateChunks, (*r.key(), false));
+                if let Err(err) = dispatch!(manager, UpdateChunks, (*r.key(), false)) {
+                    Emitter::emit_error(err.to_string());
+                }
 
                 *last_update_time = Instant::now();
                 r.last_update_downloaded_bytes
@@ -62,35 +63,4 @@ impl super::DownloadsManager {
             }
         }
     }
-
-    pub async fn hash_chunk(download_id: i64, chunk_index: i64) -> Result<Hash> {
-        let reports = Arc::clone(&Registry::get_state().reports);
-
-        let report = reports
-            .get(&download_id)
-            .with_context(|| format!("report not found for download id {}", download_id))?;
-
-        let buffer = report.buffer.get(&chunk_index).with_context(|| {
-            format!(
-                "buffer not found for download id {} with chunk index {}",
-                download_id, chunk_index
-            )
-        })?;
-
-        let buffer_lock = buffer.lock().await;
-
-        if buffer_lock.first.len() + buffer_lock.last.len() < 2048 {
-            return Err(anyhow::anyhow!(
-                "buffer for download id {} with chunk index {} must be at least 2048 bytes",
-                download_id,
-                chunk_index
-            ));
-        }
-
-        let mut hasher = Hasher::new();
-        hasher.update(&buffer_lock.first);
-        hasher.update(&buffer_lock.last);
-
-        Ok(hasher.finalize())
-    }
 }
@@ -0,0 +1,20 @@
+use crate::{
+    models::{DiskSettings, Settings},
+    registry::Registry,
+    repository::settings::SettingsRepository,
+};
+
+impl super::DownloadsManager {
+    /// New settings apply to writers started afterwards, running downloads keep theirs.
+    pub async fn update_disk_settings(settings: DiskSettings) -> Result<(), String> {
+        settings.validate()?;
+
+        SettingsRepository::save(DiskSettings::KEY, &settings)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        *Registry::get_state().disk_settings.write().await = settings;
+
+        Ok(())
+    }
+}
@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use anyhow::Context;
 
-use crate::worker::DownloadStatus;
+use crate::{file::DiskError, worker::DownloadStatus};
 
 #[derive(Debug)]
 pub enum ManagerAction {
@@ -17,7 +17,10 @@ pub enum ManagerAction {
         /*Download ID */ i64,
         /* Clean After Update */ bool,
     ),
-    ResetChunk(/*Download ID */ i64, /* Chunk Index */ i64),
+    DisableRange(/*Download ID */ i64),
+    WriteFailed(/*Download ID */ i64, DiskError),
+    VerifyDownload(/*Download ID */ i64),
+    ResolveDownloadUrl(/*Download ID */ i64),
 }
 
 impl super::DownloadsManager {
@@ -29,7 +32,7 @@ impl super::DownloadsManager {
     }
 
     pub(super) async fn reducer(self: &Arc<Self>, action: ManagerAction) -> anyhow::Result<()> {
-        let self_clone = Arc::clone(&self);
+        let self_clone = Arc::clone(self);
 
         use ManagerAction::*;
 
@@ -46,10 +49,11 @@ impl super::DownloadsManager {
                     .update_chunks_action(download_id, clean_after_update)
                     .await
             }
-            ResetChunk(download_id, chunk_index) => {
-                self_clone
-                    .reset_chunk_action(download_id, chunk_index)
-                    .await
+            DisableRange(download_id) => self_clone.disable_range_action(download_id).await,
+            WriteFailed(download_id, err) => self_clone.write_failed_action(download_id, err).await,
+            VerifyDownload(download_id) => self_clone.verify_download_action(download_id).await,
+            ResolveDownloadUrl(download_id) => {
+                self_clone.resolve_download_url_action(download_id).await
             }
         }
     }
@@ -0,0 +1,165 @@
+use std::path::Path;
+
+use tokio::fs;
+
+use crate::{
+    client::{Client, MetalinkFile, Protocol},
+    dispatch,
+    models::DownloadPiece,
+    repository::{mirror::MirrorRepository, piece::PieceRepository},
+};
+
+use super::DownloadOptions;
+
+impl super::DownloadsManager {
+    /// Adds a download for every file a metalink describes. `source` is the URL of
+    /// the metalink or a `.meta4` file on disk.
+    pub async fn add_metalink_download(
+        source: String,
+        options: DownloadOptions,
+    ) -> Result<(), String> {
+        let files = if Protocol::from_url(&source).is_some() {
+            Self::options_client(&source, &options)?.metalink().await
+        } else {
+            let xml = fs::read_to_string(&source)
+                .await
+                .map_err(|e| format!("failed to read {}: {}", source, e))?;
+            Client::parse_metalink(&xml)
+        }
+        .map_err(|e| e.to_string())?;
+
+        let mut errors = Vec::new();
+
+        for file in files {
+            let added = Self::add_metalink_file(&file, &options)
+                .await
+                .and_then(|download_id| {
+                    dispatch!(registry, NewDownload, (download_id)).map_err(|e| e.to_string())
+                });
+
+            if let Err(err) = added {
+                errors.push(format!("{}: {}", file.name, err));
+            }
+        }
+
+        if errors.is_empty() {
+            return Ok(());
+        }
+
+        Err(errors.join(", "))
+    }
+
+    /// The first mirror that answers with the size the metalink promises becomes the
+    /// download's `url`, the rest are kept for failover.
+    async fn add_metalink_file(
+        file: &MetalinkFile,
+        options: &DownloadOptions,
+    ) -> Result<i64, String> {
+        let mut options = options.clone();
+        options.checksum = options.checksum.or_else(|| file.checksum());
+
+        let mut last_error = "no mirror responded".to_string();
+
+        for (index, mirror) in file.urls.iter().enumerate() {
+            let mut response = match Self::options_client(&mirror.url, &options)?.inspect().await {
+                Ok(response) => response,
+                Err(err) => {
+                    last_error = format!("{}: {}", mirror.url, err);
+                    continue;
+                }
+            };
+
+            if file
+                .size
+                .is_some_and(|size| response.content_length > 0 && response.content_length != size)
+            {
+                last_error = format!("{} serves a different file", mirror.url);
+                continue;
+            }
+
+            if !file.name.is_empty() {
+                response.extension = Path::new(&file.name)
+                    .extension()
+                    .map(|e| e.to_string_lossy().into_owned())
+                    .unwrap_or(response.extension);
+                response.file_name = file.name.clone();
+            }
+
+            let content_length = response.content_length;
+            let download_id = Self::insert_download(response, &options).await?;
+
+            if let Err(err) =
+                Self::insert_metalink_sources(download_id, file, index, content_length).await
+            {
+                Self::discard_download(download_id).await;
+                return Err(err.to_string());
+            }
+
+            return Ok(download_id);
+        }
+
+        Err(last_error)
+    }
+
+    /// Mirrors after the one the download uses, and the published piece hashes.
+    async fn insert_metalink_sources(
+        download_id: i64,
+        file: &MetalinkFile,
+        url_index: usize,
+        content_length: u64,
+    ) -> Result<(), sqlx::Error> {
+        for (priority, mirror) in file.urls.iter().enumerate().skip(url_index + 1) {
+            MirrorRepository::create(
+                download_id,
+                &mirror.url,
+                priority as i64,
+                mirror.location.as_deref(),
+            )
+            .await?;
+        }
+
+        let pieces = Self::metalink_pieces(download_id, file, content_length);
+
+        if !pieces.is_empty() {
+            PieceRepository::create_all(download_id, pieces).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Byte ranges of the published pieces, empty when they don't add up to the file.
+    fn metalink_pieces(
+        download_id: i64,
+        file: &MetalinkFile,
+        content_length: u64,
+    ) -> Vec<DownloadPiece> {
+        let Some(pieces) = &file.pieces else {
+            return Vec::new();
+        };
+
+        if content_length == 0
+            || content_length.div_ceil(pieces.length) != pieces.hashes.len() as u64
+        {
+            return Vec::new();
+        }
+
+        pieces
+            .hashes
+            .iter()
+            .enumerate()
+            .map(|(index, hash)| {
+                let start = index as u64 * pieces.length;
+                let end = (start + pieces.length).min(content_length) - 1;
+
+                DownloadPiece {
+                    download_id,
+                    piece_index: index as i64,
+                    start_byte: start as i64,
+                    end_byte: end as i64,
+                    algorithm: pieces.algorithm.to_string(),
+                    hash: hash.clone(),
+                }
+            })
+            .collect()
+    }
+}
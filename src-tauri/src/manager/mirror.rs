@@ -0,0 +1,147 @@
+use std::sync::Arc;
+
+use crate::{
+    client::{Client, InspectResponse},
+    models::DownloadMirror,
+    registry::Registry,
+    repository::{download::DownloadRepository, mirror::MirrorRepository},
+};
+
+use super::DownloadOptions;
+
+impl super::DownloadsManager {
+    pub async fn get_download_mirrors(download_id: i64) -> Result<Vec<DownloadMirror>, String> {
+        MirrorRepository::find_all(download_id)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    /// Attaches another source to a download once it proves to serve the same file.
+    /// A running download can hand chunks to it right away.
+    pub async fn add_download_mirror(download_id: i64, url: String) -> Result<(), String> {
+        let download = DownloadRepository::find(download_id)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if url == download.url {
+            return Err("the mirror is the download's own url".to_string());
+        }
+
+        let response = Client::for_source(
+            &url,
+            &download.url,
+            &download.auth,
+            &download.proxy,
+            &download.headers,
+            &download.cookies,
+        )
+        .map_err(|e| e.to_string())?
+        .inspect()
+        .await
+        .map_err(|e| e.to_string())?;
+
+        Self::check_mirror(
+            &url,
+            download.total_bytes as u64,
+            download.etag.as_deref(),
+            &response,
+        )?;
+
+        let priority = MirrorRepository::next_priority(download_id)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        MirrorRepository::create(download_id, &url, priority, None)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let worker = Registry::get_state()
+            .workers
+            .get(&download_id)
+            .map(|w| Arc::clone(&w));
+
+        if let Some(worker) = worker {
+            let mut worker = worker.write().await;
+            if !worker.mirrors.contains(&url) {
+                worker.mirrors.push(url);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Takes effect the next time the download starts, running chunks keep their source.
+    pub async fn remove_download_mirror(download_id: i64, url: String) -> Result<(), String> {
+        MirrorRepository::delete(download_id, &url)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    /// Inspects every mirror given with a new download against the primary response.
+    pub(super) async fn check_mirrors(
+        primary_url: &str,
+        primary: &InspectResponse,
+        options: &DownloadOptions,
+    ) -> Result<(), String> {
+        for url in options.mirrors.iter().flatten() {
+            let response = Self::mirror_client(url, primary_url, options)?
+                .inspect()
+                .await
+                .map_err(|e| format!("mirror {}: {}", url, e))?;
+
+            Self::check_mirror(
+                url,
+                primary.content_length,
+                primary.etag.as_deref(),
+                &response,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    pub(super) async fn store_mirrors(
+        download_id: i64,
+        options: &DownloadOptions,
+    ) -> Result<(), String> {
+        for (index, url) in options.mirrors.iter().flatten().enumerate() {
+            MirrorRepository::create(download_id, url, index as i64 + 1, None)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+
+        Ok(())
+    }
+
+    /// Chunks from different sources end up in one file, so a mirror has to report
+    /// the same length and, when both sides send one, the same ETag.
+    fn check_mirror(
+        url: &str,
+        total_bytes: u64,
+        etag: Option<&str>,
+        response: &InspectResponse,
+    ) -> Result<(), String> {
+        if total_bytes == 0 {
+            return Err("mirrors need a download of known size".to_string());
+        }
+
+        if !response.supports_range {
+            return Err(format!("mirror {} doesn't support ranges", url));
+        }
+
+        if response.content_length != total_bytes {
+            return Err(format!(
+                "mirror {} reports {} bytes, expected {}",
+                url, response.content_length, total_bytes
+            ));
+        }
+
+        if let (Some(expected), Some(actual)) = (etag, response.etag.as_deref()) {
+            if expected != actual {
+                return Err(format!("mirror {} reports a different ETag", url));
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -5,9 +5,16 @@ use tokio::sync::mpsc;
 mod actions;
 mod bandwidth;
 mod chunk;
+mod disk;
 mod event;
+mod metalink;
+mod mirror;
 mod monitor;
 mod reports;
+mod resolve;
+mod retry;
+mod stream;
+mod verify;
 
 pub use actions::DownloadOptions;
 pub use event::ManagerAction;
@@ -36,7 +43,7 @@ impl DownloadsManager {
         spawn!("manager_mpsc", {
             while let Some(action) = rx.recv().await {
                 if let Err(err) = self_clone.reducer(action).await {
-                    debug!("{}", err.to_string());
+                    debug!("{}", err);
                     Emitter::emit_error(err.to_string());
                 }
             }
@@ -16,6 +16,9 @@ impl super::DownloadsManager {
             ("report_disk_speed", Duration::from_secs(1), {
                 Self::report_disk_speed().await;
             }),
+            ("report_bandwidth_fill", Duration::from_millis(500), {
+                Self::report_bandwidth_fill();
+            }),
             ("update_chunks_monitor", Duration::from_secs(5), {
                 Self::update_chunks_monitor().await;
             })
@@ -10,6 +10,21 @@ struct SpeedAndRemaining {
     remaining_time: u64,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
+struct DiskReport {
+    speed: u64,
+    queued_bytes: u64,
+    queue_capacity: u64,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct BandwidthFill {
+    global_rate: u64,
+    global_fill: f64,
+    download_rate: u64,
+    download_fill: f64,
+}
+
 impl DownloadsManager {
     pub(super) fn report_downloaded_bytes() {
         let reports = Arc::clone(&Registry::get_state().reports);
@@ -51,11 +66,7 @@ impl DownloadsManager {
             let total_downloaded_bytes = report.total_downloaded_bytes.load(Ordering::Relaxed);
             let remaining = report.total_bytes.saturating_sub(total_downloaded_bytes);
 
-            let remaining_time = if speed_history_avg == 0 {
-                0
-            } else {
-                remaining / speed_history_avg
-            };
+            let remaining_time = remaining.checked_div(speed_history_avg).unwrap_or(0);
 
             let event = format!("speed_and_remaining_{}", download_id);
             Emitter::emit_event(
@@ -70,6 +81,7 @@ impl DownloadsManager {
 
     pub(super) async fn report_disk_speed() {
         let reports = Arc::clone(&Registry::get_state().reports);
+        let workers = Arc::clone(&Registry::get_state().workers);
 
         for report in reports.iter() {
             let download_id = report.key();
@@ -88,11 +100,48 @@ impl DownloadsManager {
             let disk_speed_event = format!("disk_speed_{}", download_id);
             let wrote_bytes_event = format!("wrote_bytes_{}", download_id);
 
-            Emitter::emit_event(&disk_speed_event, speed);
+            let worker = workers.get(download_id).map(|w| Arc::clone(&w));
+            let (queued_bytes, queue_capacity) = match worker {
+                Some(worker) => {
+                    let file = Arc::clone(&worker.read().await.file);
+                    (file.queued_bytes(), file.capacity())
+                }
+                None => (0, 0),
+            };
+
+            Emitter::emit_event(
+                &disk_speed_event,
+                DiskReport {
+                    speed,
+                    queued_bytes,
+                    queue_capacity,
+                },
+            );
             Emitter::emit_event(
                 &wrote_bytes_event,
                 report.total_wrote_bytes.load(Ordering::Relaxed),
             );
         }
     }
+
+    pub(super) fn report_bandwidth_fill() {
+        let reports = Arc::clone(&Registry::get_state().reports);
+        let global_bucket = Arc::clone(&Registry::get_state().bandwidth_bucket);
+
+        let global_rate = global_bucket.rate();
+        let global_fill = global_bucket.fill_level();
+
+        reports.iter().for_each(|report| {
+            let event = format!("bandwidth_fill_{}", report.key());
+            Emitter::emit_event(
+                &event,
+                BandwidthFill {
+                    global_rate,
+                    global_fill,
+                    download_rate: report.bandwidth_bucket.rate(),
+                    download_fill: report.bandwidth_bucket.fill_level(),
+                },
+            );
+        });
+    }
 }
@@ -0,0 +1,169 @@
+use std::sync::Arc;
+
+use anyhow::bail;
+
+use crate::{
+    client::Client,
+    dispatch,
+    emitter::Emitter,
+    models::{Download, ResolverSettings, Settings, UpdateDownload},
+    registry::Registry,
+    repository::{download::DownloadRepository, settings::SettingsRepository},
+    spawn,
+    worker::DownloadStatus,
+};
+
+impl super::DownloadsManager {
+    pub async fn update_resolver_settings(settings: ResolverSettings) -> Result<(), String> {
+        settings.validate()?;
+
+        SettingsRepository::save(ResolverSettings::KEY, &settings)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        *Registry::get_state().resolver_settings.write().await = settings;
+
+        Ok(())
+    }
+
+    /// Continues a download from a new link the user found, e.g. a fresh presigned URL.
+    pub async fn refresh_download_url(download_id: i64, url: String) -> Result<(), String> {
+        if Registry::get_state().workers.contains_key(&download_id) {
+            return Err("pause the download before changing its link".to_string());
+        }
+
+        let download = DownloadRepository::find(download_id)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Self::accept_url(&download, &url)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        dispatch!(registry, NewDownload, (download_id)).map_err(|e| e.to_string())
+    }
+
+    /// Hands the download to the resolver once its worker is cleaned up, at most
+    /// `max_retries` times until it completes.
+    pub(super) async fn queue_url_resolve(download_id: i64) -> anyhow::Result<()> {
+        let state = Registry::get_state();
+
+        if state.resolver_settings.read().await.url.is_none() {
+            return Ok(());
+        }
+
+        let max_retries = DownloadRepository::find(download_id).await?.max_retries;
+        let mut resolves = state.url_resolves.entry(download_id).or_insert(0);
+
+        if *resolves < max_retries {
+            *resolves += 1;
+            state.pending_resolves.insert(download_id);
+        }
+
+        Ok(())
+    }
+
+    pub(super) async fn resolve_download_url_action(
+        self: &Arc<Self>,
+        download_id: i64,
+    ) -> anyhow::Result<()> {
+        spawn!("resolve_download_url", {
+            if let Err(err) = Self::resolve_url(download_id).await {
+                let message = format!("could not refresh the link: {}", err);
+
+                if let Err(err) = Self::set_error_message(download_id, message).await {
+                    Emitter::emit_error(err.to_string());
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    async fn resolve_url(download_id: i64) -> anyhow::Result<()> {
+        let Some(resolver) = Registry::get_state()
+            .resolver_settings
+            .read()
+            .await
+            .url
+            .clone()
+        else {
+            return Ok(());
+        };
+
+        let download = DownloadRepository::find(download_id).await?;
+
+        // The user already dealt with it.
+        if download.status != DownloadStatus::UrlExpired.to_string() {
+            return Ok(());
+        }
+
+        let url = Client::new(&resolver, &None, &download.proxy, &None, &None)?
+            .resolve_url(&download.url, download.referer.as_deref())
+            .await?;
+
+        Self::accept_url(&download, &url).await?;
+
+        dispatch!(registry, NewDownload, (download_id))
+    }
+
+    /// Switches the download to `url` once it serves the same size and validator,
+    /// the chunks written so far then simply continue.
+    async fn accept_url(download: &Download, url: &str) -> anyhow::Result<()> {
+        // Segment links come from the playlist, a new playlist link wouldn't reach them.
+        if download.is_stream() {
+            bail!("a stream can't be moved to a new link, restart it instead");
+        }
+
+        let response = Client::new(
+            url,
+            &download.auth,
+            &download.proxy,
+            &download.headers,
+            &download.cookies,
+        )?
+        .inspect()
+        .await?;
+
+        if download.total_bytes > 0 && response.content_length as i64 != download.total_bytes {
+            bail!(
+                "the new link serves {} bytes, expected {}",
+                response.content_length,
+                download.total_bytes
+            );
+        }
+
+        if !Self::same_validator(download, &response) {
+            bail!("the new link doesn't serve the same file");
+        }
+
+        DownloadRepository::update_url(download.id, url).await?;
+        DownloadRepository::clear_error_message(download.id).await
+    }
+
+    async fn set_error_message(download_id: i64, message: String) -> anyhow::Result<()> {
+        DownloadRepository::update(
+            download_id,
+            UpdateDownload {
+                error_message: Some(message),
+                status: None,
+                total_bytes: None,
+                auth: None,
+                backoff_factor: None,
+                cookies: None,
+                delay_secs: None,
+                headers: None,
+                max_retries: None,
+                proxy: None,
+                speed_limit: None,
+                timeout_secs: None,
+            },
+        )
+        .await?;
+
+        let download = DownloadRepository::find(download_id).await?;
+        Emitter::emit_event("download_item", &download);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,20 @@
+use crate::{
+    models::{RetrySettings, Settings},
+    registry::Registry,
+    repository::settings::SettingsRepository,
+};
+
+impl super::DownloadsManager {
+    /// New settings apply to streams started afterwards, running ones keep their policy.
+    pub async fn update_retry_settings(settings: RetrySettings) -> Result<(), String> {
+        settings.validate()?;
+
+        SettingsRepository::save(RetrySettings::KEY, &settings)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        *Registry::get_state().retry_settings.write().await = settings;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+use crate::{
+    client::{InspectResponse, StreamKind, StreamManifest},
+    dispatch,
+    models::DownloadSegment,
+    repository::segment::SegmentRepository,
+};
+
+use super::{actions::MAX_CHUNK_COUNT, DownloadOptions};
+
+impl super::DownloadsManager {
+    /// Variants of an HLS playlist or DASH manifest for the user to pick from.
+    pub async fn inspect_stream(
+        url: String,
+        options: DownloadOptions,
+    ) -> Result<StreamManifest, String> {
+        Self::options_client(&url, &options)?
+            .stream_manifest()
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    /// Adds the picked variant as one download. Its segments are fetched
+    /// `chunk_count` at a time and joined into a single file at the end.
+    pub async fn add_stream_download(
+        url: String,
+        kind: StreamKind,
+        variant_id: String,
+        options: DownloadOptions,
+    ) -> Result<(), String> {
+        let client = Self::options_client(&url, &options)?;
+        let playlist = client
+            .media_playlist(kind, &variant_id)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        // The size is only known once every segment is on disk.
+        let response = InspectResponse {
+            supports_range: true,
+            content_length: 0,
+            content_type: playlist.content_type,
+            file_name: client.stream_file_name(&playlist.extension),
+            extension: playlist.extension,
+            url,
+            etag: None,
+            last_modified: None,
+        };
+
+        // Each chunk is a runner working through the segments, as many as a file gets.
+        let chunk_count = options.chunk_count.clamp(1, MAX_CHUNK_COUNT);
+        let download_id =
+            Self::insert_download_row(response, &options, &kind.to_string(), true, chunk_count)
+                .await?;
+
+        let segments = playlist
+            .segments
+            .into_iter()
+            .enumerate()
+            .map(|(index, segment)| DownloadSegment {
+                download_id,
+                segment_index: index as i64,
+                url: segment.url,
+                range_start: segment.range.map(|(start, _)| start as i64),
+                range_end: segment.range.map(|(_, end)| end as i64),
+                key_url: segment.key.as_ref().map(|key| key.url.clone()),
+                iv: segment.key.map(|key| hex::encode(key.iv)),
+                downloaded_bytes: 0,
+                completed: false,
+            })
+            .collect();
+
+        if let Err(err) = SegmentRepository::create_all(download_id, segments).await {
+            Self::discard_download(download_id).await;
+            return Err(err.to_string());
+        }
+
+        dispatch!(registry, NewDownload, (download_id)).map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,257 @@
+use std::{io, sync::Arc};
+
+use crate::{
+    client::Client,
+    dispatch,
+    emitter::Emitter,
+    file::File,
+    models::{
+        Checksum, Download, DownloadPiece, Settings, UpdateChunk, UpdateDownload, VerifySettings,
+    },
+    registry::Registry,
+    repository::{
+        chunk::ChunkRepository, download::DownloadRepository, piece::PieceRepository,
+        settings::SettingsRepository,
+    },
+    spawn,
+    worker::DownloadStatus,
+};
+
+impl super::DownloadsManager {
+    pub async fn update_verify_settings(settings: VerifySettings) -> Result<(), String> {
+        settings.validate()?;
+
+        SettingsRepository::save(VerifySettings::KEY, &settings)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        *Registry::get_state().verify_settings.write().await = settings;
+
+        Ok(())
+    }
+
+    /// Checks a finished download again, optionally against a digest attached now.
+    pub async fn verify_download(
+        download_id: i64,
+        checksum: Option<Checksum>,
+    ) -> Result<(), String> {
+        if Registry::get_state().workers.contains_key(&download_id) {
+            return Err("the download is still running".to_string());
+        }
+
+        let download = DownloadRepository::find(download_id)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let finished = [
+            DownloadStatus::Completed,
+            DownloadStatus::Verified,
+            DownloadStatus::ChecksumMismatch,
+        ]
+        .iter()
+        .any(|status| status.to_string() == download.status);
+
+        if !finished {
+            return Err("only completed downloads can be verified".to_string());
+        }
+
+        if let Some(checksum) = checksum {
+            let checksum = checksum.validate().map_err(|e| e.to_string())?;
+
+            DownloadRepository::update_checksum(download_id, &checksum)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+
+        dispatch!(manager, VerifyDownload, (download_id)).map_err(|e| e.to_string())
+    }
+
+    /// Hashing a large file takes a while, so it runs beside the reducer.
+    pub(super) async fn verify_download_action(
+        self: &Arc<Self>,
+        download_id: i64,
+    ) -> anyhow::Result<()> {
+        spawn!("verify_download", {
+            if let Err(err) = Self::verify_file(download_id).await {
+                Emitter::emit_error(err.to_string());
+            }
+        });
+
+        Ok(())
+    }
+
+    async fn verify_file(download_id: i64) -> anyhow::Result<()> {
+        let download = DownloadRepository::find(download_id).await?;
+        let pieces = PieceRepository::find_all(download_id).await?;
+
+        let discover = Registry::get_state()
+            .verify_settings
+            .read()
+            .await
+            .discover_checksums;
+
+        let checksum = match download.checksum.clone() {
+            Some(checksum) => Some(checksum),
+            None if !discover => None,
+            None => {
+                let client = Client::new(
+                    &download.url,
+                    &download.auth,
+                    &download.proxy,
+                    &download.headers,
+                    &download.cookies,
+                )?;
+
+                let checksum = client.discover_checksum(&download.file_name).await;
+
+                if let Some(checksum) = &checksum {
+                    DownloadRepository::update_checksum(download_id, checksum).await?;
+                }
+
+                checksum
+            }
+        };
+
+        // Without a published digest there is nothing to verify against.
+        if checksum.is_none() && pieces.is_empty() {
+            return Ok(());
+        }
+
+        DownloadRepository::clear_error_message(download_id).await?;
+        Self::set_verify_status(download_id, DownloadStatus::Verifying, None).await?;
+
+        // Pieces tell us where the file is damaged, so they go first.
+        if !pieces.is_empty() {
+            match File::damaged_pieces(&download.file_path, pieces).await {
+                Ok(damaged) if damaged.is_empty() => {}
+                Ok(damaged) => return Self::repair_pieces(&download, damaged).await,
+                Err(err) => return Self::unreadable(&download, err).await,
+            }
+        }
+
+        Registry::get_state().piece_repairs.remove(&download_id);
+
+        let Some(checksum) = checksum else {
+            return Self::set_verify_status(download_id, DownloadStatus::Verified, None).await;
+        };
+
+        let actual = match File::checksum(&download.file_path, checksum.algorithm).await {
+            Ok(actual) => actual,
+            Err(err) => return Self::unreadable(&download, err).await,
+        };
+
+        if actual == checksum.digest {
+            return Self::set_verify_status(download_id, DownloadStatus::Verified, None).await;
+        }
+
+        let message = format!(
+            "{} expected {}, got {}",
+            checksum.algorithm, checksum.digest, actual
+        );
+
+        Self::set_verify_status(download_id, DownloadStatus::ChecksumMismatch, Some(message))
+            .await?;
+
+        Emitter::emit_notification("Checksum Mismatch", download.file_name);
+
+        Ok(())
+    }
+
+    /// The file is still complete as far as we know, we just couldn't read it.
+    async fn unreadable(download: &Download, err: io::Error) -> anyhow::Result<()> {
+        let message = format!("could not verify {}: {}", download.file_name, err);
+        Self::set_verify_status(download.id, DownloadStatus::Completed, Some(message)).await
+    }
+
+    /// Rewinds every chunk holding a damaged piece to the start of that piece and
+    /// queues the download again, so those bytes are fetched once more, from the
+    /// next mirror if the first one keeps failing. Gives up after `max_retries` rounds.
+    async fn repair_pieces(download: &Download, damaged: Vec<DownloadPiece>) -> anyhow::Result<()> {
+        let piece_repairs = Arc::clone(&Registry::get_state().piece_repairs);
+
+        let repairs = {
+            let mut repairs = piece_repairs.entry(download.id).or_insert(0);
+            *repairs += 1;
+            *repairs
+        };
+
+        let indexes = damaged
+            .iter()
+            .map(|piece| piece.piece_index.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        if repairs > download.max_retries {
+            piece_repairs.remove(&download.id);
+
+            let message = format!("pieces {} don't match the metalink", indexes);
+            Self::set_verify_status(download.id, DownloadStatus::ChecksumMismatch, Some(message))
+                .await?;
+
+            Emitter::emit_notification("Checksum Mismatch", download.file_name.clone());
+
+            return Ok(());
+        }
+
+        for chunk in ChunkRepository::find_all(download.id).await? {
+            let written_end = chunk.start_byte + chunk.downloaded_bytes;
+
+            let rewind_to = damaged
+                .iter()
+                .filter(|piece| {
+                    piece.end_byte >= chunk.start_byte && piece.start_byte < written_end
+                })
+                .map(|piece| piece.start_byte.max(chunk.start_byte) - chunk.start_byte)
+                .min();
+
+            if let Some(downloaded_bytes) = rewind_to {
+                ChunkRepository::update(
+                    download.id,
+                    UpdateChunk {
+                        chunk_index: chunk.chunk_index,
+                        downloaded_bytes,
+                        block_hashes: None,
+                    },
+                )
+                .await?;
+            }
+        }
+
+        let message = format!("downloading damaged pieces {} again", indexes);
+        Self::set_verify_status(download.id, DownloadStatus::Queued, Some(message)).await?;
+
+        dispatch!(registry, NewDownload, (download.id))
+    }
+
+    /// Written directly rather than through `UpdateDownloadStatus`, which would treat
+    /// `Completed` as a download that just finished.
+    async fn set_verify_status(
+        download_id: i64,
+        status: DownloadStatus,
+        error_message: Option<String>,
+    ) -> anyhow::Result<()> {
+        DownloadRepository::update(
+            download_id,
+            UpdateDownload {
+                status: Some(status.to_string()),
+                error_message,
+                total_bytes: None,
+                auth: None,
+                backoff_factor: None,
+                cookies: None,
+                delay_secs: None,
+                headers: None,
+                max_retries: None,
+                proxy: None,
+                speed_limit: None,
+                timeout_secs: None,
+            },
+        )
+        .await?;
+
+        let download = DownloadRepository::find(download_id).await?;
+        Emitter::emit_event("download_item", &download);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,107 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChecksumAlgorithm {
+    Sha256,
+    Sha1,
+    Md5,
+    Blake3,
+}
+
+impl ChecksumAlgorithm {
+    /// Length of the hex encoded digest.
+    pub fn hex_len(&self) -> usize {
+        match self {
+            ChecksumAlgorithm::Sha256 | ChecksumAlgorithm::Blake3 => 64,
+            ChecksumAlgorithm::Sha1 => 40,
+            ChecksumAlgorithm::Md5 => 32,
+        }
+    }
+}
+
+impl fmt::Display for ChecksumAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ChecksumAlgorithm::Sha256 => "sha256",
+            ChecksumAlgorithm::Sha1 => "sha1",
+            ChecksumAlgorithm::Md5 => "md5",
+            ChecksumAlgorithm::Blake3 => "blake3",
+        };
+
+        f.write_str(name)
+    }
+}
+
+impl FromStr for ChecksumAlgorithm {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_ascii_lowercase().replace('-', "").as_str() {
+            "sha256" => Ok(ChecksumAlgorithm::Sha256),
+            "sha1" => Ok(ChecksumAlgorithm::Sha1),
+            "md5" => Ok(ChecksumAlgorithm::Md5),
+            "blake3" => Ok(ChecksumAlgorithm::Blake3),
+            other => Err(anyhow!("unsupported checksum algorithm {other}")),
+        }
+    }
+}
+
+/// Digest the finished file is expected to have, kept as lowercase hex.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checksum {
+    pub algorithm: ChecksumAlgorithm,
+    pub digest: String,
+}
+
+impl Checksum {
+    pub fn new(algorithm: ChecksumAlgorithm, digest: &str) -> anyhow::Result<Self> {
+        let digest = digest.trim().to_ascii_lowercase();
+
+        if digest.len() != algorithm.hex_len() || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(anyhow!("invalid {algorithm} checksum {digest}"));
+        }
+
+        Ok(Self { algorithm, digest })
+    }
+
+    /// Validates whatever the caller sent, the frontend passes digests as typed.
+    pub fn validate(self) -> anyhow::Result<Self> {
+        Self::new(self.algorithm, &self.digest)
+    }
+
+    /// Finds the digest for `file_name` in a `.sha256` sidecar or a `SHA256SUMS` list.
+    /// Lines look like `<hex>  <name>`, `<hex> *<name>` for binary mode, or just `<hex>`
+    /// in a sidecar that describes a single file.
+    pub fn parse_sums(
+        algorithm: ChecksumAlgorithm,
+        content: &str,
+        file_name: &str,
+    ) -> Option<Self> {
+        let mut lone_digest = None;
+
+        for line in content.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = line.splitn(2, char::is_whitespace);
+            let digest = parts.next()?;
+            let name = parts
+                .next()
+                .map(|name| name.trim().trim_start_matches('*'))
+                .map(|name| name.rsplit('/').next().unwrap_or(name));
+
+            match name {
+                Some(name) if name == file_name => return Self::new(algorithm, digest).ok(),
+                Some(_) => {}
+                None => lone_digest = Self::new(algorithm, digest).ok(),
+            }
+        }
+
+        lone_digest
+    }
+}
@@ -8,20 +8,13 @@ pub struct DownloadChunk {
     pub start_byte: i64,
     pub end_byte: i64,
     pub downloaded_bytes: i64,
-    pub expected_hash: Option<String>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct NewChunk {
-    pub download_id: i64,
-    pub chunk_index: i64,
-    pub start_byte: i64,
-    pub end_byte: i64,
+    /// Blake3 hash of every written block, see `ChunkIntegrity`.
+    pub block_hashes: Option<Vec<u8>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UpdateChunk {
     pub chunk_index: i64,
-    pub downloaded_bytes: Option<i64>,
-    pub expected_hash: Option<String>,
+    pub downloaded_bytes: i64,
+    pub block_hashes: Option<Vec<u8>>,
 }
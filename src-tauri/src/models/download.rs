@@ -4,6 +4,7 @@ use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+use super::{Checksum, ChecksumAlgorithm};
 use crate::client::{AuthType, ProxyType};
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -31,6 +32,12 @@ pub struct DownloadRaw {
     pub timeout_secs: i64,
     pub supports_range: bool,
     pub error_message: Option<String>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub checksum_algorithm: Option<String>,
+    pub checksum: Option<String>,
+    pub referer: Option<String>,
+    pub kind: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -59,6 +66,13 @@ pub struct Download {
     pub file_exist: bool,
     pub supports_range: bool,
     pub error_message: Option<String>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub checksum: Option<Checksum>,
+    /// Page the link was found on, what a resolver gets to find a fresh one.
+    pub referer: Option<String>,
+    /// `file`, or `hls` and `dash` for streams assembled from segments.
+    pub kind: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -81,6 +95,12 @@ pub struct NewDownload {
     pub backoff_factor: Option<f64>,
     pub timeout_secs: Option<f64>,
     pub supports_range: i64,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub checksum_algorithm: Option<String>,
+    pub checksum: Option<String>,
+    pub referer: Option<String>,
+    pub kind: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -127,6 +147,14 @@ impl TryFrom<DownloadRaw> for Download {
             }
             None => None,
         };
+        // A digest we can no longer parse is dropped rather than failing the whole row.
+        let checksum = match (raw.checksum_algorithm, raw.checksum) {
+            (Some(algorithm), Some(digest)) => algorithm
+                .parse::<ChecksumAlgorithm>()
+                .and_then(|algorithm| Checksum::new(algorithm, &digest))
+                .ok(),
+            _ => None,
+        };
 
         Ok(Download {
             id: raw.id,
@@ -149,6 +177,11 @@ impl TryFrom<DownloadRaw> for Download {
             timeout_secs: raw.timeout_secs,
             supports_range: raw.supports_range,
             error_message: raw.error_message,
+            etag: raw.etag,
+            last_modified: raw.last_modified,
+            checksum,
+            referer: raw.referer,
+            kind: raw.kind,
             auth,
             proxy,
             headers,
@@ -156,3 +189,19 @@ impl TryFrom<DownloadRaw> for Download {
         })
     }
 }
+
+impl Download {
+    /// Validator for `If-Range`. Weak ETags are not allowed there,
+    /// so those fall back to `Last-Modified`.
+    pub fn if_range(&self) -> Option<String> {
+        self.etag
+            .as_ref()
+            .filter(|etag| !etag.starts_with("W/"))
+            .or(self.last_modified.as_ref())
+            .cloned()
+    }
+
+    pub fn is_stream(&self) -> bool {
+        self.kind != "file"
+    }
+}
@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Another URL serving the same file as the download's `url`.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DownloadMirror {
+    pub download_id: i64,
+    pub url: String,
+    /// Lower is preferred.
+    pub priority: i64,
+    pub location: Option<String>,
+}
@@ -1,5 +1,17 @@
+mod checksum;
 mod chunk;
 mod download;
+mod mirror;
+mod piece;
+mod retry;
+mod segment;
+mod settings;
 
+pub use checksum::*;
 pub use chunk::*;
 pub use download::*;
+pub use mirror::*;
+pub use piece::*;
+pub use retry::*;
+pub use segment::*;
+pub use settings::*;
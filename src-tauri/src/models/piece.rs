@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Published digest of a byte range of the file, from a metalink `<pieces>`.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DownloadPiece {
+    pub download_id: i64,
+    pub piece_index: i64,
+    pub start_byte: i64,
+    pub end_byte: i64,
+    pub algorithm: String,
+    pub hash: String,
+}
@@ -0,0 +1,86 @@
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Download, RetrySettings};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JitterMode {
+    None,
+    /// Anywhere between zero and the computed delay.
+    Full,
+    /// Half the computed delay plus a random share of the other half.
+    Equal,
+    /// The computed delay plus up to a quarter of it.
+    Proportional,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    pub max_retries: i64,
+    pub initial_delay: Duration,
+    pub multiplier: f64,
+    pub max_delay: Duration,
+    pub max_elapsed: Duration,
+    pub jitter: JitterMode,
+}
+
+impl RetryPolicy {
+    /// Attempts and backoff come from the download, the limits from the retry settings.
+    pub fn new(download: &Download, settings: &RetrySettings) -> Self {
+        Self {
+            max_retries: download.max_retries.max(0),
+            initial_delay: Duration::from_secs_f64(download.delay_secs.max(0.0)),
+            multiplier: download.backoff_factor.max(1.0),
+            max_delay: Duration::from_secs(settings.max_delay_secs),
+            max_elapsed: Duration::from_secs(settings.max_elapsed_secs),
+            jitter: settings.jitter,
+        }
+    }
+
+    /// Backoff before retry number `attempt`, starting at one.
+    pub fn delay(&self, attempt: i64) -> Duration {
+        let exponent = (attempt - 1).max(0) as i32;
+        let secs = (self.initial_delay.as_secs_f64() * self.multiplier.powi(exponent))
+            .min(self.max_delay.as_secs_f64());
+
+        let secs = match self.jitter {
+            JitterMode::None => secs,
+            JitterMode::Full => secs * fastrand::f64(),
+            JitterMode::Equal => secs / 2.0 + secs / 2.0 * fastrand::f64(),
+            JitterMode::Proportional => secs * (1.0 + fastrand::f64() * 0.25),
+        };
+
+        Duration::from_secs_f64(secs)
+    }
+}
+
+/// Failure streak of a single chunk stream, cleared whenever the stream makes progress.
+#[derive(Debug, Default)]
+pub struct RetryState {
+    attempts: i64,
+    streak_start: Option<Instant>,
+}
+
+impl RetryState {
+    /// Counts a failure and returns the attempt number to retry with, or `None` once
+    /// the policy is exhausted. `wait` is a delay the server asked for, a streak that
+    /// would outlast `max_elapsed` by waiting it out gives up instead.
+    pub fn next_attempt(&mut self, policy: &RetryPolicy, wait: Option<Duration>) -> Option<i64> {
+        let streak_start = *self.streak_start.get_or_insert_with(Instant::now);
+        let elapsed = streak_start.elapsed() + wait.unwrap_or_default();
+
+        if self.attempts >= policy.max_retries || elapsed >= policy.max_elapsed {
+            return None;
+        }
+
+        self.attempts += 1;
+        Some(self.attempts)
+    }
+
+    pub fn reset(&mut self) {
+        self.attempts = 0;
+        self.streak_start = None;
+    }
+}
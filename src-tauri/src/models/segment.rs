@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// One media segment of an HLS or DASH download, fetched on its own and joined
+/// with the others in `segment_index` order once all of them are done.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DownloadSegment {
+    pub download_id: i64,
+    pub segment_index: i64,
+    pub url: String,
+    /// Inclusive byte range within `url`, for byte range playlists and DASH index ranges.
+    pub range_start: Option<i64>,
+    pub range_end: Option<i64>,
+    /// AES-128 key the segment is encrypted with.
+    pub key_url: Option<String>,
+    /// Hex encoded, only set together with `key_url`.
+    pub iv: Option<String>,
+    pub downloaded_bytes: i64,
+    pub completed: bool,
+}
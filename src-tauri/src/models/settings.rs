@@ -0,0 +1,247 @@
+use chrono::{Datelike, NaiveDateTime, NaiveTime, Weekday};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::JitterMode;
+
+/// Settings stored as JSON under `KEY` and loaded into the registry on startup.
+pub trait Settings: Serialize + DeserializeOwned + Default {
+    const KEY: &'static str;
+
+    fn validate(&self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BandwidthSettings {
+    pub global_limit: Option<i64>,
+    #[serde(default)]
+    pub schedules: Vec<BandwidthSchedule>,
+    /// Streams allowed against a single host across all downloads, so servers that
+    /// rate-limit by connection are never hammered.
+    #[serde(default = "BandwidthSettings::default_max_streams_per_host")]
+    pub max_streams_per_host: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BandwidthSchedule {
+    pub days: Vec<Weekday>,
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    pub limit: i64,
+}
+
+impl BandwidthSchedule {
+    /// A schedule whose end is before its start runs overnight into the next day.
+    fn is_active(&self, now: NaiveDateTime) -> bool {
+        let time = now.time();
+        let today = now.weekday();
+
+        if self.start < self.end {
+            return self.days.contains(&today) && time >= self.start && time < self.end;
+        }
+
+        (self.days.contains(&today) && time >= self.start)
+            || (self.days.contains(&today.pred()) && time < self.end)
+    }
+}
+
+impl Default for BandwidthSettings {
+    fn default() -> Self {
+        Self {
+            global_limit: None,
+            schedules: Vec::new(),
+            max_streams_per_host: Self::default_max_streams_per_host(),
+        }
+    }
+}
+
+impl BandwidthSettings {
+    fn default_max_streams_per_host() -> usize {
+        8
+    }
+
+    /// Bytes per second allowed at `now`, zero means unlimited.
+    /// The first matching schedule wins, otherwise the global limit applies.
+    pub fn active_limit(&self, now: NaiveDateTime) -> u64 {
+        let limit = self
+            .schedules
+            .iter()
+            .find(|schedule| schedule.is_active(now))
+            .map(|schedule| schedule.limit)
+            .or(self.global_limit)
+            .unwrap_or(0);
+
+        limit.max(0) as u64
+    }
+}
+
+impl Settings for BandwidthSettings {
+    const KEY: &'static str = "bandwidth";
+
+    fn validate(&self) -> Result<(), String> {
+        if self.global_limit.is_some_and(|limit| limit < 0) {
+            return Err("global bandwidth limit cannot be negative".to_string());
+        }
+        if self.max_streams_per_host == 0 || self.max_streams_per_host > 32 {
+            return Err("streams per host must be between 1 and 32".to_string());
+        }
+
+        for schedule in &self.schedules {
+            if schedule.limit < 0 {
+                return Err("scheduled bandwidth limit cannot be negative".to_string());
+            }
+            if schedule.start == schedule.end {
+                return Err("bandwidth schedule start and end cannot be equal".to_string());
+            }
+            if schedule.days.is_empty() {
+                return Err("bandwidth schedule must have at least one day".to_string());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiskSettings {
+    /// Contiguous bytes buffered per chunk before they are written out.
+    pub write_buffer_size: u64,
+    /// A buffer older than this is written out even when it isn't full.
+    pub flush_interval_ms: u64,
+    /// Written but unsynced bytes after which the file is fsynced.
+    pub sync_bytes: u64,
+    /// Longest time written bytes stay unsynced.
+    pub sync_interval_secs: u64,
+    /// Received bytes a download may hold in memory before its streams wait for the disk.
+    pub write_queue_bytes: u64,
+    /// How much of a chunk's written bytes is read back and checked before it resumes.
+    pub resume_verification: ResumeVerification,
+    /// Blocks checked per chunk when sampling, the last block is always among them.
+    pub resume_sample_blocks: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResumeVerification {
+    Full,
+    Sampled,
+}
+
+impl Default for DiskSettings {
+    fn default() -> Self {
+        Self {
+            write_buffer_size: 1024 * 1024,
+            flush_interval_ms: 1000,
+            sync_bytes: 64 * 1024 * 1024,
+            sync_interval_secs: 30,
+            write_queue_bytes: 32 * 1024 * 1024,
+            resume_verification: ResumeVerification::Full,
+            resume_sample_blocks: 16,
+        }
+    }
+}
+
+impl Settings for DiskSettings {
+    const KEY: &'static str = "disk";
+
+    fn validate(&self) -> Result<(), String> {
+        if self.write_buffer_size < 16 * 1024 || self.write_buffer_size > 64 * 1024 * 1024 {
+            return Err("write buffer size must be between 16 KiB and 64 MiB".to_string());
+        }
+        if self.flush_interval_ms == 0 {
+            return Err("flush interval must be greater than zero".to_string());
+        }
+        if self.sync_bytes < self.write_buffer_size {
+            return Err("sync threshold cannot be smaller than the write buffer".to_string());
+        }
+        if self.sync_interval_secs == 0 {
+            return Err("sync interval must be greater than zero".to_string());
+        }
+        // Every chunk can park a full buffer in the queue, leave room for the rest.
+        if self.write_queue_bytes < self.write_buffer_size * 4
+            || self.write_queue_bytes > u32::MAX as u64
+        {
+            return Err(
+                "write queue must hold at least four write buffers and at most 4 GiB".to_string(),
+            );
+        }
+        if self.resume_verification == ResumeVerification::Sampled && self.resume_sample_blocks == 0
+        {
+            return Err("sampled verification must check at least one block".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResolverSettings {
+    /// Endpoint asked for a fresh link when one expires, `None` leaves it to the user.
+    /// It gets the old link as `url` and the page it came from as `referer`, and
+    /// answers with the new link as plain text.
+    pub url: Option<String>,
+}
+
+impl Settings for ResolverSettings {
+    const KEY: &'static str = "resolver";
+
+    fn validate(&self) -> Result<(), String> {
+        if let Some(url) = &self.url {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                return Err("resolver must be an http or https url".to_string());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VerifySettings {
+    /// Look for a `.sha256` or `SHA256SUMS` next to a finished download that has no
+    /// checksum. Off by default, the requests carry the download's credentials.
+    pub discover_checksums: bool,
+}
+
+impl Settings for VerifySettings {
+    const KEY: &'static str = "verify";
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetrySettings {
+    /// No single backoff wait grows past this, whatever the multiplier says.
+    pub max_delay_secs: u64,
+    /// A failure streak longer than this gives up even with retries left.
+    pub max_elapsed_secs: u64,
+    pub jitter: JitterMode,
+}
+
+impl Default for RetrySettings {
+    fn default() -> Self {
+        Self {
+            max_delay_secs: 5 * 60,
+            max_elapsed_secs: 30 * 60,
+            jitter: JitterMode::Proportional,
+        }
+    }
+}
+
+impl Settings for RetrySettings {
+    const KEY: &'static str = "retry";
+
+    fn validate(&self) -> Result<(), String> {
+        if self.max_delay_secs == 0 {
+            return Err("max retry delay must be greater than zero".to_string());
+        }
+        if self.max_elapsed_secs < self.max_delay_secs {
+            return Err("retry window cannot be shorter than the max retry delay".to_string());
+        }
+
+        Ok(())
+    }
+}
@@ -1,8 +1,8 @@
 use std::{
     collections::VecDeque,
     sync::{
-        atomic::{AtomicBool, AtomicU64},
-        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex as StdMutex,
     },
     time::Instant,
 };
@@ -16,9 +16,11 @@ use super::super::{Registry, Report};
 use crate::{
     dispatch,
     emitter::Emitter,
-    file::File,
-    repository::{chunk::ChunkRepository, download::DownloadRepository},
-    worker::Worker,
+    file::{ChunkIntegrity, File},
+    manager::DownloadsManager,
+    models::UpdateDownload,
+    repository::{chunk::ChunkRepository, download::DownloadRepository, mirror::MirrorRepository},
+    worker::{TokenBucket, Worker},
 };
 
 pub trait DownloadActions {
@@ -27,6 +29,7 @@ pub trait DownloadActions {
     async fn pause_download(download_id: i64) -> anyhow::Result<()>;
     async fn resume_download(download_id: i64) -> anyhow::Result<()>;
     async fn remove_download(download_id: i64, remove_file: bool) -> anyhow::Result<()>;
+    async fn update_speed_limit(download_id: i64, speed_limit: Option<i64>) -> anyhow::Result<()>;
     async fn prepare_download_data(download_id: i64) -> anyhow::Result<()>;
     async fn clean_download_data(download_id: i64) -> anyhow::Result<()>;
 }
@@ -34,14 +37,14 @@ pub trait DownloadActions {
 impl DownloadActions for Registry {
     async fn recover_downloads() -> anyhow::Result<()> {
         let downloads = DownloadRepository::find_all(None).await?;
-        let ids = downloads
-            .into_iter()
-            .filter(|d| d.status == "downloading" || d.status == "queued")
-            .map(|d| d.id)
-            .collect::<Vec<i64>>();
 
-        for id in ids {
-            dispatch!(registry, NewDownload, (id))?
+        for download in downloads {
+            match download.status.as_str() {
+                "downloading" | "queued" => dispatch!(registry, NewDownload, (download.id))?,
+                // Verification was cut short, the file itself is complete.
+                "verifying" => dispatch!(manager, VerifyDownload, (download.id))?,
+                _ => {}
+            }
         }
 
         Ok(())
@@ -69,10 +72,67 @@ impl DownloadActions for Registry {
     async fn remove_download(download_id: i64, remove_file: bool) -> anyhow::Result<()> {
         let file_path = DownloadRepository::delete(download_id).await?;
 
+        File::remove_segments(&file_path)?;
+
         if remove_file {
             File::remove_file(&file_path)?;
+        } else {
+            // A part file is useless once its download is gone.
+            File::remove_part_file(&file_path)?;
+        }
+
+        Ok(())
+    }
+
+    async fn update_speed_limit(download_id: i64, speed_limit: Option<i64>) -> anyhow::Result<()> {
+        // Zero means unlimited, since `UpdateDownload` cannot set the column back to NULL.
+        let speed_limit = speed_limit.unwrap_or(0).max(0);
+
+        DownloadRepository::update(
+            download_id,
+            UpdateDownload {
+                speed_limit: Some(speed_limit),
+                status: None,
+                total_bytes: None,
+                auth: None,
+                proxy: None,
+                headers: None,
+                cookies: None,
+                max_retries: None,
+                delay_secs: None,
+                backoff_factor: None,
+                timeout_secs: None,
+                error_message: None,
+            },
+        )
+        .await?;
+
+        if let Some(report) = Self::get_state().reports.get(&download_id) {
+            report
+                .speed_limit
+                .store(speed_limit as u64, Ordering::Relaxed);
+
+            let bandwidth_limit = Self::get_state().bandwidth_limit.load(Ordering::Relaxed);
+            report
+                .bandwidth_bucket
+                .set_rate(DownloadsManager::effective_limit(
+                    bandwidth_limit,
+                    speed_limit as u64,
+                ));
+        }
+
+        let worker = Self::get_state()
+            .workers
+            .get(&download_id)
+            .map(|w| Arc::clone(&w));
+
+        if let Some(worker) = worker {
+            worker.write().await.download.speed_limit = Some(speed_limit);
         }
 
+        let download = DownloadRepository::find(download_id).await?;
+        Emitter::emit_event("download_item", download);
+
         Ok(())
     }
 
@@ -80,14 +140,73 @@ impl DownloadActions for Registry {
         let workers = Arc::clone(&Self::get_state().workers);
         let reports = Arc::clone(&Self::get_state().reports);
 
-        let download = DownloadRepository::find(download_id).await?;
-        let chunks = ChunkRepository::find_all(download_id).await?;
+        // Whatever stopped the previous run no longer applies.
+        DownloadRepository::clear_error_message(download_id).await?;
+
+        let mut download = DownloadRepository::find(download_id).await?;
+        let mut chunks = ChunkRepository::find_all(download_id).await?;
+
+        // A partial download of unknown size continues only when the server now
+        // advertises a size and ranges for the same file. Streams keep their
+        // progress per segment and learn their size once joined.
+        if download.total_bytes == 0 && download.downloaded_bytes > 0 && !download.is_stream() {
+            if let Some(total_bytes) = DownloadsManager::probe_resumable(&download).await {
+                DownloadRepository::update_remote(
+                    download_id,
+                    total_bytes,
+                    true,
+                    download.etag.clone(),
+                    download.last_modified.clone(),
+                )
+                .await?;
+
+                for chunk in chunks.iter().filter(|chunk| chunk.end_byte < 0) {
+                    ChunkRepository::update_end_byte(
+                        download_id,
+                        chunk.chunk_index,
+                        total_bytes - 1,
+                    )
+                    .await?;
+                }
+
+                download = DownloadRepository::find(download_id).await?;
+                chunks = ChunkRepository::find_all(download_id).await?;
+            }
+        }
+
+        // Without range support the only way to resume is from the first byte.
+        let single_fresh_chunk = matches!(
+            chunks.as_slice(),
+            [chunk] if chunk.start_byte == 0 && chunk.downloaded_bytes == 0
+        );
+
+        if !download.supports_range && !single_fresh_chunk {
+            ChunkRepository::delete_all(download_id).await?;
+            ChunkRepository::create(download_id, 0, (0, download.total_bytes - 1)).await?;
+
+            download = DownloadRepository::find(download_id).await?;
+            chunks = ChunkRepository::find_all(download_id).await?;
+        }
 
         let not_downloaded_chunks = chunks
             .into_iter()
-            .filter(|chunk| chunk.downloaded_bytes < chunk.end_byte - chunk.start_byte)
+            .filter(|chunk| {
+                chunk.end_byte < 0 || chunk.downloaded_bytes < chunk.end_byte - chunk.start_byte
+            })
             .collect::<Vec<_>>();
 
+        File::adopt_legacy_partial(&download.file_path).await?;
+
+        let mirrors = std::iter::once(download.url.clone())
+            .chain(
+                MirrorRepository::find_all(download_id)
+                    .await?
+                    .into_iter()
+                    .map(|mirror| mirror.url)
+                    .filter(|url| *url != download.url),
+            )
+            .collect();
+
         let file = File::new(
             download_id,
             &download.file_path,
@@ -102,6 +221,7 @@ impl DownloadActions for Registry {
                 chunks: not_downloaded_chunks.clone(),
                 cancel_token: Arc::new(CancellationToken::new()),
                 file: Arc::new(file),
+                mirrors,
             })),
         );
 
@@ -110,7 +230,20 @@ impl DownloadActions for Registry {
             .map(|f| (f.chunk_index, AtomicU64::new(f.downloaded_bytes as u64)))
             .collect();
 
-        let buffer = File::get_chunks_bytes_from_file(download.id).await?;
+        let integrity = not_downloaded_chunks
+            .iter()
+            .map(|chunk| {
+                let integrity = ChunkIntegrity::resume(
+                    chunk.start_byte as u64,
+                    chunk.downloaded_bytes as u64,
+                    chunk.block_hashes.clone(),
+                );
+                (chunk.chunk_index, Arc::new(StdMutex::new(integrity)))
+            })
+            .collect();
+
+        let speed_limit = download.speed_limit.unwrap_or(0).max(0) as u64;
+        let bandwidth_limit = Self::get_state().bandwidth_limit.load(Ordering::Relaxed);
 
         reports.insert(
             download.id,
@@ -126,12 +259,17 @@ impl DownloadActions for Registry {
                 speed_bps: AtomicU64::new(0),
                 last_update_downloaded_bytes: AtomicU64::new(download.downloaded_bytes as u64),
                 stable_speed: AtomicBool::new(false),
+                speed_limit: AtomicU64::new(speed_limit),
+                bandwidth_bucket: TokenBucket::new(DownloadsManager::effective_limit(
+                    bandwidth_limit,
+                    speed_limit,
+                )),
                 last_update_time: Arc::new(Mutex::new(Instant::now())),
-                buffer: Arc::new(buffer),
+                integrity,
             }),
         );
 
-        dispatch!(manager, StartDownload, (download.id));
+        dispatch!(manager, StartDownload, (download.id))?;
 
         Ok(())
     }
@@ -143,6 +281,22 @@ impl DownloadActions for Registry {
         reports.remove(&download_id);
         workers.remove(&download_id);
 
+        if Self::get_state()
+            .pending_restarts
+            .remove(&download_id)
+            .is_some()
+        {
+            dispatch!(registry, NewDownload, (download_id))?;
+        }
+
+        if Self::get_state()
+            .pending_resolves
+            .remove(&download_id)
+            .is_some()
+        {
+            dispatch!(manager, ResolveDownloadUrl, (download_id))?;
+        }
+
         Ok(())
     }
 }
@@ -1,5 +1,4 @@
 use super::super::Registry;
-use anyhow::anyhow;
 
 use std::sync::{
     atomic::{AtomicU64, Ordering},
@@ -8,16 +7,12 @@ use std::sync::{
 
 pub trait ReportActions {
     async fn update_network_report(download_id: i64, bytes_len: u64) -> anyhow::Result<()>;
+    async fn rewind_network_report(download_id: i64, bytes_len: u64) -> anyhow::Result<()>;
     async fn update_disk_report(
         download_id: i64,
         chunk_index: i64,
         bytes_len: u64,
     ) -> anyhow::Result<()>;
-    async fn update_chunk_buffer_report(
-        download_id: i64,
-        chunk_index: i64,
-        bytes: Vec<u8>,
-    ) -> anyhow::Result<()>;
 }
 
 impl ReportActions for Registry {
@@ -31,7 +26,7 @@ impl ReportActions for Registry {
         if let Some(report) = maybe_report {
             report
                 .chunks_wrote_bytes
-                .entry(chunk_index as i64)
+                .entry(chunk_index)
                 .and_modify(|atomic| {
                     atomic.fetch_add(bytes_len, Ordering::Relaxed);
                 })
@@ -63,44 +58,17 @@ impl ReportActions for Registry {
         Ok(())
     }
 
-    async fn update_chunk_buffer_report(
-        download_id: i64,
-        chunk_index: i64,
-        bytes: Vec<u8>,
-    ) -> anyhow::Result<()> {
+    /// Takes back progress of bytes that will be downloaded again. They still
+    /// went over the network, so the speed keeps them.
+    async fn rewind_network_report(download_id: i64, bytes_len: u64) -> anyhow::Result<()> {
         let reports = Arc::clone(&Self::get_state().reports);
-        let report = reports.get(&download_id).ok_or_else(|| {
-            anyhow!(
-                "update chunk buffer error: cannot find report with download id {}",
-                download_id
-            )
-        })?;
-
-        let buffer = report.buffer.get(&chunk_index).ok_or_else(|| {
-            anyhow!(
-                "update chunk buffer error: cannot find buffer report with download id {} and index {}",
-                download_id,
-                chunk_index
-            )
-        })?;
-
-        let mut buffer = buffer.lock().await;
-
-        if buffer.first.len() < 1024 {
-            let remaining = 1024 - buffer.first.len();
-            let take = remaining.min(bytes.len());
-            buffer.first.extend_from_slice(&bytes[..take]);
-        }
-
-        if bytes.len() >= 1024 {
-            buffer.last.clear();
-            buffer.last.extend_from_slice(&&bytes[bytes.len() - 1024..]);
-        } else {
-            buffer.last.extend_from_slice(&bytes);
-            if buffer.last.len() > 1024 {
-                let drop = buffer.last.len() - 1024;
-                let _ = buffer.last.split_to(drop);
-            }
+        let maybe_report = reports.get(&download_id);
+        if let Some(report) = maybe_report {
+            let _ = report.total_downloaded_bytes.fetch_update(
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+                |total| Some(total.saturating_sub(bytes_len)),
+            );
         }
 
         Ok(())
@@ -1,7 +1,4 @@
-use std::{
-    sync::{atomic::Ordering, Arc},
-    time::Instant,
-};
+use std::sync::{atomic::Ordering, Arc};
 
 use log::debug;
 use serde::Serialize;
@@ -16,8 +13,6 @@ use super::super::Registry;
 pub struct Task {
     pub name: String,
     pub status: TaskStatus,
-    #[serde(skip)]
-    pub start_time: Instant,
 }
 
 #[derive(Debug, Serialize)]
@@ -37,7 +32,7 @@ pub trait SystemActions {
 impl SystemActions for Registry {
     async fn check_available_permit() -> anyhow::Result<()> {
         queue_spawn!("check_available_permit_action", {
-            let state = Arc::clone(&Self::get_state());
+            let state = Arc::clone(Self::get_state());
             let pending_queue = Arc::clone(&state.pending_queue);
 
             let download_id = {
@@ -81,7 +76,11 @@ impl SystemActions for Registry {
 
                         if permit_available {
                             pending_queue.lock().await.pop_front();
-                            dispatch!(registry, PrepareDownloadData, (download_id));
+                            if let Err(err) =
+                                dispatch!(registry, PrepareDownloadData, (download_id))
+                            {
+                                Emitter::emit_error(err.to_string());
+                            }
                         }
                     }
                     Err(_) => {
@@ -108,13 +107,11 @@ impl SystemActions for Registry {
 
     async fn add_task(task_id: u64, task_name: impl Into<String>) -> anyhow::Result<()> {
         let tasks = Arc::clone(&Self::get_state().tasks);
-        let now = Instant::now();
         tasks.insert(
             task_id,
             Task {
                 name: task_name.into(),
                 status: TaskStatus::Running,
-                start_time: now,
             },
         );
 
@@ -129,11 +126,8 @@ impl SystemActions for Registry {
     async fn change_task_status(task_id: u64, status: TaskStatus) -> anyhow::Result<()> {
         let tasks = Arc::clone(&Self::get_state().tasks);
 
-        match status {
-            TaskStatus::Completed => {
-                tasks.remove(&task_id);
-            }
-            _ => {}
+        if let TaskStatus::Completed = status {
+            tasks.remove(&task_id);
         };
 
         for task in tasks.iter() {
@@ -10,6 +10,7 @@ pub enum RegistryAction {
     NewDownload(/*Download ID */ i64),
     CheckAvailablePermit,
     UpdateNetworkReport(/*Download ID */ i64, /*Bytes len*/ u64),
+    RewindNetworkReport(/*Download ID */ i64, /*Bytes len*/ u64),
     UpdateDiskReport(
         /* Download ID */ i64,
         /* Chunk Index */ i64,
@@ -20,13 +21,12 @@ pub enum RegistryAction {
     ResumeDownload(/* Download ID */ i64),
     RecoverDownloads,
     RemoveDownload(/* Download ID */ i64, /* Remove File */ bool),
-    CloseRequested,
-    PrepareDownloadData(/* Download ID */ i64),
-    UpdateChunkBufferReport(
+    UpdateSpeedLimit(
         /* Download ID */ i64,
-        /* Chunk Index */ i64,
-        /* Bytes */ Vec<u8>,
+        /* Bytes per second */ Option<i64>,
     ),
+    CloseRequested,
+    PrepareDownloadData(/* Download ID */ i64),
     AddTask(u64, String),
     ChangeTaskStatus(u64, TaskStatus),
 }
@@ -49,6 +49,9 @@ impl super::Registry {
             RemoveDownload(download_id, remove_file) => {
                 Self::remove_download(download_id, remove_file).await
             }
+            UpdateSpeedLimit(download_id, speed_limit) => {
+                Self::update_speed_limit(download_id, speed_limit).await
+            }
             NewDownload(download_id) => Self::new_download(download_id).await,
             RecoverDownloads => Self::recover_downloads().await,
             PrepareDownloadData(download_id) => Self::prepare_download_data(download_id).await,
@@ -58,12 +61,12 @@ impl super::Registry {
             UpdateNetworkReport(download_id, bytes_len) => {
                 Self::update_network_report(download_id, bytes_len).await
             }
+            RewindNetworkReport(download_id, bytes_len) => {
+                Self::rewind_network_report(download_id, bytes_len).await
+            }
             UpdateDiskReport(download_id, chunk_index, bytes_len) => {
                 Self::update_disk_report(download_id, chunk_index, bytes_len).await
             }
-            UpdateChunkBufferReport(download_id, chunk_index, bytes) => {
-                Self::update_chunk_buffer_report(download_id, chunk_index, bytes).await
-            }
 
             // System
             CheckAvailablePermit => Self::check_available_permit().await,
@@ -1,6 +1,17 @@
-use crate::{dispatch, emitter::Emitter, manager::DownloadsManager, spawn, worker::Worker};
+use crate::{
+    dispatch,
+    emitter::Emitter,
+    file::ChunkIntegrity,
+    manager::DownloadsManager,
+    models::{
+        BandwidthSettings, DiskSettings, ResolverSettings, RetrySettings, Settings, VerifySettings,
+    },
+    repository::settings::SettingsRepository,
+    spawn,
+    worker::{HostSlots, TokenBucket, Worker},
+};
 use atomic_float::AtomicF64;
-use dashmap::DashMap;
+use dashmap::{DashMap, DashSet};
 use log::debug;
 use once_cell::sync::OnceCell;
 use sqlx::SqlitePool;
@@ -8,7 +19,7 @@ use std::{
     collections::VecDeque,
     sync::{
         atomic::{AtomicBool, AtomicU64, AtomicUsize},
-        Arc,
+        Arc, Mutex as StdMutex,
     },
     time::Instant,
 };
@@ -17,7 +28,7 @@ use tokio::sync::{
     mpsc::{self, UnboundedReceiver},
     Mutex, RwLock, Semaphore,
 };
-use tokio_util::{bytes::BytesMut, sync::CancellationToken};
+use tokio_util::sync::CancellationToken;
 
 mod actions;
 mod event;
@@ -26,12 +37,6 @@ mod pool;
 pub use actions::TaskStatus;
 pub use event::RegistryAction;
 
-#[derive(Debug)]
-pub struct Buffer {
-    pub first: BytesMut,
-    pub last: BytesMut,
-}
-
 #[derive(Debug)]
 pub struct Report {
     pub total_downloaded_bytes: AtomicU64,
@@ -46,7 +51,9 @@ pub struct Report {
     pub last_update_downloaded_bytes: AtomicU64,
     pub last_update_time: Arc<Mutex<Instant>>,
     pub stable_speed: AtomicBool,
-    pub buffer: Arc<DashMap<i64, Arc<Mutex<Buffer>>>>,
+    pub speed_limit: AtomicU64,
+    pub bandwidth_bucket: TokenBucket,
+    pub integrity: DashMap<i64, Arc<StdMutex<ChunkIntegrity>>>,
 }
 
 #[derive(Debug)]
@@ -62,6 +69,20 @@ pub struct State {
     pub reports: Arc<DashMap<i64, Arc<Report>>>,
     pub monitor_running: Arc<AtomicBool>,
     pub bandwidth_limit: Arc<AtomicF64>,
+    pub bandwidth_bucket: Arc<TokenBucket>,
+    pub bandwidth_settings: Arc<RwLock<BandwidthSettings>>,
+    pub disk_settings: Arc<RwLock<DiskSettings>>,
+    pub resolver_settings: Arc<RwLock<ResolverSettings>>,
+    pub verify_settings: Arc<RwLock<VerifySettings>>,
+    pub retry_settings: Arc<RwLock<RetrySettings>>,
+    pub host_slots: Arc<DashMap<String, Arc<HostSlots>>>,
+    pub pending_restarts: Arc<DashSet<i64>>,
+    /// Times a download was sent back for pieces that failed verification.
+    pub piece_repairs: Arc<DashMap<i64, i64>>,
+    /// Downloads whose link expired, handed to the resolver once their worker is gone.
+    pub pending_resolves: Arc<DashSet<i64>>,
+    /// Times the resolver was asked for a fresh link per download.
+    pub url_resolves: Arc<DashMap<i64, i64>>,
     pub download_speed: Arc<AtomicF64>,
     pub spawn_cancellation_token: Arc<CancellationToken>,
     queue_listener_running: Arc<AtomicBool>,
@@ -74,6 +95,7 @@ static STATE: OnceCell<Arc<State>> = OnceCell::new();
 pub struct Registry;
 
 impl Registry {
+    #[allow(clippy::new_ret_no_self)]
     pub async fn new(app_handle: AppHandle) {
         let max_concurrent_tasks = Self::detect_max_concurrent_tasks();
         let current_tasks = Arc::new(Semaphore::new(max_concurrent_tasks));
@@ -89,6 +111,17 @@ impl Registry {
         let manager = OnceCell::new();
         let monitor_running = Arc::new(AtomicBool::new(false));
         let bandwidth_limit = Arc::new(AtomicF64::new(0.0));
+        let bandwidth_bucket = Arc::new(TokenBucket::new(0));
+        let bandwidth_settings = Arc::new(RwLock::new(BandwidthSettings::default()));
+        let disk_settings = Arc::new(RwLock::new(DiskSettings::default()));
+        let resolver_settings = Arc::new(RwLock::new(ResolverSettings::default()));
+        let verify_settings = Arc::new(RwLock::new(VerifySettings::default()));
+        let retry_settings = Arc::new(RwLock::new(RetrySettings::default()));
+        let host_slots = Arc::new(DashMap::new());
+        let pending_restarts = Arc::new(DashSet::new());
+        let piece_repairs = Arc::new(DashMap::new());
+        let pending_resolves = Arc::new(DashSet::new());
+        let url_resolves = Arc::new(DashMap::new());
         let spawn_cancellation_token = Arc::new(CancellationToken::new());
         let download_speed = Arc::new(AtomicF64::new(0.0));
         let tasks = Arc::new(DashMap::new());
@@ -108,6 +141,17 @@ impl Registry {
             download_speed,
             monitor_running,
             bandwidth_limit,
+            bandwidth_bucket,
+            bandwidth_settings,
+            disk_settings,
+            resolver_settings,
+            verify_settings,
+            retry_settings,
+            host_slots,
+            pending_restarts,
+            piece_repairs,
+            pending_resolves,
+            url_resolves,
             available_permits,
             queue_listener_running,
             spawn_cancellation_token,
@@ -117,14 +161,36 @@ impl Registry {
         Self::initialize_mpsc_action(rx);
         Self::initialize_manager();
 
-        dispatch!(registry, RecoverDownloads);
+        let state = Self::get_state();
+        Self::initialize_settings(&state.bandwidth_settings).await;
+        Self::initialize_settings(&state.disk_settings).await;
+        Self::initialize_settings(&state.resolver_settings).await;
+        Self::initialize_settings(&state.verify_settings).await;
+        Self::initialize_settings(&state.retry_settings).await;
+        DownloadsManager::apply_bandwidth_schedule().await;
+
+        if let Err(err) = dispatch!(registry, RecoverDownloads) {
+            Emitter::emit_error(err.to_string());
+        }
+    }
+
+    /// Stored settings that no longer validate are left at their defaults.
+    async fn initialize_settings<T: Settings>(settings: &RwLock<T>) {
+        match SettingsRepository::find::<T>(T::KEY).await {
+            Ok(Some(stored)) => match stored.validate() {
+                Ok(()) => *settings.write().await = stored,
+                Err(err) => Emitter::emit_error(format!("{} settings: {}", T::KEY, err)),
+            },
+            Ok(None) => {}
+            Err(err) => Emitter::emit_error(err.to_string()),
+        }
     }
 
     fn initialize_mpsc_action(mut rx: UnboundedReceiver<RegistryAction>) {
         spawn!("registry_mpsc", {
             while let Some(action) = rx.recv().await {
                 if let Err(err) = Self::reducer(action).await {
-                    debug!("{}", err.to_string());
+                    debug!("{}", err);
                     Emitter::emit_error(err.to_string());
                 }
             }
@@ -133,7 +199,7 @@ impl Registry {
 
     fn initialize_manager() {
         let manager = DownloadsManager::new();
-        if let Err(_) = Self::get_state().manager.set(manager) {
+        if Self::get_state().manager.set(manager).is_err() {
             Emitter::emit_error("manager is already initialized");
         }
     }
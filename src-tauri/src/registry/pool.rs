@@ -9,10 +9,7 @@ impl super::Registry {
     pub(super) async fn init_db(path: &Result<PathBuf, tauri::Error>) -> SqlitePool {
         let db_url = {
             if cfg!(debug_assertions) {
-                format!(
-                    "{}",
-                    std::env::var("DATABASE_URL").expect("DATABASE_URL in .env file not set")
-                )
+                std::env::var("DATABASE_URL").expect("DATABASE_URL in .env file not set")
             } else {
                 let data_path = path.as_ref().expect("failed to get app data directory");
                 let is_exist = std::fs::metadata(data_path).is_ok();
@@ -10,7 +10,10 @@ impl ChunkRepository {
         let pool = Registry::get_pool();
         sqlx::query_as!(
             DownloadChunk,
-            "SELECT * FROM download_chunks WHERE download_id = ?;",
+            r#"
+            SELECT download_id, chunk_index, start_byte, end_byte, downloaded_bytes, block_hashes
+            FROM download_chunks WHERE download_id = ?;
+            "#,
             download_id
         )
         .fetch_all(pool)
@@ -63,6 +66,85 @@ impl ChunkRepository {
         .map(|_| ())
     }
 
+    pub async fn next_index(download_id: i64) -> Result<i64, sqlx::Error> {
+        let pool = Registry::get_pool();
+
+        sqlx::query_scalar!(
+            r#"SELECT COALESCE(MAX(chunk_index) + 1, 0) AS "next_index!: i64" FROM download_chunks WHERE download_id = ?"#,
+            download_id
+        )
+        .fetch_one(pool)
+        .await
+    }
+
+    pub async fn update_end_byte(
+        download_id: i64,
+        chunk_index: i64,
+        end_byte: i64,
+    ) -> Result<(), sqlx::Error> {
+        let pool = Registry::get_pool();
+
+        sqlx::query!(
+            "UPDATE download_chunks SET end_byte = ? WHERE download_id = ? AND chunk_index = ?",
+            end_byte,
+            download_id,
+            chunk_index
+        )
+        .execute(pool)
+        .await
+        .map(|_| ())
+    }
+
+    /// Hands `range` over from `victim_index` to a new chunk, both rows or neither.
+    pub async fn split(
+        download_id: i64,
+        victim_index: i64,
+        index: i64,
+        range: (i64, i64),
+    ) -> Result<(), sqlx::Error> {
+        let pool = Registry::get_pool();
+        let mut tx = pool.begin().await?;
+
+        let (start, end) = range;
+        let victim_end = start - 1;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO download_chunks (download_id, chunk_index, start_byte, end_byte)
+            VALUES (?, ?, ?, ?)
+            "#,
+            download_id,
+            index,
+            start,
+            end
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "UPDATE download_chunks SET end_byte = ? WHERE download_id = ? AND chunk_index = ?",
+            victim_end,
+            download_id,
+            victim_index
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await
+    }
+
+    pub async fn delete_all(download_id: i64) -> Result<(), sqlx::Error> {
+        let pool = Registry::get_pool();
+
+        sqlx::query!(
+            "DELETE FROM download_chunks WHERE download_id = ?",
+            download_id
+        )
+        .execute(pool)
+        .await
+        .map(|_| ())
+    }
+
     pub async fn update_all(
         download_id: i64,
         chunks: Vec<UpdateChunk>,
@@ -85,44 +167,18 @@ impl ChunkRepository {
     pub async fn update(download_id: i64, chunk: UpdateChunk) -> Result<(), sqlx::Error> {
         let pool = Registry::get_pool();
 
-        let mut fields = Vec::new();
-        let mut binds: Vec<String> = Vec::new();
-
-        match &chunk.expected_hash {
-            Some(hash) => {
-                fields.push("expected_hash = ?".to_string());
-                fields.push("downloaded_bytes = ?".to_string());
-                binds.push(hash.to_string());
-
-                if let Some(bytes) = chunk.downloaded_bytes {
-                    binds.push(bytes.to_string());
-                } else {
-                    binds.push("0".to_string());
-                }
-            }
-            None => {
-                fields.push("expected_hash = NULL".to_string());
-                fields.push("downloaded_bytes = 0".to_string());
-            }
-        }
-
-        if fields.is_empty() {
-            return Ok(());
-        }
-
-        let sql: String = format!(
-            "UPDATE download_chunks SET {} WHERE download_id = ? AND chunk_index = ?",
-            fields.join(", ")
-        );
-
-        let mut query = sqlx::query(&sql);
-
-        for val in binds {
-            query = query.bind(val);
-        }
-
-        query = query.bind(download_id).bind(chunk.chunk_index);
-
-        query.execute(pool).await.map(|_| ())
+        sqlx::query!(
+            r#"
+            UPDATE download_chunks SET downloaded_bytes = ?, block_hashes = ?
+            WHERE download_id = ? AND chunk_index = ?
+            "#,
+            chunk.downloaded_bytes,
+            chunk.block_hashes,
+            download_id,
+            chunk.chunk_index
+        )
+        .execute(pool)
+        .await
+        .map(|_| ())
     }
 }
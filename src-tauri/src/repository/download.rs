@@ -1,5 +1,5 @@
 use crate::{
-    models::{Download, DownloadRaw, NewDownload, UpdateDownload},
+    models::{Checksum, Download, DownloadRaw, NewDownload, UpdateDownload},
     registry::Registry,
 };
 
@@ -33,6 +33,12 @@ impl DownloadRepository {
             d.timeout_secs,
             d.supports_range,
             d.error_message,
+            d.etag,
+            d.last_modified,
+            d.checksum_algorithm,
+            d.checksum,
+            d.referer,
+            d.kind,
             COALESCE(
                 (
                     SELECT SUM(c.downloaded_bytes)
@@ -40,6 +46,13 @@ impl DownloadRepository {
                     WHERE c.download_id = d.id
                 ),
                 0
+            ) + COALESCE(
+                (
+                    SELECT SUM(s.downloaded_bytes)
+                    FROM download_segments s
+                    WHERE s.download_id = d.id
+                ),
+                0
             ) AS downloaded_bytes
         FROM downloads d
         LEFT JOIN download_chunks c ON c.download_id = d.id
@@ -95,6 +108,12 @@ impl DownloadRepository {
     d.timeout_secs,
     d.supports_range,
     d.error_message,
+    d.etag,
+    d.last_modified,
+    d.checksum_algorithm,
+    d.checksum,
+    d.referer,
+    d.kind,
     COALESCE(
 		(
 			SELECT
@@ -105,6 +124,16 @@ impl DownloadRepository {
 				c.download_id = d.id
 		),
 		0
+	) + COALESCE(
+		(
+			SELECT
+				SUM(s.downloaded_bytes)
+			FROM
+				download_segments s
+			WHERE
+				s.download_id = d.id
+		),
+		0
 	) AS downloaded_bytes
 FROM downloads d
 LEFT JOIN download_chunks c ON c.download_id = d.id
@@ -132,9 +161,10 @@ GROUP BY d.id;
             "extension",
             "total_bytes",
             "supports_range",
+            "kind",
         ];
 
-        let mut values = vec!["?", "?", "?", "?", "?", "?", "?", "?", "?"];
+        let mut values = vec!["?", "?", "?", "?", "?", "?", "?", "?", "?", "?"];
         let mut params = vec![
             new.url,
             new.status,
@@ -145,6 +175,7 @@ GROUP BY d.id;
             new.extension,
             new.total_bytes.to_string(),
             new.supports_range.to_string(),
+            new.kind,
         ];
 
         if let Some(auth) = new.auth {
@@ -172,30 +203,50 @@ GROUP BY d.id;
             values.push("?");
             params.push(speed_limit.to_string());
         }
+        if let Some(etag) = new.etag {
+            fields.push("etag");
+            values.push("?");
+            params.push(etag);
+        }
+        if let Some(last_modified) = new.last_modified {
+            fields.push("last_modified");
+            values.push("?");
+            params.push(last_modified);
+        }
+        if let Some(checksum_algorithm) = new.checksum_algorithm {
+            fields.push("checksum_algorithm");
+            values.push("?");
+            params.push(checksum_algorithm);
+        }
+        if let Some(checksum) = new.checksum {
+            fields.push("checksum");
+            values.push("?");
+            params.push(checksum);
+        }
+        if let Some(referer) = new.referer {
+            fields.push("referer");
+            values.push("?");
+            params.push(referer);
+        }
         if let Some(max_retries) = new.max_retries {
-            fields.push("speed_limit");
+            fields.push("max_retries");
             values.push("?");
             params.push(max_retries.to_string());
         }
         if let Some(delay_secs) = new.delay_secs {
-            fields.push("speed_limit");
+            fields.push("delay_secs");
             values.push("?");
             params.push(delay_secs.to_string());
         }
         if let Some(backoff_factor) = new.backoff_factor {
-            fields.push("speed_limit");
+            fields.push("backoff_factor");
             values.push("?");
             params.push(backoff_factor.to_string());
         }
         if let Some(timeout_secs) = new.timeout_secs {
-            fields.push("speed_limit");
-            values.push("?");
-            params.push(timeout_secs.to_string());
-        }
-        if let Some(timeout_secs) = new.timeout_secs {
-            fields.push("speed_limit");
+            fields.push("timeout_secs");
             values.push("?");
-            params.push(timeout_secs.to_string());
+            params.push((timeout_secs.round() as i64).to_string());
         }
 
         let query = format!(
@@ -246,12 +297,12 @@ GROUP BY d.id;
 
         if let Some(headers) = &update.headers {
             fields.push("headers = ?");
-            binds.push(headers.clone().into());
+            binds.push(headers.clone());
         }
 
         if let Some(cookies) = &update.cookies {
             fields.push("cookies = ?");
-            binds.push(cookies.clone().into());
+            binds.push(cookies.clone());
         }
 
         if let Some(max_retries) = update.max_retries {
@@ -297,6 +348,86 @@ GROUP BY d.id;
         Ok(())
     }
 
+    /// Points the download at the current remote file, clearing validators it no longer sends.
+    pub async fn update_remote(
+        id: i64,
+        total_bytes: i64,
+        supports_range: bool,
+        etag: Option<String>,
+        last_modified: Option<String>,
+    ) -> anyhow::Result<()> {
+        let pool = Registry::get_pool();
+
+        sqlx::query!(
+            r#"
+            UPDATE downloads
+            SET total_bytes = ?, supports_range = ?, etag = ?, last_modified = ?, error_message = NULL
+            WHERE id = ?
+            "#,
+            total_bytes,
+            supports_range,
+            etag,
+            last_modified,
+            id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Remembers a digest found after the download was added, e.g. from a `SHA256SUMS` file.
+    pub async fn update_checksum(id: i64, checksum: &Checksum) -> anyhow::Result<()> {
+        let pool = Registry::get_pool();
+        let algorithm = checksum.algorithm.to_string();
+
+        sqlx::query!(
+            "UPDATE downloads SET checksum_algorithm = ?, checksum = ? WHERE id = ?",
+            algorithm,
+            checksum.digest,
+            id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Points the download at a fresh link for the same file, see `refresh_download_url`.
+    pub async fn update_url(id: i64, url: &str) -> anyhow::Result<()> {
+        let pool = Registry::get_pool();
+
+        sqlx::query!("UPDATE downloads SET url = ? WHERE id = ?", url, id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn clear_error_message(id: i64) -> anyhow::Result<()> {
+        let pool = Registry::get_pool();
+
+        sqlx::query!("UPDATE downloads SET error_message = NULL WHERE id = ?", id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn update_supports_range(id: i64, supports_range: bool) -> anyhow::Result<()> {
+        let pool = Registry::get_pool();
+
+        sqlx::query!(
+            "UPDATE downloads SET supports_range = ? WHERE id = ?",
+            supports_range,
+            id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
     pub async fn delete(id: i64) -> anyhow::Result<String> {
         let pool = Registry::get_pool();
         let record = sqlx::query!("DELETE FROM downloads WHERE id = ? RETURNING file_path", id)
@@ -0,0 +1,63 @@
+use crate::{models::DownloadMirror, registry::Registry};
+
+pub struct MirrorRepository;
+
+impl MirrorRepository {
+    pub async fn find_all(download_id: i64) -> Result<Vec<DownloadMirror>, sqlx::Error> {
+        let pool = Registry::get_pool();
+        sqlx::query_as!(
+            DownloadMirror,
+            "SELECT * FROM download_mirrors WHERE download_id = ? ORDER BY priority;",
+            download_id
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn create(
+        download_id: i64,
+        url: &str,
+        priority: i64,
+        location: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let pool = Registry::get_pool();
+
+        sqlx::query!(
+            r#"
+            INSERT OR IGNORE INTO download_mirrors (download_id, url, priority, location)
+            VALUES (?, ?, ?, ?)
+            "#,
+            download_id,
+            url,
+            priority,
+            location
+        )
+        .execute(pool)
+        .await
+        .map(|_| ())
+    }
+
+    pub async fn delete(download_id: i64, url: &str) -> Result<(), sqlx::Error> {
+        let pool = Registry::get_pool();
+
+        sqlx::query!(
+            "DELETE FROM download_mirrors WHERE download_id = ? AND url = ?",
+            download_id,
+            url
+        )
+        .execute(pool)
+        .await
+        .map(|_| ())
+    }
+
+    pub async fn next_priority(download_id: i64) -> Result<i64, sqlx::Error> {
+        let pool = Registry::get_pool();
+
+        sqlx::query_scalar!(
+            r#"SELECT COALESCE(MAX(priority) + 1, 1) AS "next_priority!: i64" FROM download_mirrors WHERE download_id = ?"#,
+            download_id
+        )
+        .fetch_one(pool)
+        .await
+    }
+}
@@ -1,2 +1,6 @@
 pub mod chunk;
 pub mod download;
+pub mod mirror;
+pub mod piece;
+pub mod segment;
+pub mod settings;
@@ -0,0 +1,43 @@
+use crate::{models::DownloadPiece, registry::Registry};
+
+pub struct PieceRepository;
+
+impl PieceRepository {
+    pub async fn find_all(download_id: i64) -> Result<Vec<DownloadPiece>, sqlx::Error> {
+        let pool = Registry::get_pool();
+        sqlx::query_as!(
+            DownloadPiece,
+            "SELECT * FROM download_pieces WHERE download_id = ? ORDER BY piece_index;",
+            download_id
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn create_all(
+        download_id: i64,
+        pieces: Vec<DownloadPiece>,
+    ) -> Result<(), sqlx::Error> {
+        let pool = Registry::get_pool();
+        let mut tx = pool.begin().await?;
+
+        for piece in pieces {
+            sqlx::query!(
+                r#"
+                INSERT INTO download_pieces (download_id, piece_index, start_byte, end_byte, algorithm, hash)
+                VALUES (?, ?, ?, ?, ?, ?)
+                "#,
+                download_id,
+                piece.piece_index,
+                piece.start_byte,
+                piece.end_byte,
+                piece.algorithm,
+                piece.hash
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await
+    }
+}
@@ -0,0 +1,94 @@
+use crate::{models::DownloadSegment, registry::Registry};
+
+pub struct SegmentRepository;
+
+impl SegmentRepository {
+    pub async fn find_all(download_id: i64) -> Result<Vec<DownloadSegment>, sqlx::Error> {
+        let pool = Registry::get_pool();
+        sqlx::query_as!(
+            DownloadSegment,
+            r#"
+            SELECT
+                download_id,
+                segment_index,
+                url,
+                range_start,
+                range_end,
+                key_url,
+                iv,
+                downloaded_bytes,
+                completed AS "completed: bool"
+            FROM download_segments
+            WHERE download_id = ?
+            ORDER BY segment_index;
+            "#,
+            download_id
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn create_all(
+        download_id: i64,
+        segments: Vec<DownloadSegment>,
+    ) -> Result<(), sqlx::Error> {
+        let pool = Registry::get_pool();
+        let mut tx = pool.begin().await?;
+
+        for segment in segments {
+            sqlx::query!(
+                r#"
+                INSERT INTO download_segments (download_id, segment_index, url, range_start, range_end, key_url, iv)
+                VALUES (?, ?, ?, ?, ?, ?, ?)
+                "#,
+                download_id,
+                segment.segment_index,
+                segment.url,
+                segment.range_start,
+                segment.range_end,
+                segment.key_url,
+                segment.iv
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await
+    }
+
+    /// Marks a segment as fully on disk, `downloaded_bytes` is what its file holds.
+    pub async fn complete(
+        download_id: i64,
+        segment_index: i64,
+        downloaded_bytes: i64,
+    ) -> Result<(), sqlx::Error> {
+        let pool = Registry::get_pool();
+
+        sqlx::query!(
+            r#"
+            UPDATE download_segments
+            SET downloaded_bytes = ?, completed = 1
+            WHERE download_id = ? AND segment_index = ?
+            "#,
+            downloaded_bytes,
+            download_id,
+            segment_index
+        )
+        .execute(pool)
+        .await
+        .map(|_| ())
+    }
+
+    /// Forgets every finished segment, for starting a stream over.
+    pub async fn reset_all(download_id: i64) -> Result<(), sqlx::Error> {
+        let pool = Registry::get_pool();
+
+        sqlx::query!(
+            "UPDATE download_segments SET downloaded_bytes = 0, completed = 0 WHERE download_id = ?",
+            download_id
+        )
+        .execute(pool)
+        .await
+        .map(|_| ())
+    }
+}
@@ -0,0 +1,37 @@
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::registry::Registry;
+
+pub struct SettingsRepository;
+
+impl SettingsRepository {
+    pub async fn find<T: DeserializeOwned>(key: &str) -> anyhow::Result<Option<T>> {
+        let pool = Registry::get_pool();
+        let record = sqlx::query!("SELECT value FROM settings WHERE key = ?", key)
+            .fetch_optional(pool)
+            .await?;
+
+        match record {
+            Some(record) => Ok(Some(serde_json::from_str(&record.value)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn save<T: Serialize>(key: &str, value: &T) -> anyhow::Result<()> {
+        let pool = Registry::get_pool();
+        let value = serde_json::to_string(value)?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO settings (key, value) VALUES (?, ?)
+            ON CONFLICT(key) DO UPDATE SET value = excluded.value
+            "#,
+            key,
+            value
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -1,49 +1,270 @@
 use crate::registry::Registry;
 
 use std::{
-    sync::{atomic::Ordering, Arc},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
     time::{Duration, Instant},
 };
-use tokio::time::sleep;
+use tokio::{
+    sync::{OwnedSemaphorePermit, Semaphore},
+    time::sleep,
+};
 
-impl super::DownloadWorker {
-    pub async fn limiter(&self, bytes_len: u64) {
-        let bandwidth_limit =
-            Arc::clone(&Registry::get_state().bandwidth_limit).load(Ordering::Relaxed);
+/// How many seconds of traffic a bucket may hold, keeps bursts well under a second.
+const BURST_SECS: f64 = 0.1;
+/// Lower bound of a bucket capacity so a single network frame always fits.
+const MIN_BURST_BYTES: f64 = 16.0 * 1024.0;
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// Token bucket measured in bytes, a rate of zero means unlimited.
+#[derive(Debug)]
+pub struct TokenBucket {
+    rate: AtomicU64,
+    state: Mutex<BucketState>,
+}
 
-        if bandwidth_limit == 0.0 {
+impl TokenBucket {
+    pub fn new(rate: u64) -> Self {
+        Self {
+            rate: AtomicU64::new(rate),
+            state: Mutex::new(BucketState {
+                tokens: Self::capacity(rate),
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    pub fn rate(&self) -> u64 {
+        self.rate.load(Ordering::Relaxed)
+    }
+
+    pub fn set_rate(&self, rate: u64) {
+        if self.rate.swap(rate, Ordering::Relaxed) == rate {
             return;
         }
 
-        let mut received_bytes = self.received_bytes.lock().await;
-        let mut duration = self.stream_duration.lock().await;
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.tokens = state.tokens.min(Self::capacity(rate));
+        state.last_refill = Instant::now();
+    }
+
+    /// Takes `bytes` out of the bucket and returns how long the caller has to wait
+    /// before the tokens it just borrowed are paid back.
+    pub fn reserve(&self, bytes: u64) -> Duration {
+        let rate = self.rate();
+
+        if rate == 0 {
+            return Duration::ZERO;
+        }
+
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        Self::refill(&mut state, rate);
+        state.tokens -= bytes as f64;
+
+        if state.tokens >= 0.0 {
+            return Duration::ZERO;
+        }
+
+        Duration::from_secs_f64(-state.tokens / rate as f64)
+    }
+
+    /// Current fill level between 0 and 1, an unlimited bucket is always full.
+    pub fn fill_level(&self) -> f64 {
+        let rate = self.rate();
+
+        if rate == 0 {
+            return 1.0;
+        }
+
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        Self::refill(&mut state, rate);
+
+        (state.tokens / Self::capacity(rate)).clamp(0.0, 1.0)
+    }
+
+    fn refill(state: &mut BucketState, rate: u64) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(state.last_refill).as_secs_f64();
+
+        state.tokens = (state.tokens + elapsed * rate as f64).min(Self::capacity(rate));
+        state.last_refill = now;
+    }
+
+    fn capacity(rate: u64) -> f64 {
+        (rate as f64 * BURST_SECS).max(MIN_BURST_BYTES)
+    }
+}
+
+/// Keeps the stream counters accurate even when a chunk stream is dropped by `select!`.
+pub struct StreamGuard(Vec<Arc<AtomicUsize>>);
+
+impl StreamGuard {
+    pub fn new(counters: &[&Arc<AtomicUsize>]) -> Self {
+        let counters = counters
+            .iter()
+            .map(|counter| {
+                counter.fetch_add(1, Ordering::Relaxed);
+                Arc::clone(counter)
+            })
+            .collect();
+
+        Self(counters)
+    }
+}
+
+impl Drop for StreamGuard {
+    fn drop(&mut self) {
+        for counter in &self.0 {
+            counter.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Streams allowed against one host, shared by every download pointing at it.
+#[derive(Debug)]
+pub struct HostSlots {
+    semaphore: Arc<Semaphore>,
+    limit: Mutex<usize>,
+    /// Slots still to take away after a lower limit, paid by streams as they end.
+    debt: AtomicUsize,
+}
+
+/// A stream's slot on its host, handed back when the stream ends.
+pub struct HostSlot {
+    permit: Option<OwnedSemaphorePermit>,
+    slots: Arc<HostSlots>,
+}
+
+impl HostSlots {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(limit)),
+            limit: Mutex::new(limit),
+            debt: AtomicUsize::new(0),
+        }
+    }
+
+    /// Waits until a stream may start against this host.
+    pub async fn acquire(self: &Arc<Self>) -> HostSlot {
+        let permit = Arc::clone(&self.semaphore)
+            .acquire_owned()
+            .await
+            .expect("host slots are never closed");
+
+        HostSlot {
+            permit: Some(permit),
+            slots: Arc::clone(self),
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.semaphore.available_permits() == 0
+    }
+
+    /// Running streams keep their slots, a lower limit takes effect as they end.
+    pub fn resize(&self, limit: usize) {
+        let mut current = self.limit.lock().unwrap();
+
+        if limit > *current {
+            let grow = limit - *current;
+            let paid = self
+                .debt
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |debt| {
+                    Some(debt.saturating_sub(grow))
+                })
+                .unwrap_or_default()
+                .min(grow);
+
+            self.semaphore.add_permits(grow - paid);
+        } else {
+            let shrink = *current - limit;
+            let forgotten = self.semaphore.forget_permits(shrink);
 
-        if duration.is_none() {
-            *duration = Some(Instant::now());
+            self.debt.fetch_add(shrink - forgotten, Ordering::Relaxed);
         }
 
-        if bandwidth_limit <= *received_bytes as f64 {
-            if let Some(instant) = *duration {
-                let time_overflow = 1.0 - instant.elapsed().as_secs_f32();
-                let bytes_overflow = 1.0 - ((bandwidth_limit as f32) / (*received_bytes as f32));
-                if time_overflow < 1.0 && time_overflow > 0.0 {
-                    let added_time = time_overflow * (bytes_overflow / 100.0);
-                    let final_delay = time_overflow + added_time;
-                    sleep(Duration::from_secs_f32(final_delay)).await;
-                }
-            };
+        *current = limit;
+    }
+}
+
+impl Drop for HostSlot {
+    fn drop(&mut self) {
+        let owed = self
+            .slots
+            .debt
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |debt| {
+                debt.checked_sub(1)
+            })
+            .is_ok();
 
-            *duration = Some(Instant::now());
-            *received_bytes = 0;
+        if let Some(permit) = self.permit.take().filter(|_| owed) {
+            permit.forget();
         }
+    }
+}
 
-        *received_bytes += bytes_len;
+impl super::DownloadWorker {
+    /// Walks the bucket hierarchy (global -> download -> chunk) and waits for the slowest one.
+    pub async fn limiter(&self, chunk_bucket: &TokenBucket, bytes_len: u64) {
+        let global_bucket = Arc::clone(&Registry::get_state().bandwidth_bucket);
+        let download_bucket = &self.report.bandwidth_bucket;
+
+        let streams = self.active_streams.load(Ordering::Relaxed).max(1) as u64;
+        chunk_bucket.set_rate(download_bucket.rate().div_ceil(streams));
+
+        let delay = global_bucket
+            .reserve(bytes_len)
+            .max(download_bucket.reserve(bytes_len))
+            .max(chunk_bucket.reserve(bytes_len));
 
-        if let Some(instant) = *duration {
-            if instant.elapsed().as_secs_f32() > 1.0 {
-                *received_bytes = 0;
-                *duration = Some(Instant::now());
-            }
+        if !delay.is_zero() {
+            sleep(delay).await;
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::HostSlots;
+
+    #[tokio::test]
+    async fn a_lower_limit_waits_for_running_streams() {
+        let slots = Arc::new(HostSlots::new(2));
+        let first = slots.acquire().await;
+        let second = slots.acquire().await;
+
+        slots.resize(1);
+        drop(first);
+        assert!(slots.is_full());
+
+        drop(second);
+        assert!(!slots.is_full());
+        let _third = slots.acquire().await;
+        assert!(slots.is_full());
+    }
+
+    #[tokio::test]
+    async fn a_higher_limit_first_cancels_what_is_owed() {
+        let slots = Arc::new(HostSlots::new(2));
+        let first = slots.acquire().await;
+        let _second = slots.acquire().await;
+
+        slots.resize(1);
+        slots.resize(3);
+        assert!(!slots.is_full());
+
+        drop(first);
+        let _third = slots.acquire().await;
+        let _fourth = slots.acquire().await;
+        assert!(slots.is_full());
+    }
+}
@@ -0,0 +1,53 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    client::{Client, ClientError},
+    models::Download,
+};
+
+use super::*;
+
+impl DownloadWorker {
+    /// Hands out the shared client for `url` so retries and new segments reuse warm
+    /// connections, it is only rebuilt when the connection settings changed.
+    /// A mirror on another origin doesn't get the download's credentials.
+    pub(super) async fn client(&self, url: &str) -> Result<Arc<Client>, ClientError> {
+        let w = self.data.read().await;
+        let key = Self::client_key(&w.download);
+
+        let mut clients = self.clients.lock().await;
+
+        if let Some((cached_key, client)) = clients.get(url) {
+            if *cached_key == key {
+                return Ok(Arc::clone(client));
+            }
+        }
+
+        let client = Arc::new(Client::for_source(
+            url,
+            &w.download.url,
+            &w.download.auth,
+            &w.download.proxy,
+            &w.download.headers,
+            &w.download.cookies,
+        )?);
+
+        clients.insert(url.to_string(), (key, Arc::clone(&client)));
+
+        Ok(client)
+    }
+
+    fn client_key(download: &Download) -> String {
+        fn sorted(map: &Option<HashMap<String, String>>) -> Option<BTreeMap<&String, &String>> {
+            map.as_ref().map(|m| m.iter().collect())
+        }
+
+        serde_json::to_string(&(
+            &download.auth,
+            &download.proxy,
+            sorted(&download.headers),
+            sorted(&download.cookies),
+        ))
+        .unwrap_or_default()
+    }
+}
@@ -0,0 +1,147 @@
+use std::{sync::atomic::Ordering, time::Duration};
+
+use tokio::{select, time::interval};
+
+use crate::{registry::Registry, spawn, worker::status::ChunkDownloadStatus};
+
+use super::*;
+
+/// Long enough for the ten second `speed_bps` average to settle after a change.
+const PROBE_INTERVAL: Duration = Duration::from_secs(10);
+/// An added stream has to bring at least this much extra throughput to be kept.
+const MIN_SPEED_GAIN: f64 = 0.1;
+/// Probes to sit out after a stream didn't pay off before growing again.
+const PLATEAU_PROBES: u32 = 6;
+
+impl DownloadWorker {
+    /// Grows the number of streams while throughput keeps improving and parks
+    /// the last added one as soon as it stops paying off.
+    pub(super) fn spawn_connection_tuner(self: &Arc<Self>) {
+        let worker_clone = Arc::clone(self);
+
+        spawn!("connection_tuner", {
+            let cancel_token = Arc::clone(&worker_clone.data.read().await.cancel_token);
+            let mut interval = interval(PROBE_INTERVAL);
+            let mut speed_before_growth: Option<u64> = None;
+            let mut plateau_probes = 0;
+
+            interval.tick().await;
+
+            loop {
+                select! {
+                    _ = interval.tick() => {},
+                    _ = cancel_token.cancelled() => break,
+                }
+
+                if worker_clone.is_finished() {
+                    break;
+                }
+
+                if !worker_clone.report.stable_speed.load(Ordering::Relaxed) {
+                    continue;
+                }
+
+                let speed = worker_clone.report.speed_bps.load(Ordering::Relaxed);
+
+                if let Some(before) = speed_before_growth.take() {
+                    if (speed as f64) < before as f64 * (1.0 + MIN_SPEED_GAIN) {
+                        worker_clone.park_chunk();
+                        plateau_probes = PLATEAU_PROBES;
+                    }
+                    continue;
+                }
+
+                if plateau_probes > 0 {
+                    plateau_probes -= 1;
+                    continue;
+                }
+
+                if worker_clone.is_throttled(speed) {
+                    continue;
+                }
+
+                if worker_clone.add_stream().await {
+                    speed_before_growth = Some(speed);
+                }
+            }
+        });
+    }
+
+    pub(super) async fn resume_parked_chunk(self: &Arc<Self>) -> bool {
+        let Some(chunk) = self.parked_chunks.lock().await.pop_front() else {
+            return false;
+        };
+
+        self.update_chunk_status(chunk.chunk_index, ChunkDownloadStatus::Downloading)
+            .await;
+        self.spawn_chunk(chunk);
+
+        true
+    }
+
+    async fn add_stream(self: &Arc<Self>) -> bool {
+        // The new stream would only wait for a slot when every host serving the file is full.
+        let mirrors = self.data.read().await.mirrors.clone();
+        let mut hosts_full = true;
+
+        for url in &mirrors {
+            hosts_full &= Self::host_slots(url).await.is_full();
+        }
+
+        if hosts_full {
+            return false;
+        }
+
+        if self.resume_parked_chunk().await {
+            return true;
+        }
+
+        self.steal_segment().await
+    }
+
+    /// Parks the running chunk closest to its end, its leftover bytes are picked up
+    /// by the next stream that finishes.
+    fn park_chunk(&self) {
+        if self.active_streams.load(Ordering::Relaxed) <= 1 {
+            return;
+        }
+
+        let chunk_index = self
+            .chunks_status
+            .iter()
+            .filter(|status| matches!(status.value(), ChunkDownloadStatus::Downloading))
+            .filter_map(|status| {
+                let segment = self.segments.get(status.key())?;
+                Some((*status.key(), segment.remaining()))
+            })
+            .min_by_key(|(_, remaining)| *remaining)
+            .map(|(chunk_index, _)| chunk_index);
+
+        if let Some(token) = chunk_index.and_then(|index| self.park_tokens.get(&index)) {
+            token.cancel();
+        }
+    }
+
+    /// Adding streams can't help once a speed limit is what holds the download back.
+    fn is_throttled(&self, speed: u64) -> bool {
+        let global_rate = Registry::get_state().bandwidth_bucket.rate();
+        let download_rate = self.report.bandwidth_bucket.rate();
+
+        let rate = match (global_rate > 0, download_rate > 0) {
+            (true, true) => global_rate.min(download_rate),
+            (true, false) => global_rate,
+            (false, true) => download_rate,
+            (false, false) => return false,
+        };
+
+        speed as f64 >= rate as f64 * 0.9
+    }
+
+    fn is_finished(&self) -> bool {
+        !self.chunks_status.is_empty()
+            && self
+                .chunks_status
+                .iter()
+                .all(|status| matches!(status.value(), ChunkDownloadStatus::Finished))
+    }
+}
@@ -1,5 +1,6 @@
 use futures_util::StreamExt;
 use std::{
+    future::pending,
     sync::{atomic::Ordering, Arc},
     time::Duration,
 };
@@ -9,24 +10,36 @@ use tokio::{
 };
 
 use crate::{
-    client::{Client, ClientError},
+    client::ClientError,
     dispatch,
-    file::WriteMessage,
+    emitter::Emitter,
+    models::{RetryPolicy, RetryState},
     spawn,
-    worker::status::ChunkDownloadStatus,
+    worker::{
+        bandwidth::{StreamGuard, TokenBucket},
+        status::ChunkDownloadStatus,
+    },
 };
 
 use super::*;
 
 impl DownloadWorker {
     pub async fn start_download(self: &Arc<Self>) {
-        use ChunkDownloadStatus::*;
+        if self.data.read().await.download.is_stream() {
+            return self.start_stream().await;
+        }
+
+        let chunks = self.data.read().await.chunks.clone();
+
+        for chunk in chunks {
+            self.spawn_chunk(chunk);
+        }
 
-        let worker = self.data.read().await;
+        self.spawn_connection_tuner();
+    }
 
-        let chunks = worker.chunks.clone();
-        let cancel_token = Arc::clone(&worker.cancel_token);
-        let max_retries = worker.download.max_retries;
+    pub(super) fn spawn_chunk(self: &Arc<Self>, chunk: DownloadChunk) {
+        use ChunkDownloadStatus::*;
 
         let cancelable_sleep = async move |d: Duration, c: Arc<CancellationToken>| {
             select! {
@@ -35,160 +48,238 @@ impl DownloadWorker {
             }
         };
 
-        for mut chunk in chunks {
-            let worker_clone = Arc::clone(self);
-            let cancel_token = Arc::clone(&cancel_token);
+        let worker_clone = Arc::clone(self);
+
+        spawn!("download_chunk", {
+            let retry_settings = Registry::get_state().retry_settings.read().await.clone();
+            let (cancel_token, policy) = {
+                let worker = worker_clone.data.read().await;
+                (
+                    Arc::clone(&worker.cancel_token),
+                    RetryPolicy::new(&worker.download, &retry_settings),
+                )
+            };
+            let park_token = CancellationToken::new();
+            worker_clone
+                .park_tokens
+                .insert(chunk.chunk_index, park_token.clone());
 
-            spawn!("download_chunk", {
-                let max_retries = max_retries;
-                let mut retries = 0;
+            let mut retry = RetryState::default();
 
-                let set = async |st| {
-                    worker_clone
-                        .update_chunk_status(chunk.chunk_index, st)
-                        .await
+            let set = async |st| {
+                worker_clone
+                    .update_chunk_status(chunk.chunk_index, st)
+                    .await
+            };
+
+            loop {
+                set(Downloading).await;
+
+                let cursor = worker_clone.segment_cursor(chunk.chunk_index);
+
+                let st = select! {
+                    status = worker_clone.download_chunk(&chunk) => {
+                        match status {
+                            Ok(()) => Finished,
+                            Err(err) if err.is_retryable() => Trying(err),
+                            Err(err) => Errored(err)
+                        }
+                    },
+                    _ = cancel_token.cancelled() => Paused,
+                    _ = park_token.cancelled() => Parked,
                 };
 
-                loop {
-                    set(Downloading).await;
+                match st {
+                    Paused => {
+                        set(st).await;
+                        break;
+                    }
+                    Parked => {
+                        worker_clone.release_mirror(chunk.chunk_index);
+                        set(st).await;
+                        worker_clone.parked_chunks.lock().await.push_back(chunk);
+                        break;
+                    }
+                    Finished => {
+                        worker_clone.mirror_recovered(chunk.chunk_index);
+                        worker_clone.release_mirror(chunk.chunk_index);
+
+                        // Pick up a parked chunk, or half of the largest remaining segment,
+                        // before reporting this one as finished so the download never looks
+                        // completed in between.
+                        if !cancel_token.is_cancelled() && !worker_clone.resume_parked_chunk().await
+                        {
+                            worker_clone.steal_segment().await;
+                        }
 
-                    let st = select! {
-                        status = worker_clone.download_chunk(&chunk) => {
-                            match status {
-                                Ok(()) => Finished,
-                                Err(err) if err.is_retryable() => Trying(err),
-                                Err(err) => Errored(err)
-                            }
-                        },
-                        _ = cancel_token.cancelled() => Paused,
-                    };
+                        set(st).await;
+                        break;
+                    }
+                    Errored(err) => {
+                        // Another mirror may still serve the bytes, a changed file is
+                        // decided by the primary URL alone.
+                        if !matches!(err, ClientError::RemoteChanged)
+                            && worker_clone.fail_over(chunk.chunk_index).await
+                        {
+                            retry.reset();
+                            continue;
+                        }
 
-                    match st {
-                        Paused | Finished => {
-                            set(st).await;
+                        if matches!(err, ClientError::RangeNotSupported) {
+                            // The whole download restarts as a single stream from byte zero.
+                            if let Err(err) = dispatch!(manager, DisableRange, (chunk.download_id))
+                            {
+                                Emitter::emit_error(err.to_string());
+                            }
+                            set(Paused).await;
                             break;
                         }
-                        Errored(err) => {
-                            set(Errored(err)).await;
-                            cancel_token.cancel();
+
+                        set(Errored(err)).await;
+                        cancel_token.cancel();
+                        break;
+                    }
+                    Trying(err) => {
+                        if cancel_token.is_cancelled() {
+                            set(Paused).await;
                             break;
                         }
-                        Trying(err) => {
-                            if cancel_token.is_cancelled() {
-                                set(Paused).await;
-                                break;
-                            }
 
-                            if retries < max_retries {
-                                set(Trying(err.clone())).await;
+                        // Only consecutive failures count against the policy, a stream
+                        // that got further than last time starts a fresh streak.
+                        if worker_clone.segment_cursor(chunk.chunk_index) > cursor {
+                            retry.reset();
+                            worker_clone.mirror_recovered(chunk.chunk_index);
+                        }
 
-                                match err {
-                                    ClientError::UnexpectedChunkHash => {
-                                        chunk.expected_hash = None;
+                        // A mirror that keeps failing is left for the others right away.
+                        if worker_clone.mirror_failed(chunk.chunk_index).await
+                            && worker_clone.fail_over(chunk.chunk_index).await
+                        {
+                            retry.reset();
+                            continue;
+                        }
 
-                                        dispatch!(
-                                            manager,
-                                            ResetChunk,
-                                            (chunk.download_id, chunk.chunk_index)
-                                        );
-                                    }
-                                    _ => {}
-                                };
+                        // A server that told us how long to back off gets exactly that.
+                        let server_delay = err.retry_after();
 
-                                retries += 1;
+                        if let Some(attempt) = retry.next_attempt(&policy, server_delay) {
+                            let delay = match server_delay {
+                                Some(delay) => {
+                                    set(Waiting(err.clone(), delay)).await;
+                                    delay
+                                }
+                                None => {
+                                    set(Trying(err.clone())).await;
+                                    policy.delay(attempt)
+                                }
+                            };
 
-                                let delay = worker_clone.backoff_delay(retries).await;
-                                let cancel_clone = Arc::clone(&cancel_token);
+                            let cancel_clone = Arc::clone(&cancel_token);
 
-                                if !cancelable_sleep(delay, cancel_clone).await {
-                                    set(Paused).await;
-                                    break;
-                                }
-                            } else {
-                                set(Errored(err)).await;
-                                cancel_token.cancel();
+                            if !cancelable_sleep(delay, cancel_clone).await {
+                                set(Paused).await;
                                 break;
                             }
+                        } else if worker_clone.fail_over(chunk.chunk_index).await {
+                            retry.reset();
+                        } else {
+                            set(Errored(err)).await;
+                            cancel_token.cancel();
+                            break;
                         }
-
-                        _ => {}
                     }
+
+                    _ => {}
                 }
-            });
-        }
+            }
+        });
     }
 
     async fn download_chunk(self: &Arc<Self>, chunk: &DownloadChunk) -> Result<(), ClientError> {
-        if let Err(_) = self
-            .validate_chunk(chunk.expected_hash.clone(), chunk.chunk_index)
-            .await
-        {
-            return Err(ClientError::UnexpectedChunkHash);
-        }
+        self.validate_chunk(chunk).await;
 
         let report = Arc::clone(&self.report);
 
-        let mut downloaded_bytes = match report.chunks_wrote_bytes.get(&chunk.chunk_index) {
+        let downloaded_bytes = match report.chunks_wrote_bytes.get(&chunk.chunk_index) {
             Some(bytes) => bytes.load(Ordering::SeqCst) as i64,
             None => 0,
         };
 
-        let start_byte = chunk.start_byte;
-        let end_byte = chunk.end_byte;
+        let segment = self.register_segment(chunk, downloaded_bytes);
+        let (cursor, end_byte) = segment.bounds();
+
+        if cursor > end_byte {
+            return Ok(());
+        }
+
+        let url = self.chunk_url(chunk.chunk_index).await;
 
-        let (client, range, timeout_secs, file) = {
+        let (range, if_range, timeout_secs, file) = {
             let w = self.data.read().await;
 
             let range = match &w.download.supports_range {
-                true => Some((start_byte + downloaded_bytes, end_byte)),
+                true => Some((cursor, end_byte)),
                 false => None,
             };
 
-            let client = match Client::new(
-                &w.download.url,
-                &w.download.auth,
-                &w.download.proxy,
-                &w.download.headers,
-                &w.download.cookies,
-            ) {
-                Ok(c) => c,
-                Err(err) => {
-                    return Err(err);
-                }
-            };
-
             let file = Arc::clone(&w.file);
 
+            // The validators belong to the primary URL, mirrors report their own.
+            let if_range = w.download.if_range().filter(|_| url == w.download.url);
+
             let timeout_secs = w.download.timeout_secs;
 
-            (client, range, timeout_secs, file)
+            (range, if_range, timeout_secs, file)
         };
 
-        let mut stream = client.stream(range).await?;
+        let client = self.client(&url).await?;
+
+        // Held until the stream ends, the chunk waits here while its host is busy.
+        let _host_slot = Self::host_slots(&url).await.acquire().await;
+        let mut stream = client.stream(range, if_range.as_deref()).await?;
+
+        let _stream_guard = StreamGuard::new(&[&self.active_streams]);
+        let chunk_bucket = TokenBucket::new(0);
 
         loop {
             match timeout(Duration::from_secs(timeout_secs as u64), stream.next()).await {
-                Ok(Some(Ok(bytes))) => {
-                    let bytes_len = bytes.len() as u64;
+                Ok(Some(Ok(mut bytes))) => {
+                    // The segment end can shrink under us when another stream steals half of it.
+                    let Some(offset) = segment.claim(&mut bytes).await else {
+                        return Ok(());
+                    };
 
-                    let write_message: WriteMessage = (
-                        chunk.chunk_index,
-                        (start_byte + downloaded_bytes) as u64,
-                        bytes_len,
-                        bytes.to_vec(),
-                    );
+                    let bytes_len = bytes.len() as u64;
 
-                    file.send(write_message).unwrap();
+                    // Waits here while the writer is behind by more than the write queue.
+                    if file
+                        .write(chunk.chunk_index, offset as u64, bytes)
+                        .await
+                        .is_err()
+                    {
+                        // The writer failed and is pausing the download, let the cancel win.
+                        return pending().await;
+                    }
 
-                    self.limiter(bytes_len).await;
+                    self.limiter(&chunk_bucket, bytes_len).await;
 
-                    downloaded_bytes += bytes_len as i64;
+                    if let Err(err) =
+                        dispatch!(registry, UpdateNetworkReport, (self.download_id, bytes_len))
+                    {
+                        Emitter::emit_error(err.to_string());
+                    }
 
-                    dispatch!(registry, UpdateNetworkReport, (self.download_id, bytes_len));
+                    if segment.is_finished().await {
+                        return Ok(());
+                    }
                 }
                 Ok(Some(Err(err))) => {
                     return Err(err);
                 }
+                Ok(None) if range.is_some() && !segment.is_finished().await => {
+                    return Err(ClientError::UnexpectedEof);
+                }
                 Ok(None) => {
                     return Ok(());
                 }
@@ -0,0 +1,111 @@
+use std::collections::HashSet;
+
+use super::*;
+
+/// Consecutive retryable failures after which a mirror gets no new chunks.
+const MIRROR_DEMOTE_FAILURES: u32 = 3;
+
+/// Which mirror a chunk streams from and which ones it already gave up on.
+#[derive(Debug, Default)]
+pub(super) struct ChunkMirror {
+    current: usize,
+    tried: HashSet<usize>,
+}
+
+impl DownloadWorker {
+    /// URL the chunk downloads from. A chunk without a mirror yet gets the least busy
+    /// healthy one, so parallel chunks spread over every source.
+    pub(super) async fn chunk_url(&self, chunk_index: i64) -> String {
+        let w = self.data.read().await;
+
+        let current = match self.chunk_mirrors.get(&chunk_index).map(|m| m.current) {
+            Some(current) => current,
+            None => {
+                let current = self.least_busy_mirror(w.mirrors.len(), &HashSet::new());
+                self.chunk_mirrors.insert(
+                    chunk_index,
+                    ChunkMirror {
+                        current: current.unwrap_or(0),
+                        tried: HashSet::new(),
+                    },
+                );
+                current.unwrap_or(0)
+            }
+        };
+
+        w.mirrors
+            .get(current)
+            .cloned()
+            .unwrap_or_else(|| w.download.url.clone())
+    }
+
+    /// Moves the chunk to another mirror it hasn't tried, `false` once none is left.
+    pub(super) async fn fail_over(&self, chunk_index: i64) -> bool {
+        let mirrors = self.data.read().await.mirrors.len();
+        let mut mirror = self.chunk_mirrors.entry(chunk_index).or_default();
+
+        let current = mirror.current;
+        mirror.tried.insert(current);
+        let tried = mirror.tried.clone();
+        drop(mirror);
+
+        let Some(next) = self.least_busy_mirror(mirrors, &tried) else {
+            return false;
+        };
+
+        if let Some(mut mirror) = self.chunk_mirrors.get_mut(&chunk_index) {
+            mirror.current = next;
+        }
+
+        true
+    }
+
+    /// Counts a retryable failure against the chunk's mirror, `true` when that
+    /// demoted it and the chunk should move on rather than keep retrying there.
+    pub(super) async fn mirror_failed(&self, chunk_index: i64) -> bool {
+        if self.data.read().await.mirrors.len() < 2 {
+            return false;
+        }
+
+        let Some(current) = self.chunk_mirrors.get(&chunk_index).map(|m| m.current) else {
+            return false;
+        };
+
+        let mut failures = self.mirror_failures.entry(current).or_insert(0);
+        *failures += 1;
+
+        *failures >= MIRROR_DEMOTE_FAILURES
+    }
+
+    /// The chunk's mirror delivered bytes again, its failure streak is over.
+    pub(super) fn mirror_recovered(&self, chunk_index: i64) {
+        if let Some(mirror) = self.chunk_mirrors.get(&chunk_index) {
+            self.mirror_failures.remove(&mirror.current);
+        }
+    }
+
+    pub(super) fn release_mirror(&self, chunk_index: i64) {
+        self.chunk_mirrors.remove(&chunk_index);
+    }
+
+    /// Mirror serving the fewest chunks outside `excluded`, demoted ones only when
+    /// nothing else is left. Ties go to the preferred, lower index.
+    fn least_busy_mirror(&self, mirrors: usize, excluded: &HashSet<usize>) -> Option<usize> {
+        let demoted = |index: &usize| {
+            self.mirror_failures
+                .get(index)
+                .is_some_and(|failures| *failures >= MIRROR_DEMOTE_FAILURES)
+        };
+
+        let busy = |index: &usize| {
+            self.chunk_mirrors
+                .iter()
+                .filter(|mirror| mirror.current == *index)
+                .count()
+        };
+
+        (0..mirrors)
+            .filter(|index| !excluded.contains(index))
+            .min_by_key(|index| (demoted(index), busy(index)))
+    }
+}
@@ -1,29 +1,44 @@
 use anyhow::{anyhow, Context};
 use dashmap::DashMap;
-use std::{sync::Arc, time::Instant};
-use tokio::sync::{mpsc::UnboundedSender, Mutex, RwLock};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{atomic::AtomicUsize, Arc},
+};
+use tauri::Url;
+use tokio::sync::{Mutex, RwLock};
 use tokio_util::sync::CancellationToken;
 
 use crate::{
-    file::WriteMessage,
+    client::Client,
+    file::FileSender,
     models::{Download, DownloadChunk},
     registry::{Registry, Report},
 };
 
-mod backoff;
 mod bandwidth;
+mod client;
+mod connections;
 mod download;
+mod mirror;
+mod segment;
 mod status;
+mod stream;
 mod validation;
 
+pub use bandwidth::{HostSlots, TokenBucket};
 pub use status::DownloadStatus;
 
+/// Shared clients per URL, with the connection settings they were built from.
+type ClientCache = HashMap<String, (String, Arc<Client>)>;
+
 #[derive(Clone, Debug)]
 pub struct Worker {
     pub download: Download,
     pub chunks: Vec<DownloadChunk>,
     pub cancel_token: Arc<CancellationToken>,
-    pub file: Arc<UnboundedSender<WriteMessage>>,
+    pub file: Arc<FileSender>,
+    /// Every URL serving the file, the download's `url` first.
+    pub mirrors: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -32,14 +47,23 @@ pub struct DownloadWorker {
     data: Arc<RwLock<Worker>>,
     report: Arc<Report>,
     chunks_status: Arc<DashMap<i64, status::ChunkDownloadStatus>>,
-    stream_duration: Arc<Mutex<Option<Instant>>>,
-    received_bytes: Arc<Mutex<u64>>,
+    clients: Arc<Mutex<ClientCache>>,
+    chunk_mirrors: Arc<DashMap<i64, mirror::ChunkMirror>>,
+    mirror_failures: Arc<DashMap<usize, u32>>,
+    active_streams: Arc<AtomicUsize>,
+    park_tokens: Arc<DashMap<i64, CancellationToken>>,
+    parked_chunks: Arc<Mutex<VecDeque<DownloadChunk>>>,
+    segments: Arc<DashMap<i64, Arc<segment::Segment>>>,
+    split_lock: Arc<Mutex<()>>,
     last_worker_status: Arc<Mutex<DownloadStatus>>,
 }
 
 impl DownloadWorker {
     pub async fn new(download_id: i64) -> anyhow::Result<Arc<Self>> {
         let chunks_status = Arc::new(DashMap::new());
+        let clients = Arc::new(Mutex::new(HashMap::new()));
+        let chunk_mirrors = Arc::new(DashMap::new());
+        let mirror_failures = Arc::new(DashMap::new());
         let state = Registry::get_state();
         let worker = state
             .workers
@@ -49,8 +73,11 @@ impl DownloadWorker {
             .reports
             .get(&download_id)
             .context(anyhow!("cannot find report with id {}", download_id))?;
-        let stream_duration = Arc::new(Mutex::new(None));
-        let received_bytes = Arc::new(Mutex::new(0));
+        let active_streams = Arc::new(AtomicUsize::new(0));
+        let park_tokens = Arc::new(DashMap::new());
+        let parked_chunks = Arc::new(Mutex::new(VecDeque::new()));
+        let segments = Arc::new(DashMap::new());
+        let split_lock = Arc::new(Mutex::new(()));
         let last_worker_status = Arc::new(Mutex::new(DownloadStatus::Unknown));
 
         let w = Arc::new(Self {
@@ -58,11 +85,34 @@ impl DownloadWorker {
             data: Arc::clone(&worker),
             report: Arc::clone(&report),
             chunks_status,
-            stream_duration,
-            received_bytes,
+            clients,
+            chunk_mirrors,
+            mirror_failures,
+            active_streams,
+            park_tokens,
+            parked_chunks,
+            segments,
+            split_lock,
             last_worker_status,
         });
 
         Ok(w)
     }
+
+    /// Stream slots of the host `url` points at, shared by every download using it.
+    async fn host_slots(url: &str) -> Arc<HostSlots> {
+        let host = Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(|host| host.to_string()))
+            .unwrap_or_default();
+
+        let state = Registry::get_state();
+        let limit = state.bandwidth_settings.read().await.max_streams_per_host;
+        let slots = state
+            .host_slots
+            .entry(host)
+            .or_insert_with(|| Arc::new(HostSlots::new(limit)));
+
+        Arc::clone(&slots)
+    }
 }
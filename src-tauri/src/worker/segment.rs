@@ -0,0 +1,271 @@
+use std::sync::{atomic::AtomicU64, Mutex as StdMutex};
+
+use tokio::sync::Mutex as AsyncMutex;
+use tokio_util::bytes::Bytes;
+
+use crate::{
+    emitter::Emitter, file::ChunkIntegrity, repository::chunk::ChunkRepository,
+    worker::status::ChunkDownloadStatus,
+};
+
+use super::*;
+
+/// A segment is never split into halves smaller than this.
+const MIN_SPLIT_BYTES: i64 = 1024 * 1024;
+
+/// Byte window a chunk stream is still responsible for, `(cursor, end)` both inclusive.
+#[derive(Debug)]
+pub struct Segment {
+    bounds: StdMutex<(i64, i64)>,
+    /// Held while a split is being stored, the stream can't finish until it is
+    /// kept or handed back.
+    splitting: AsyncMutex<()>,
+}
+
+impl Segment {
+    fn new(cursor: i64, end_byte: i64) -> Self {
+        Self {
+            bounds: StdMutex::new((cursor, end_byte)),
+            splitting: AsyncMutex::new(()),
+        }
+    }
+
+    pub fn bounds(&self) -> (i64, i64) {
+        *self.bounds.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn is_done(&self) -> bool {
+        let (cursor, end_byte) = self.bounds();
+        cursor > end_byte
+    }
+
+    /// Whether the stream is through with the segment, once no split is pending.
+    pub async fn is_finished(&self) -> bool {
+        if !self.is_done() {
+            return false;
+        }
+
+        let _splitting = self.splitting.lock().await;
+        self.is_done()
+    }
+
+    pub(super) fn remaining(&self) -> i64 {
+        let (cursor, end_byte) = self.bounds();
+        (end_byte.saturating_add(1) - cursor).max(0)
+    }
+
+    fn reset_cursor(&self, cursor: i64) {
+        self.bounds.lock().unwrap_or_else(|e| e.into_inner()).0 = cursor;
+    }
+
+    /// Like `advance`, but a full segment first waits for a pending split, which
+    /// may still hand its range back.
+    pub async fn claim(&self, bytes: &mut Bytes) -> Option<i64> {
+        if let Some(offset) = self.advance(bytes) {
+            return Some(offset);
+        }
+
+        let _splitting = self.splitting.lock().await;
+        self.advance(bytes)
+    }
+
+    /// Claims as much of `bytes` as still fits in the segment, trims the rest
+    /// and returns the file offset to write at, or `None` when nothing fits.
+    fn advance(&self, bytes: &mut Bytes) -> Option<i64> {
+        let mut bounds = self.bounds.lock().unwrap_or_else(|e| e.into_inner());
+        let allowed = (bounds.1.saturating_add(1) - bounds.0).max(0) as usize;
+
+        if allowed == 0 {
+            return None;
+        }
+
+        bytes.truncate(allowed);
+
+        let offset = bounds.0;
+        bounds.0 += bytes.len() as i64;
+
+        Some(offset)
+    }
+
+    /// Shrinks the segment to its first half and returns the range given away.
+    fn split(&self) -> Option<(i64, i64)> {
+        let mut bounds = self.bounds.lock().unwrap_or_else(|e| e.into_inner());
+        let remaining = bounds.1.saturating_add(1) - bounds.0;
+
+        if remaining < MIN_SPLIT_BYTES * 2 {
+            return None;
+        }
+
+        let start_byte = bounds.0 + remaining / 2;
+        let end_byte = bounds.1;
+        bounds.1 = start_byte - 1;
+
+        Some((start_byte, end_byte))
+    }
+
+    /// Gives back the range taken by `split`. The stream is held off finishing while
+    /// the split is pending, so it is still there to download it.
+    fn unsplit(&self, end_byte: i64) {
+        self.bounds.lock().unwrap_or_else(|e| e.into_inner()).1 = end_byte;
+    }
+}
+
+impl DownloadWorker {
+    pub(super) fn register_segment(
+        &self,
+        chunk: &DownloadChunk,
+        downloaded_bytes: i64,
+    ) -> Arc<Segment> {
+        let cursor = chunk.start_byte + downloaded_bytes;
+        // An open ended chunk (unknown size) runs until the server closes the stream.
+        let end_byte = match chunk.end_byte {
+            end_byte if end_byte < 0 => i64::MAX,
+            end_byte => end_byte,
+        };
+
+        // A retried chunk keeps its segment so an earlier split still bounds it.
+        let segment = self
+            .segments
+            .entry(chunk.chunk_index)
+            .or_insert_with(|| Arc::new(Segment::new(cursor, end_byte)))
+            .clone();
+
+        segment.reset_cursor(cursor);
+        segment
+    }
+
+    /// How far the chunk's stream has got, `None` before its first attempt.
+    pub(super) fn segment_cursor(&self, chunk_index: i64) -> Option<i64> {
+        self.segments
+            .get(&chunk_index)
+            .map(|segment| segment.bounds().0)
+    }
+
+    /// Splits the segment with the most bytes left in half and starts a new stream
+    /// on its second half, so a download doesn't finish at the speed of its slowest chunk.
+    pub(super) async fn steal_segment(self: &Arc<Self>) -> bool {
+        if !self.data.read().await.download.supports_range {
+            return false;
+        }
+
+        let _split_lock = self.split_lock.lock().await;
+
+        let victim = self
+            .segments
+            .iter()
+            .map(|s| (*s.key(), Arc::clone(s.value())))
+            .max_by_key(|(_, segment)| segment.remaining());
+
+        let Some((victim_index, victim)) = victim else {
+            return false;
+        };
+
+        if victim.remaining() < MIN_SPLIT_BYTES * 2 {
+            return false;
+        }
+
+        let chunk_index = match ChunkRepository::next_index(self.download_id).await {
+            Ok(index) => index,
+            Err(err) => {
+                Emitter::emit_error(err.to_string());
+                return false;
+            }
+        };
+
+        let splitting = victim.splitting.lock().await;
+
+        let Some((start_byte, end_byte)) = victim.split() else {
+            return false;
+        };
+
+        // Without both rows the stolen range would be a gap on resume.
+        if let Err(err) = ChunkRepository::split(
+            self.download_id,
+            victim_index,
+            chunk_index,
+            (start_byte, end_byte),
+        )
+        .await
+        {
+            victim.unsplit(end_byte);
+            Emitter::emit_error(err.to_string());
+            return false;
+        }
+
+        drop(splitting);
+
+        let new_chunk = DownloadChunk {
+            download_id: self.download_id,
+            chunk_index,
+            start_byte,
+            end_byte,
+            downloaded_bytes: 0,
+            block_hashes: None,
+        };
+
+        {
+            let mut worker = self.data.write().await;
+            if let Some(chunk) = worker
+                .chunks
+                .iter_mut()
+                .find(|c| c.chunk_index == victim_index)
+            {
+                chunk.end_byte = start_byte - 1;
+            }
+            worker.chunks.push(new_chunk.clone());
+        }
+
+        self.report
+            .chunks_wrote_bytes
+            .insert(chunk_index, AtomicU64::new(0));
+        self.report.integrity.insert(
+            chunk_index,
+            Arc::new(StdMutex::new(ChunkIntegrity::new(start_byte as u64))),
+        );
+        self.segments
+            .insert(chunk_index, Arc::new(Segment::new(start_byte, end_byte)));
+
+        self.update_chunk_status(chunk_index, ChunkDownloadStatus::Downloading)
+            .await;
+        self.spawn_chunk(new_chunk);
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use tokio::time::sleep;
+    use tokio_util::bytes::Bytes;
+
+    use super::{Segment, MIN_SPLIT_BYTES};
+
+    #[tokio::test]
+    async fn a_split_handed_back_is_still_downloaded() {
+        let end_byte = MIN_SPLIT_BYTES * 4 - 1;
+        let segment = Arc::new(Segment::new(0, end_byte));
+
+        let splitting = segment.splitting.lock().await;
+        let (start_byte, _) = segment.split().unwrap();
+        segment.reset_cursor(start_byte);
+
+        let stream = Arc::clone(&segment);
+        let finished = tokio::spawn(async move { stream.is_finished().await });
+        let claimed = {
+            let stream = Arc::clone(&segment);
+            tokio::spawn(async move { stream.claim(&mut Bytes::from_static(b"next")).await })
+        };
+
+        // The stream reached the shrunk end but may not finish while the split is pending.
+        sleep(Duration::from_millis(50)).await;
+        assert!(!finished.is_finished() && !claimed.is_finished());
+
+        segment.unsplit(end_byte);
+        drop(splitting);
+
+        assert!(!finished.await.unwrap());
+        assert_eq!(claimed.await.unwrap(), Some(start_byte));
+    }
+}
@@ -1,29 +1,45 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt, time::Duration};
 
-use crate::{client::ClientError, dispatch};
+use crate::{client::ClientError, dispatch, emitter::Emitter};
 
 use super::*;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum DownloadStatus {
+    Queued,
     Paused,
     Completed,
     Failed,
     Downloading,
     Trying,
+    Waiting,
+    RemoteChanged,
+    UrlExpired,
+    Verifying,
+    Verified,
+    ChecksumMismatch,
     Unknown,
 }
 
-impl ToString for DownloadStatus {
-    fn to_string(&self) -> String {
-        match self {
-            DownloadStatus::Paused => "paused".to_string(),
-            DownloadStatus::Completed => "completed".to_string(),
-            DownloadStatus::Failed => "failed".to_string(),
-            DownloadStatus::Downloading => "downloading".to_string(),
-            DownloadStatus::Trying => "trying".to_string(),
-            DownloadStatus::Unknown => "unknown".to_string(),
-        }
+impl fmt::Display for DownloadStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            DownloadStatus::Queued => "queued",
+            DownloadStatus::Paused => "paused",
+            DownloadStatus::Completed => "completed",
+            DownloadStatus::Failed => "failed",
+            DownloadStatus::Downloading => "downloading",
+            DownloadStatus::Trying => "trying",
+            DownloadStatus::Waiting => "waiting",
+            DownloadStatus::RemoteChanged => "remote_changed",
+            DownloadStatus::UrlExpired => "url_expired",
+            DownloadStatus::Verifying => "verifying",
+            DownloadStatus::Verified => "verified",
+            DownloadStatus::ChecksumMismatch => "checksum_mismatch",
+            DownloadStatus::Unknown => "unknown",
+        };
+
+        f.write_str(status)
     }
 }
 
@@ -32,7 +48,9 @@ pub enum ChunkDownloadStatus {
     Paused,
     Finished,
     Downloading,
+    Parked,
     Trying(ClientError),
+    Waiting(ClientError, Duration),
     Errored(ClientError),
 }
 
@@ -50,11 +68,16 @@ impl DownloadWorker {
 
         let (st, msg) = self.calculate_worker_status(&status_snapshot).await;
 
-        if (*last_status_lock != st || st == DownloadStatus::Trying)
+        if (*last_status_lock != st
+            || st == DownloadStatus::Trying
+            || st == DownloadStatus::Waiting)
             && st != DownloadStatus::Unknown
         {
             *last_status_lock = st.clone();
-            dispatch!(manager, UpdateDownloadStatus, (st, msg, self.download_id));
+            if let Err(err) = dispatch!(manager, UpdateDownloadStatus, (st, msg, self.download_id))
+            {
+                Emitter::emit_error(err.to_string());
+            }
         }
     }
 
@@ -63,6 +86,12 @@ impl DownloadWorker {
         self.update_worker_status().await;
     }
 
+    /// Drops a stream runner that ran out of segments, the others decide the status.
+    pub(super) async fn remove_chunk_status(&self, index: i64) {
+        self.chunks_status.remove(&index);
+        self.update_worker_status().await;
+    }
+
     async fn calculate_worker_status(
         &self,
         statuses: &[ChunkDownloadStatus],
@@ -70,15 +99,20 @@ impl DownloadWorker {
         use ChunkDownloadStatus::*;
 
         let mut has_errored = false;
+        let mut remote_changed = false;
+        let mut url_expired = false;
         let mut has_trying = false;
+        let mut has_waiting = false;
         let mut all_downloading = true;
         let mut all_paused = true;
         let mut all_finished = true;
 
         for status in statuses {
             match status {
-                Errored(_) => {
+                Errored(err) => {
                     has_errored = true;
+                    remote_changed |= matches!(err, ClientError::RemoteChanged);
+                    url_expired |= err.is_url_expired();
                     all_downloading = false;
                     all_paused = false;
                     all_finished = false;
@@ -89,6 +123,12 @@ impl DownloadWorker {
                     all_paused = false;
                     all_finished = false;
                 }
+                Waiting(_, _) => {
+                    has_waiting = true;
+                    all_downloading = false;
+                    all_paused = false;
+                    all_finished = false;
+                }
                 Downloading => {
                     all_paused = false;
                     all_finished = false;
@@ -97,6 +137,11 @@ impl DownloadWorker {
                     all_downloading = false;
                     all_finished = false;
                 }
+                // A parked chunk follows whatever the running chunks are doing,
+                // it only keeps the download from looking completed.
+                Parked => {
+                    all_finished = false;
+                }
                 Finished => {
                     all_downloading = false;
                     all_paused = false;
@@ -107,21 +152,30 @@ impl DownloadWorker {
         match (
             has_errored,
             has_trying,
+            has_waiting,
             all_downloading,
             all_paused,
             all_finished,
         ) {
-            (true, _, _, _, _) => {
+            (true, _, _, _, _, _) => {
                 let msg = self.generate_error_message(statuses);
-                (DownloadStatus::Failed, msg)
+                match (remote_changed, url_expired) {
+                    (true, _) => (DownloadStatus::RemoteChanged, msg),
+                    (false, true) => (DownloadStatus::UrlExpired, msg),
+                    (false, false) => (DownloadStatus::Failed, msg),
+                }
             }
-            (_, true, _, _, _) => {
+            (_, true, _, _, _, _) => {
                 let msg = self.generate_error_message(statuses);
                 (DownloadStatus::Trying, msg)
             }
-            (_, _, true, _, _) => (DownloadStatus::Downloading, None),
-            (_, _, _, true, _) => (DownloadStatus::Paused, None),
-            (_, _, _, _, true) => (DownloadStatus::Completed, None),
+            (_, _, true, _, _, _) => {
+                let msg = self.generate_error_message(statuses);
+                (DownloadStatus::Waiting, msg)
+            }
+            (_, _, _, true, _, _) => (DownloadStatus::Downloading, None),
+            (_, _, _, _, true, _) => (DownloadStatus::Paused, None),
+            (_, _, _, _, _, true) => (DownloadStatus::Completed, None),
             _ => (DownloadStatus::Unknown, None),
         }
     }
@@ -140,6 +194,13 @@ impl DownloadWorker {
                     .and_modify(|count| *count += 1)
                     .or_insert(1);
             }
+            Waiting(err, delay) => {
+                let secs = delay.as_secs_f64().ceil() as u64;
+                errors
+                    .entry(format!("{} seconds ({})", secs, err))
+                    .and_modify(|count| *count += 1)
+                    .or_insert(1);
+            }
             _ => {}
         });
 
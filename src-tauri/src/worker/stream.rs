@@ -0,0 +1,424 @@
+use futures_util::StreamExt;
+use std::{
+    collections::HashMap,
+    future::pending,
+    io,
+    path::Path,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+use tokio::{
+    fs,
+    io::{AsyncWriteExt, BufWriter},
+    select,
+    time::{sleep, timeout},
+};
+
+use crate::{
+    client::ClientError,
+    dispatch,
+    emitter::Emitter,
+    file::{DiskError, File},
+    models::{DownloadSegment, RetryPolicy, RetryState, UpdateDownload},
+    repository::{download::DownloadRepository, segment::SegmentRepository},
+    spawn,
+    worker::{
+        bandwidth::{StreamGuard, TokenBucket},
+        status::ChunkDownloadStatus,
+    },
+};
+
+use super::*;
+
+/// Segments of one stream shared by its runners.
+#[derive(Debug)]
+struct SegmentQueue {
+    pending: Mutex<VecDeque<DownloadSegment>>,
+    /// AES-128 keys by URL, most streams use a handful for every segment.
+    keys: Mutex<HashMap<String, [u8; 16]>>,
+    segment_count: i64,
+    completed: AtomicUsize,
+    runners: AtomicUsize,
+}
+
+impl DownloadWorker {
+    /// Runs `chunk_count` runners that take segments off a shared queue. A runner
+    /// reports through `chunks_status` the way a chunk does, so pausing, retries and
+    /// the download status behave as they do for files.
+    pub(super) async fn start_stream(self: &Arc<Self>) {
+        let chunk_count = self.data.read().await.download.chunk_count;
+
+        let segments = match SegmentRepository::find_all(self.download_id).await {
+            Ok(segments) => segments,
+            Err(err) => {
+                if let Err(err) = dispatch!(
+                    manager,
+                    UpdateDownloadStatus,
+                    (
+                        DownloadStatus::Failed,
+                        Some(err.to_string()),
+                        self.download_id
+                    )
+                ) {
+                    Emitter::emit_error(err.to_string());
+                }
+                return;
+            }
+        };
+
+        let segment_count = segments.len() as i64;
+        let completed = segments.iter().filter(|s| s.completed).count();
+        let pending = segments
+            .into_iter()
+            .filter(|segment| !segment.completed)
+            .collect::<VecDeque<_>>();
+
+        // With every segment done one runner is still needed to join them.
+        let runners = (chunk_count.max(1) as usize).min(pending.len().max(1));
+
+        let queue = Arc::new(SegmentQueue {
+            pending: Mutex::new(pending),
+            keys: Mutex::new(HashMap::new()),
+            segment_count,
+            completed: AtomicUsize::new(completed),
+            runners: AtomicUsize::new(runners),
+        });
+
+        for runner in 0..runners {
+            self.spawn_segment_runner(runner as i64, Arc::clone(&queue));
+        }
+    }
+
+    fn spawn_segment_runner(self: &Arc<Self>, runner: i64, queue: Arc<SegmentQueue>) {
+        use ChunkDownloadStatus::*;
+
+        let cancelable_sleep = async move |d: Duration, c: Arc<CancellationToken>| {
+            select! {
+                _ = sleep(d) => true,
+                _ = c.cancelled() => false,
+            }
+        };
+
+        let worker_clone = Arc::clone(self);
+
+        spawn!("download_segment", {
+            let retry_settings = Registry::get_state().retry_settings.read().await.clone();
+            let (cancel_token, policy) = {
+                let worker = worker_clone.data.read().await;
+                (
+                    Arc::clone(&worker.cancel_token),
+                    RetryPolicy::new(&worker.download, &retry_settings),
+                )
+            };
+
+            let mut retry = RetryState::default();
+            let mut segment = queue.pending.lock().await.pop_front();
+
+            let set = async |st| worker_clone.update_chunk_status(runner, st).await;
+
+            while let Some(current) = segment.as_ref() {
+                set(Downloading).await;
+
+                let st = select! {
+                    result = worker_clone.download_segment(current, &queue) => {
+                        match result {
+                            Ok(()) => Finished,
+                            Err(err) if err.is_retryable() => Trying(err),
+                            Err(err) => Errored(err)
+                        }
+                    },
+                    _ = cancel_token.cancelled() => Paused,
+                };
+
+                match st {
+                    Finished => {
+                        queue.completed.fetch_add(1, Ordering::SeqCst);
+                        retry.reset();
+                        segment = queue.pending.lock().await.pop_front();
+                    }
+                    Trying(err) => {
+                        if cancel_token.is_cancelled() {
+                            set(Paused).await;
+                            break;
+                        }
+
+                        let server_delay = err.retry_after();
+
+                        if let Some(attempt) = retry.next_attempt(&policy, server_delay) {
+                            let delay = match server_delay {
+                                Some(delay) => {
+                                    set(Waiting(err.clone(), delay)).await;
+                                    delay
+                                }
+                                None => {
+                                    set(Trying(err.clone())).await;
+                                    policy.delay(attempt)
+                                }
+                            };
+
+                            if !cancelable_sleep(delay, Arc::clone(&cancel_token)).await {
+                                set(Paused).await;
+                                break;
+                            }
+                        } else {
+                            set(Errored(err)).await;
+                            cancel_token.cancel();
+                            break;
+                        }
+                    }
+                    Errored(err) => {
+                        set(Errored(err)).await;
+                        cancel_token.cancel();
+                        break;
+                    }
+                    _ => {
+                        set(st).await;
+                        break;
+                    }
+                }
+            }
+
+            let last_runner = queue.runners.fetch_sub(1, Ordering::SeqCst) == 1;
+
+            // Stopped on a segment, its status is already reported.
+            if segment.is_some() {
+                return;
+            }
+
+            // Out of segments while others still run, the download follows them.
+            if !last_runner {
+                worker_clone.remove_chunk_status(runner).await;
+                return;
+            }
+
+            let all_completed =
+                queue.completed.load(Ordering::SeqCst) as i64 == queue.segment_count;
+
+            if !all_completed || cancel_token.is_cancelled() {
+                set(Paused).await;
+                return;
+            }
+
+            match worker_clone.join_segments(queue.segment_count).await {
+                Ok(()) => set(Finished).await,
+                Err(err) => {
+                    if let Err(err) =
+                        dispatch!(manager, WriteFailed, (worker_clone.download_id, err))
+                    {
+                        Emitter::emit_error(err.to_string());
+                    }
+                    set(Paused).await;
+                }
+            }
+        });
+    }
+
+    /// Fetches a segment into its own file, decrypting it once complete. A segment
+    /// cut short is fetched again from its first byte.
+    async fn download_segment(
+        self: &Arc<Self>,
+        segment: &DownloadSegment,
+        queue: &SegmentQueue,
+    ) -> Result<(), ClientError> {
+        let mut received = 0;
+        let result = self.fetch_segment(segment, queue, &mut received).await;
+
+        // The retry counts these bytes again, take them back out of the progress.
+        if result.is_err() {
+            if let Err(err) = dispatch!(registry, RewindNetworkReport, (self.download_id, received))
+            {
+                Emitter::emit_error(err.to_string());
+            }
+        }
+
+        result
+    }
+
+    async fn fetch_segment(
+        self: &Arc<Self>,
+        segment: &DownloadSegment,
+        queue: &SegmentQueue,
+        received: &mut u64,
+    ) -> Result<(), ClientError> {
+        let (url, file_path, timeout_secs) = {
+            let w = self.data.read().await;
+            (
+                w.download.url.clone(),
+                w.download.file_path.clone(),
+                w.download.timeout_secs,
+            )
+        };
+
+        // Every segment goes through the playlist's client and its connections.
+        let client = self.client(&url).await?;
+        let range = segment.range_start.zip(segment.range_end);
+
+        let _host_slot = Self::host_slots(&segment.url).await.acquire().await;
+        let mut stream = client.stream_segment(&segment.url, range).await?;
+
+        let path = File::segment_path(&file_path, segment.segment_index);
+        let mut file = match Self::create_segment_file(&path).await {
+            Ok(file) => BufWriter::new(file),
+            Err(err) => return self.segment_write_failed(err).await,
+        };
+
+        let _stream_guard = StreamGuard::new(&[&self.active_streams]);
+        let chunk_bucket = TokenBucket::new(0);
+
+        loop {
+            match timeout(Duration::from_secs(timeout_secs as u64), stream.next()).await {
+                Ok(Some(Ok(bytes))) => {
+                    let bytes_len = bytes.len() as u64;
+
+                    if let Err(err) = file.write_all(&bytes).await {
+                        return self.segment_write_failed(err).await;
+                    }
+
+                    *received += bytes_len;
+
+                    self.limiter(&chunk_bucket, bytes_len).await;
+
+                    if let Err(err) =
+                        dispatch!(registry, UpdateNetworkReport, (self.download_id, bytes_len))
+                    {
+                        Emitter::emit_error(err.to_string());
+                    }
+                }
+                Ok(Some(Err(err))) => return Err(err),
+                Ok(None) => break,
+                Err(_) => return Err(ClientError::StreamTimeout),
+            }
+        }
+
+        if range.is_some_and(|(start, end)| *received < (end - start + 1) as u64) {
+            return Err(ClientError::UnexpectedEof);
+        }
+
+        let flushed = match file.flush().await {
+            Ok(()) => file.get_ref().sync_data().await,
+            Err(err) => Err(err),
+        };
+
+        if let Err(err) = flushed {
+            return self.segment_write_failed(err).await;
+        }
+
+        drop(file);
+
+        let size = match (&segment.key_url, &segment.iv) {
+            (Some(key_url), Some(iv)) => {
+                let key = Self::segment_key(&client, key_url, queue).await?;
+                let iv = hex::decode(iv)
+                    .ok()
+                    .and_then(|iv| iv.try_into().ok())
+                    .ok_or_else(|| ClientError::InvalidPlaylist(format!("bad IV {}", iv)))?;
+
+                let mut data = match fs::read(&path).await {
+                    Ok(data) => data,
+                    Err(err) => return self.segment_write_failed(err).await,
+                };
+
+                // A key that got garbled on the way is fetched again with the segment.
+                if let Err(err) = Client::decrypt_segment(&key, &iv, &mut data) {
+                    queue.keys.lock().await.remove(key_url);
+                    return Err(err);
+                }
+
+                if let Err(err) = fs::write(&path, &data).await {
+                    return self.segment_write_failed(err).await;
+                }
+
+                data.len() as u64
+            }
+            _ => *received,
+        };
+
+        // Without the record the segment is fetched again on resume, nothing worse.
+        if let Err(err) =
+            SegmentRepository::complete(self.download_id, segment.segment_index, size as i64).await
+        {
+            Emitter::emit_error(err.to_string());
+        }
+
+        if let Err(err) = dispatch!(
+            registry,
+            UpdateDiskReport,
+            (self.download_id, segment.segment_index, size)
+        ) {
+            Emitter::emit_error(err.to_string());
+        }
+
+        Ok(())
+    }
+
+    async fn segment_key(
+        client: &Client,
+        url: &str,
+        queue: &SegmentQueue,
+    ) -> Result<[u8; 16], ClientError> {
+        // Held across the request so runners don't all fetch the same key.
+        let mut keys = queue.keys.lock().await;
+
+        if let Some(key) = keys.get(url) {
+            return Ok(*key);
+        }
+
+        let key = client.segment_key(url).await?;
+        keys.insert(url.to_string(), key);
+
+        Ok(key)
+    }
+
+    async fn create_segment_file(path: &Path) -> io::Result<fs::File> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).await?;
+        }
+
+        fs::File::create(path).await
+    }
+
+    /// Same as a failed chunk write, the download pauses with the reason.
+    async fn segment_write_failed(&self, err: io::Error) -> Result<(), ClientError> {
+        if let Err(err) = dispatch!(
+            manager,
+            WriteFailed,
+            (self.download_id, DiskError::from(err))
+        ) {
+            Emitter::emit_error(err.to_string());
+        }
+
+        // The pause cancels this segment, let the cancel win.
+        pending().await
+    }
+
+    /// Joins the finished segments into the part file and records the size we ended up with.
+    async fn join_segments(&self, segment_count: i64) -> Result<(), DiskError> {
+        let file_path = self.data.read().await.download.file_path.clone();
+        let total_bytes = File::join_segments(&file_path, segment_count).await?;
+
+        let updated = DownloadRepository::update(
+            self.download_id,
+            UpdateDownload {
+                total_bytes: Some(total_bytes as i64),
+                status: None,
+                error_message: None,
+                auth: None,
+                backoff_factor: None,
+                cookies: None,
+                delay_secs: None,
+                headers: None,
+                max_retries: None,
+                proxy: None,
+                speed_limit: None,
+                timeout_secs: None,
+            },
+        )
+        .await;
+
+        if let Err(err) = updated {
+            Emitter::emit_error(err.to_string());
+        }
+
+        Ok(())
+    }
+}
@@ -1,25 +1,80 @@
-use anyhow::anyhow;
+use std::sync::atomic::{AtomicU64, Ordering};
 
-use crate::manager::DownloadsManager;
+use crate::{
+    emitter::Emitter,
+    file::{ChunkIntegrity, File},
+    models::UpdateChunk,
+    repository::chunk::ChunkRepository,
+};
 
 use super::*;
 
 impl DownloadWorker {
-    pub(super) async fn validate_chunk(
-        self: &Arc<Self>,
-        current_hash: Option<String>,
-        chunk_index: i64,
-    ) -> anyhow::Result<()> {
-        if let Some(current_hash) = current_hash {
-            let hash = DownloadsManager::hash_chunk(self.download_id, chunk_index).await?;
-
-            if hash.to_string() == current_hash {
-                return Ok(());
+    /// Reads back what a resumed chunk wrote in an earlier run and rewinds it to the
+    /// first block that no longer matches its stored hash. Runs once per chunk.
+    pub(super) async fn validate_chunk(self: &Arc<Self>, chunk: &DownloadChunk) {
+        let Some(integrity) = self
+            .report
+            .integrity
+            .get(&chunk.chunk_index)
+            .map(|i| Arc::clone(&i))
+        else {
+            return;
+        };
+
+        let pending = {
+            let integrity = integrity.lock().unwrap_or_else(|e| e.into_inner());
+            if !integrity.needs_check() {
+                return;
             }
+            integrity.clone()
+        };
+
+        let file_path = self.data.read().await.download.file_path.clone();
+        let settings = Registry::get_state().disk_settings.read().await.clone();
+
+        let claimed = pending.hashed_bytes();
+        // A part file we can't read can't vouch for anything, the chunk starts over.
+        let checked = match File::verify_chunk(&file_path, pending, &settings).await {
+            Ok(checked) => checked,
+            Err(err) => {
+                Emitter::emit_error(err.to_string());
+                ChunkIntegrity::new(chunk.start_byte as u64)
+            }
+        };
+
+        let verified = checked.hashed_bytes();
+        let block_hashes = checked.snapshot();
+        *integrity.lock().unwrap_or_else(|e| e.into_inner()) = checked;
 
-            return Err(anyhow!("hash not match"));
+        if verified == claimed {
+            return;
         }
 
-        Ok(())
+        let lost = claimed - verified;
+        let saturating_fetch_sub = |atom: &AtomicU64, amount: u64| {
+            let _ = atom.fetch_update(Ordering::AcqRel, Ordering::Acquire, |cur| {
+                Some(cur.saturating_sub(amount))
+            });
+        };
+
+        if let Some(wrote_bytes) = self.report.chunks_wrote_bytes.get(&chunk.chunk_index) {
+            wrote_bytes.store(verified, Ordering::Release);
+        }
+        saturating_fetch_sub(&self.report.total_downloaded_bytes, lost);
+        saturating_fetch_sub(&self.report.total_wrote_bytes, lost);
+
+        if let Err(err) = ChunkRepository::update(
+            self.download_id,
+            UpdateChunk {
+                chunk_index: chunk.chunk_index,
+                downloaded_bytes: verified as i64,
+                block_hashes,
+            },
+        )
+        .await
+        {
+            Emitter::emit_error(err.to_string());
+        }
     }
 }